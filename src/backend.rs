@@ -0,0 +1,58 @@
+use math::mat::mat4::*;
+use camera::*;
+use error::*;
+use stats::*;
+use texture::*;
+use objects::mesh::*;
+use objects::indexed_mesh::*;
+use objects::instance::*;
+use objects::skin::*;
+use objects::scene::*;
+
+/*
+	Common interface of every render implementation. The rasterizer is the only
+	backend today, but callers should talk to this trait instead of the Renderer fields.
+*/
+pub trait RenderBackend {
+	fn render_mesh(&mut self, mesh: &Mesh) -> RenderResult<()>;
+	
+	//model places the mesh in the world, the vertices are not modified
+	fn render_mesh_transformed(&mut self, mesh: &Mesh, model: &Mat4) -> RenderResult<()>;
+	
+	//draws the mesh once per instance with its transform and tint, the vertices are shared
+	fn render_mesh_instances(&mut self, mesh: &Mesh, instances: &[Instance]) -> RenderResult<()>;
+	
+	fn render_indexed_mesh(&mut self, mesh: &IndexedMesh) -> RenderResult<()>;
+	
+	fn render_indexed_mesh_transformed(&mut self, mesh: &IndexedMesh, model: &Mat4) -> RenderResult<()>;
+	
+	//the pose moves the weighted vertices into the world, the model matrix of the mesh is not used
+	fn render_mesh_skinned(&mut self, mesh: &Mesh, pose: &SkinPose) -> RenderResult<()>;
+	
+	fn render_indexed_mesh_skinned(&mut self, mesh: &IndexedMesh, pose: &SkinPose) -> RenderResult<()>;
+	
+	//returns the statistics of the frame rendered since the last clear_frame
+	fn render_scene(&mut self, scene: &Scene) -> RenderResult<RenderStats>;
+	
+	fn clear_frame(&mut self);
+	
+	//reallocates all buffers, the content of the frame is lost afterwards, a camera with fit_aspect follows the new size
+	fn resize(&mut self, img_width: i32, img_height: i32);
+	
+	fn img_width(&self) -> i32;
+	
+	fn img_height(&self) -> i32;
+	
+	//a camera with fit_aspect gets the aspect of the image
+	fn set_camera(&mut self, camera: Camera);
+	
+	fn camera(&self) -> &Camera;
+	
+	fn camera_mut(&mut self) -> &mut Camera;
+	
+	//resolved color of the current frame, one color per image pixel
+	fn read_frame(&self) -> Texture;
+	
+	//resolved depth of the current frame, f32::MAX where nothing was drawn
+	fn read_depth(&self) -> Vec<f32>;
+}
@@ -1,8 +1,18 @@
 use math::vec::vec3::*;
 use math::mat::mat4::*;
 use math::mat::mat3::*;
+use quaternion::*;
 use std::f32::consts::PI;
 
+//projection set by the to_projection functions, look_at and set_aspect keep it
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ProjectionKind {
+	None, //Camera::new and new_init, no projection yet
+	Orthographic, //to_projection_ortho and to_projection_ortho2
+	Perspective, //to_projection_perspective and to_projection_perspective_ with a fixed field of view
+	Frustum, //to_projection_frustum
+}
+
 pub struct Camera {
 	pub view: Mat4,
 	pub projection: Mat4,
@@ -18,6 +28,9 @@ pub struct Camera {
 	pub b: f32,
 	pub n: f32,
 	pub f: f32,
+	pub world_up: Vec3, //look_at keeps up of the view close to it, must not be parallel to the view direction
+	pub fit_aspect: bool, //the renderer widens l and r to the aspect of its image, see set_aspect
+	pub projection_kind: ProjectionKind,
 }
 
 impl Camera {
@@ -31,6 +44,9 @@ impl Camera {
 				 from: 		    Vec3::new_empty(),
 				 to:            Vec3::new_empty(),
 				 l: 0.0, r: 0.0, t: 0.0, b: 0.0, n: 0.0, f: 0.0,
+				 world_up:      Vec3::new(0.0, 1.0, 0.0),
+				 fit_aspect:    false,
+				 projection_kind: ProjectionKind::None,
 		}
 	}
 
@@ -44,7 +60,55 @@ impl Camera {
 				 from: 		    from.clone(),
 				 to:            to.clone(),
 				 l, r, t, b, n, f,
+				 world_up:      Vec3::new(0.0, 1.0, 0.0),
+				 fit_aspect:    false,
+				 projection_kind: ProjectionKind::None,
+		}
+	}
+	
+	/*
+		perspective camera with a vertical field of view in degrees and aspect = width / height. Unlike to_perspective
+		the projection uses the frustum bounds, the Renderer refits the aspect to its image size
+	*/
+	pub fn perspective_fov(from: &Vec3, to: &Vec3, up: &Vec3, fov_y: f32, aspect: f32, n: f32, f: f32) -> Camera {
+		let t: f32 = n * (fov_y * 0.5 * PI / 180.0).tan();
+		let mut camera: Camera = Camera::new_init(from, to, -t * aspect, t * aspect, t, -t, n, f);
+		camera.world_up.set_from(up);
+		camera.fit_aspect = true;
+		camera.look_at_perspective(from, to);
+		camera.to_projection_frustum();
+		camera.transformation.set_from(&camera.projection);
+		camera.transformation *= &camera.view;
+		camera
+	}
+	
+	//orthographic camera showing height world units from bottom to top, the width follows the aspect
+	pub fn orthographic_size(from: &Vec3, to: &Vec3, up: &Vec3, height: f32, aspect: f32, n: f32, f: f32) -> Camera {
+		let t: f32 = height * 0.5;
+		let mut camera: Camera = Camera::new_init(from, to, -t * aspect, t * aspect, t, -t, n, f);
+		camera.world_up.set_from(up);
+		camera.fit_aspect = true;
+		camera.look_at_ortho(from, to);
+		camera.to_projection_ortho();
+		camera.transformation.set_from(&camera.projection);
+		camera.transformation *= &camera.view;
+		camera
+	}
+	
+	//changes l and r to aspect = width / height around their center, t and b stay
+	pub fn set_aspect(&mut self, aspect: f32) {
+		let center: f32 = (self.r + self.l) * 0.5;
+		let half: f32 = (self.t - self.b).abs() * 0.5 * aspect;
+		self.l = center - half;
+		self.r = center + half;
+		match self.projection_kind {
+			ProjectionKind::Orthographic => self.to_projection_ortho(),
+			ProjectionKind::Perspective => self.to_projection_perspective(),
+			ProjectionKind::Frustum => self.to_projection_frustum(),
+			ProjectionKind::None => {},
 		}
+		self.transformation.set_from(&self.projection);
+		self.transformation *= &self.view;
 	}
 	
 	pub fn set_viewport(&mut self, l: f32, r: f32, t: f32, b: f32, n: f32, f: f32) {
@@ -62,7 +126,7 @@ impl Camera {
 		self.forward -= to;
 		self.forward.normalize();
 		
-		let mut tmp = Vec3::new(0.0, 1.0, 0.0);
+		let mut tmp = self.world_up.clone();
 		tmp.normalize();
 		
 		//self.left.set_from(&tmp);
@@ -75,6 +139,11 @@ impl Camera {
 		self.forward.cross_to(&self.left, &mut self.up);
 		self.up.normalize();
 		
+		self._view_ortho(from);
+	}
+	
+	//view matrix of the basis left, up, forward at position from
+	fn _view_ortho(&mut self, from: &Vec3) {
 		self.view._11 = self.left.x;
 		self.view._21 = self.left.y;
 		self.view._31 = self.left.z;
@@ -99,6 +168,7 @@ impl Camera {
 	}
 	
 	pub fn to_projection_ortho(&mut self) {
+		self.projection_kind = ProjectionKind::Orthographic;
 		
 		self.projection._11 = 2.0/(self.r-self.l);
 		self.projection._12 = 0.0;
@@ -122,6 +192,7 @@ impl Camera {
 	}
 	
 	pub fn to_projection_ortho2(&mut self) {
+		self.projection_kind = ProjectionKind::Orthographic;
 		self.projection._11 = 1.0/self.r;
 		self.projection._12 = 0.0;
 		self.projection._13 = 0.0;
@@ -162,7 +233,7 @@ impl Camera {
 		self.forward -= to;
 		self.forward.normalize();
 		
-		let mut tmp = Vec3::new(0.0, 1.0, 0.0);
+		let mut tmp = self.world_up.clone();
 		tmp.normalize();
 		
 		tmp.cross_to(&self.forward, &mut self.left);
@@ -171,6 +242,10 @@ impl Camera {
 		self.forward.cross_to(&self.left, &mut self.up);
 		self.up.normalize();
 		
+		self._view_perspective(from);
+	}
+	
+	fn _view_perspective(&mut self, from: &Vec3) {
 		let m: Mat4 = Mat4::new( self.left.x, self.up.x, -self.forward.x, from.x,	
 							     self.left.y, self.up.y, -self.forward.y, from.y,	
 							     self.left.z, self.up.z, -self.forward.z, from.z, 	
@@ -267,6 +342,7 @@ impl Camera {
 	
 	//scratch a pixel projection => right hand Matrix in use like directX
 	pub fn to_projection_perspective_(&mut self) {
+		self.projection_kind = ProjectionKind::Perspective;
 		let scale: f32 = 1.0 / (90.0 * 0.5 * PI / 180.0).tan(); 
 		self.projection._11 = scale;
 		self.projection._12 = 0.0;
@@ -291,6 +367,7 @@ impl Camera {
 	
 	// open GL projection
 	pub fn to_projection_perspective(&mut self) {
+		self.projection_kind = ProjectionKind::Perspective;
 		let scale: f32 = 1.0 as f32 / (90.0 as f32 * 0.5 as f32 * PI / 180.0 as f32).tan(); 
 		self.projection._11 = scale;//(2.0*self.n)/(self.r-self.l);//scale;//
 		self.projection._12 = 0.0;
@@ -313,6 +390,30 @@ impl Camera {
 		self.projection._44 = 0.0;
 	}
 	
+	//like to_projection_perspective, but the scale comes from l, r, t, b at the near plane
+	pub fn to_projection_frustum(&mut self) {
+		self.projection_kind = ProjectionKind::Frustum;
+		self.projection._11 = (2.0*self.n)/(self.r-self.l);
+		self.projection._12 = 0.0;
+		self.projection._13 = -(self.r+self.l)/(self.r-self.l);
+		self.projection._14 = 0.0;
+		
+		self.projection._21 = 0.0;
+		self.projection._22 = (2.0*self.n)/(self.t-self.b);
+		self.projection._23 = -(self.t+self.b)/(self.t-self.b);
+		self.projection._24 = 0.0;
+		
+		self.projection._31 = 0.0;
+		self.projection._32 = 0.0;
+		self.projection._33 = -(self.f+self.n)/(self.f-self.n);
+		self.projection._34 = -(2.0*self.f*self.n)/(self.f-self.n);
+		
+		self.projection._41 = 0.0;
+		self.projection._42 = 0.0;
+		self.projection._43 = 1.0;
+		self.projection._44 = 0.0;
+	}
+	
 	pub fn to_perspective(&mut self, from: &Vec3, to: &Vec3, l: f32, r: f32, t: f32, b: f32, n: f32, f: f32) {
 		self.set_viewport(l,r,t,b,n,f);
 		self.look_at_perspective(from, to);
@@ -321,4 +422,58 @@ impl Camera {
 		self.transformation *= &self.view;
 	}
 	
+	//moves the camera keeping the kind of its projection, view and transformation are updated
+	pub fn look_at(&mut self, from: &Vec3, to: &Vec3) {
+		self.from.set_from(from);
+		self.to.set_from(to);
+		//without a projection the plain view of look_at_ortho is used
+		match self.projection_kind {
+			ProjectionKind::Perspective | ProjectionKind::Frustum => self.look_at_perspective(from, to),
+			ProjectionKind::Orthographic | ProjectionKind::None => self.look_at_ortho(from, to),
+		}
+		self.transformation.set_from(&self.projection);
+		self.transformation *= &self.view;
+	}
+	
+	//rotation of the basis left, up, forward, the identity looks along -z with y up
+	pub fn orientation(&self) -> Quaternion {
+		Quaternion::from_mat3(&Mat3::new(self.left.x, self.up.x, self.forward.x,
+										 self.left.y, self.up.y, self.forward.y,
+										 self.left.z, self.up.z, self.forward.z))
+	}
+	
+	/*
+		the orient functions replace look_at without a fixed world up, so looking straight up or down keeps working.
+		to stays at its distance from the camera, view and transformation are updated with the current projection
+	*/
+	pub fn orient_ortho(&mut self, from: &Vec3, orientation: &Quaternion) {
+		self._orient(from, orientation);
+		self._view_ortho(from);
+		self.transformation.set_from(&self.projection);
+		self.transformation *= &self.view;
+	}
+	
+	pub fn orient_perspective(&mut self, from: &Vec3, orientation: &Quaternion) {
+		self._orient(from, orientation);
+		self._view_perspective(from);
+		self.transformation.set_from(&self.projection);
+		self.transformation *= &self.view;
+	}
+	
+	fn _orient(&mut self, from: &Vec3, orientation: &Quaternion) {
+		let mut distance: Vec3 = self.to.clone();
+		distance -= &self.from;
+		let distance: f32 = if distance.len() > 0.0 { distance.len() } else { 1.0 };
+		
+		self.left.set(1.0, 0.0, 0.0);
+		orientation.rotate(&mut self.left);
+		self.up.set(0.0, 1.0, 0.0);
+		orientation.rotate(&mut self.up);
+		self.forward.set(0.0, 0.0, 1.0);
+		orientation.rotate(&mut self.forward);
+		
+		self.from.set_from(from);
+		self.to.set(from.x - self.forward.x * distance, from.y - self.forward.y * distance, from.z - self.forward.z * distance);
+	}
+	
 }
\ No newline at end of file
@@ -0,0 +1,101 @@
+use std;
+use std::fmt;
+
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+pub enum DiagnosticLevel {
+	Error,
+	Warn,
+	Info,
+	Debug,
+	Trace,
+}
+
+/*
+	Receiver of renderer diagnostics. The renderer asks enabled() before formatting a
+	message, so disabled levels cost nothing in the raster loops.
+*/
+pub trait DiagnosticSink {
+	fn enabled(&self, level: DiagnosticLevel) -> bool;
+	fn log(&self, level: DiagnosticLevel, message: &str);
+}
+
+pub struct StdoutSink {
+	pub max_level: DiagnosticLevel,
+}
+
+pub struct CallbackSink {
+	pub max_level: DiagnosticLevel,
+	pub callback: fn(DiagnosticLevel, &str),
+}
+
+#[derive(Clone)]
+pub struct FrameReport {
+	pub triangles_submitted: u32,
+	pub triangles_clipped: u32,
+	pub triangles_culled: u32,
+	pub samples_shaded: u64,
+	pub min_z: f32,
+	pub max_z: f32,
+}
+
+impl StdoutSink {
+	pub fn new(max_level: DiagnosticLevel) -> StdoutSink {
+		StdoutSink { max_level }
+	}
+}
+
+impl DiagnosticSink for StdoutSink {
+	fn enabled(&self, level: DiagnosticLevel) -> bool {
+		level <= self.max_level
+	}
+	
+	fn log(&self, level: DiagnosticLevel, message: &str) {
+		println!("[{:?}] {}", level, message);
+	}
+}
+
+impl CallbackSink {
+	pub fn new(max_level: DiagnosticLevel, callback: fn(DiagnosticLevel, &str)) -> CallbackSink {
+		CallbackSink { max_level, callback }
+	}
+}
+
+impl DiagnosticSink for CallbackSink {
+	fn enabled(&self, level: DiagnosticLevel) -> bool {
+		level <= self.max_level
+	}
+	
+	fn log(&self, level: DiagnosticLevel, message: &str) {
+		(self.callback)(level, message);
+	}
+}
+
+impl FrameReport {
+	pub fn new() -> FrameReport {
+		FrameReport {
+			triangles_submitted: 0,
+			triangles_clipped: 0,
+			triangles_culled: 0,
+			samples_shaded: 0,
+			min_z: std::f32::MAX,
+			max_z: 0.0,
+		}
+	}
+	
+	pub fn reset(&mut self) {
+		self.triangles_submitted = 0;
+		self.triangles_clipped = 0;
+		self.triangles_culled = 0;
+		self.samples_shaded = 0;
+		self.min_z = std::f32::MAX;
+		self.max_z = 0.0;
+	}
+}
+
+impl fmt::Display for FrameReport {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "triangles submitted:{} clipped:{} culled:{} samples shaded:{} depth:{}..{}",
+				self.triangles_submitted, self.triangles_clipped, self.triangles_culled,
+				self.samples_shaded, self.min_z, self.max_z)
+	}
+}
@@ -0,0 +1,54 @@
+use std::fmt;
+use std::error::Error;
+use std::io;
+
+#[derive(Debug)]
+pub enum RenderError {
+	Io(io::Error),
+	InvalidShape(usize), //count of vertices the shape has
+	InvalidTextureId(i32),
+	InvalidIndex(u32), //index beyond the vertex buffer of an indexed mesh
+	DimensionMismatch { expected_width: u32, expected_height: u32, width: u32, height: u32 },
+	InvalidImage(String),
+	InvalidModel(String),
+	InvalidAnimation(String),
+}
+
+pub type RenderResult<T> = Result<T, RenderError>;
+
+impl RenderError {
+	pub fn dimension_mismatch(expected_width: u32, expected_height: u32, width: u32, height: u32) -> RenderError {
+		RenderError::DimensionMismatch { expected_width, expected_height, width, height }
+	}
+}
+
+impl fmt::Display for RenderError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			RenderError::Io(ref err) => write!(f, "io error: {}", err),
+			RenderError::InvalidShape(cnt) => write!(f, "invalid shape with {} vertices", cnt),
+			RenderError::InvalidTextureId(id) => write!(f, "invalid texture id {}", id),
+			RenderError::InvalidIndex(index) => write!(f, "invalid vertex index {}", index),
+			RenderError::DimensionMismatch { expected_width, expected_height, width, height } =>
+				write!(f, "dimension mismatch: expected {}x{} got {}x{}", expected_width, expected_height, width, height),
+			RenderError::InvalidImage(ref msg) => write!(f, "invalid image: {}", msg),
+			RenderError::InvalidModel(ref msg) => write!(f, "invalid model: {}", msg),
+			RenderError::InvalidAnimation(ref msg) => write!(f, "invalid animation: {}", msg),
+		}
+	}
+}
+
+impl Error for RenderError {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		match *self {
+			RenderError::Io(ref err) => Some(err),
+			_ => None,
+		}
+	}
+}
+
+impl From<io::Error> for RenderError {
+	fn from(err: io::Error) -> RenderError {
+		RenderError::Io(err)
+	}
+}
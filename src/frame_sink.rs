@@ -0,0 +1,88 @@
+use texture::*;
+use texture::format::y4m::*;
+use texture::format::gif::*;
+use error::*;
+use std::fs::File;
+use std::io::{Write, BufWriter};
+use std::path::Path;
+
+/*
+	Receiver of rendered frames, e.g. from Renderer::write_frame_to once per animation step.
+	finish has to be called after the last frame to flush buffered output.
+*/
+pub trait FrameSink {
+	fn write_frame(&mut self, frame: &Texture) -> RenderResult<()>;
+	fn finish(&mut self) -> RenderResult<()>;
+}
+
+//numbered image files, the writer is picked by the extension of the pattern like Texture::write
+pub struct ImageSequenceSink {
+	pub pattern: String, //the last run of # is replaced by the zero padded frame number, e.g. "frames/turntable_####.png"
+	pub next: u32, //number of the next frame
+}
+
+impl ImageSequenceSink {
+	pub fn new(pattern: &str) -> ImageSequenceSink {
+		ImageSequenceSink { pattern: pattern.to_string(), next: 0 }
+	}
+	
+	//a pattern without # gets the number appended before the extension
+	pub fn filename(&self, number: u32) -> String {
+		let pattern: &str = &self.pattern;
+		match pattern.rfind('#') {
+			Some(end) => {
+				let start: usize = pattern[..end].rfind(|c| c != '#').map(|i| i + 1).unwrap_or(0);
+				format!("{}{:0width$}{}", &pattern[..start], number, &pattern[end + 1..], width = end + 1 - start)
+			},
+			None => match pattern.rfind('.') {
+				Some(dot) if !pattern[dot..].contains('/') => format!("{}{}{}", &pattern[..dot], number, &pattern[dot..]),
+				_ => format!("{}{}", pattern, number),
+			},
+		}
+	}
+}
+
+impl FrameSink for ImageSequenceSink {
+	fn write_frame(&mut self, frame: &Texture) -> RenderResult<()> {
+		let filename: String = self.filename(self.next);
+		frame.write(&filename)?;
+		self.next += 1;
+		Ok(())
+	}
+	
+	fn finish(&mut self) -> RenderResult<()> {
+		Ok(())
+	}
+}
+
+impl<W: Write> FrameSink for Y4mWriter<W> {
+	fn write_frame(&mut self, frame: &Texture) -> RenderResult<()> {
+		Y4mWriter::write_frame(self, frame)
+	}
+	
+	fn finish(&mut self) -> RenderResult<()> {
+		self.flush()
+	}
+}
+
+pub fn create_y4m<P: AsRef<Path>>(filename: P, width: u32, height: u32, fps_num: u32, fps_den: u32) -> RenderResult<Y4mWriter<BufWriter<File>>> {
+	Y4mWriter::new(BufWriter::new(File::create(filename)?), width, height, fps_num, fps_den)
+}
+
+impl<W: Write> FrameSink for GifWriter<W> {
+	fn write_frame(&mut self, frame: &Texture) -> RenderResult<()> {
+		GifWriter::write_frame(self, frame)
+	}
+	
+	fn finish(&mut self) -> RenderResult<()> {
+		GifWriter::finish(self)
+	}
+}
+
+//animated gif looping forever, delay in 1/100 s per frame
+pub fn create_gif<P: AsRef<Path>>(filename: P, width: u32, height: u32, delay: u16, dither: bool) -> RenderResult<GifWriter<BufWriter<File>>> {
+	let mut writer = GifWriter::new(BufWriter::new(File::create(filename)?), width, height, Some(0))?;
+	writer.delay = delay;
+	writer.dither = dither;
+	Ok(writer)
+}
@@ -2,10 +2,20 @@ extern crate math;
 
 #[allow(dead_code)]
 pub mod color;
+pub mod error;
 pub mod texture;
 pub mod objects;
 pub mod camera;
+pub mod quaternion;
 pub mod rasterizer;
+pub mod backend;
+pub mod diagnostics;
+pub mod stats;
+pub mod frame_sink;
 
 #[cfg(test)]
-mod raterizer_test;
\ No newline at end of file
+mod raterizer_test;
+#[cfg(test)]
+mod quaternion_test;
+#[cfg(test)]
+mod test_util;
\ No newline at end of file
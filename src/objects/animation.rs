@@ -0,0 +1,262 @@
+/*
+	Keyframe animation. A clip holds tracks, each track animates one property of a node, the model matrix or morph weights of a mesh,
+	a camera or a material. Clip::apply poses the scene for a point in time, render_scene draws the pose afterwards.
+	Transform tracks replace only their part of the translation * rotation * scale of the target, the others are kept.
+*/
+use math::vec::vec3::*;
+use math::mat::mat4::*;
+use objects::node::*;
+use objects::scene::*;
+use quaternion::*;
+use camera::*;
+use color::*;
+use error::*;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Interpolation {
+	Step, //keeps the value of the previous key
+	Linear,
+	Bezier(f32, f32, f32, f32), //easing from key to key with the control points x1, y1, x2, y2 like css cubic-bezier
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Target {
+	Node(String), //by name, see Scene::find_node
+	Mesh(usize), //model matrix of Scene::meshes
+	Camera(usize), //index in Scene::cameras
+	Material(usize), //index in Scene::materials
+}
+
+#[derive(Clone)]
+pub struct Keyframe<T> {
+	pub time: f32, //seconds
+	pub value: T,
+}
+
+impl<T> Keyframe<T> {
+	pub fn new(time: f32, value: T) -> Keyframe<T> {
+		Keyframe { time, value }
+	}
+}
+
+#[derive(Clone)]
+pub enum Channel {
+	Position(Vec<Keyframe<Vec3>>),
+	Rotation(Vec<Keyframe<Quaternion>>),
+	Scale(Vec<Keyframe<Vec3>>),
+	CameraFrom(Vec<Keyframe<Vec3>>),
+	CameraTo(Vec<Keyframe<Vec3>>),
+	Color(Vec<Keyframe<Color>>), //diffuse color of a material
+	Weights(Vec<Keyframe<Vec<f32>>>), //morph target weights of a mesh
+}
+
+#[derive(Clone)]
+pub struct Track {
+	pub target: Target,
+	pub channel: Channel,
+	pub interpolation: Interpolation,
+}
+
+impl Track {
+	pub fn new(target: Target, channel: Channel, interpolation: Interpolation) -> Track {
+		Track { target, channel, interpolation }
+	}
+	
+	//time of the last key
+	pub fn duration(&self) -> f32 {
+		let last: Option<f32> = match self.channel {
+			Channel::Position(ref keys) | Channel::Scale(ref keys) |
+			Channel::CameraFrom(ref keys) | Channel::CameraTo(ref keys) => keys.last().map(|k| k.time),
+			Channel::Rotation(ref keys) => keys.last().map(|k| k.time),
+			Channel::Color(ref keys) => keys.last().map(|k| k.time),
+			Channel::Weights(ref keys) => keys.last().map(|k| k.time),
+		};
+		last.unwrap_or(0.0)
+	}
+}
+
+pub trait Interpolate: Clone {
+	fn interpolate(a: &Self, b: &Self, t: f32) -> Self;
+}
+
+impl Interpolate for Vec3 {
+	fn interpolate(a: &Vec3, b: &Vec3, t: f32) -> Vec3 {
+		Vec3::new(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t, a.z + (b.z - a.z) * t)
+	}
+}
+
+impl Interpolate for Quaternion {
+	fn interpolate(a: &Quaternion, b: &Quaternion, t: f32) -> Quaternion {
+		a.slerp(b, t)
+	}
+}
+
+//missing weights of the shorter key count as zero
+impl Interpolate for Vec<f32> {
+	fn interpolate(a: &Vec<f32>, b: &Vec<f32>, t: f32) -> Vec<f32> {
+		(0..a.len().max(b.len())).map(|i| {
+			let (wa, wb) = (a.get(i).cloned().unwrap_or(0.0), b.get(i).cloned().unwrap_or(0.0));
+			wa + (wb - wa) * t
+		}).collect()
+	}
+}
+
+impl Interpolate for Color {
+	fn interpolate(a: &Color, b: &Color, t: f32) -> Color {
+		Color::new(a.r + (b.r - a.r) * t, a.g + (b.g - a.g) * t, a.b + (b.b - a.b) * t)
+	}
+}
+
+//y of the easing curve at x, the curve is monotonic in x for control points with x in 0..1
+fn bezier_ease(x: f32, x1: f32, y1: f32, x2: f32, y2: f32) -> f32 {
+	let curve = |s: f32, p1: f32, p2: f32| 3.0 * (1.0 - s) * (1.0 - s) * s * p1 + 3.0 * (1.0 - s) * s * s * p2 + s * s * s;
+	let (mut lo, mut hi): (f32, f32) = (0.0, 1.0);
+	for _ in 0..24 {
+		let mid: f32 = 0.5 * (lo + hi);
+		if curve(mid, x1, x2) < x {
+			lo = mid;
+		} else {
+			hi = mid;
+		}
+	}
+	curve(0.5 * (lo + hi), y1, y2)
+}
+
+//value of the keys at time, before the first and after the last key their values are held
+pub fn sample<T: Interpolate>(keys: &[Keyframe<T>], interpolation: Interpolation, time: f32) -> RenderResult<T> {
+	if keys.is_empty() {
+		return Err(RenderError::InvalidAnimation("track without keys".to_string()));
+	}
+	if keys.windows(2).any(|k| k[0].time > k[1].time || k[0].time.is_nan() || k[1].time.is_nan()) {
+		return Err(RenderError::InvalidAnimation("keys are not sorted by time".to_string()));
+	}
+	let next: usize = keys.iter().position(|k| k.time > time).unwrap_or(keys.len());
+	if next == 0 {
+		return Ok(keys[0].value.clone());
+	}
+	if next == keys.len() {
+		return Ok(keys[next - 1].value.clone());
+	}
+	let (a, b) = (&keys[next - 1], &keys[next]);
+	let t: f32 = (time - a.time) / (b.time - a.time);
+	let t: f32 = match interpolation {
+		Interpolation::Step => return Ok(a.value.clone()),
+		Interpolation::Linear => t,
+		Interpolation::Bezier(x1, y1, x2, y2) => bezier_ease(t, x1, y1, x2, y2),
+	};
+	Ok(T::interpolate(&a.value, &b.value, t))
+}
+
+fn invalid(message: &str) -> RenderError {
+	RenderError::InvalidAnimation(message.to_string())
+}
+
+fn pose_transform(mat: &mut Mat4, track: &Track, time: f32) -> RenderResult<()> {
+	let (mut translation, mut rotation, mut scale) = (Vec3::new_empty(), Quaternion::new_identity(), Vec3::new_empty());
+	decompose(mat, &mut translation, &mut rotation, &mut scale);
+	//a scale after a rotation is no translation * rotation * scale, a track would silently change the other parts
+	let entries = |m: &Mat4| [m._11, m._12, m._13, m._14, m._21, m._22, m._23, m._24, m._31, m._32, m._33, m._34, m._41, m._42, m._43, m._44];
+	let trs = entries(&compose(&translation, &rotation, &scale));
+	if entries(mat).iter().zip(trs.iter()).any(|(a, b)| (a - b).abs() > 1e-4 * a.abs().max(1.0)) {
+		return Err(invalid(&format!("transform of {:?} is not translation * rotation * scale", track.target)));
+	}
+	match track.channel {
+		Channel::Position(ref keys) => translation = sample(keys, track.interpolation, time)?,
+		Channel::Rotation(ref keys) => rotation = sample(keys, track.interpolation, time)?,
+		Channel::Scale(ref keys) => scale = sample(keys, track.interpolation, time)?,
+		_ => return Err(invalid(&format!("{:?} has no camera, color or weights", track.target))),
+	}
+	*mat = compose(&translation, &rotation, &scale);
+	Ok(())
+}
+
+pub struct Clip {
+	pub name: String,
+	pub tracks: Vec<Track>,
+	pub looping: bool, //times beyond the duration wrap around
+}
+
+impl Clip {
+	pub fn new(name: &str) -> Clip {
+		Clip { name: name.to_string(), tracks: Vec::new(), looping: false }
+	}
+	
+	pub fn add(&mut self, target: Target, channel: Channel, interpolation: Interpolation) {
+		self.tracks.push(Track::new(target, channel, interpolation));
+	}
+	
+	pub fn duration(&self) -> f32 {
+		self.tracks.iter().map(|t| t.duration()).fold(0.0, f32::max)
+	}
+	
+	fn _local_time(&self, time: f32) -> f32 {
+		let duration: f32 = self.duration();
+		if self.looping && duration > 0.0 {
+			time - (time / duration).floor() * duration
+		} else {
+			time
+		}
+	}
+	
+	pub fn apply(&self, scene: &mut Scene, time: f32) -> RenderResult<()> {
+		let time: f32 = self._local_time(time);
+		let mut cameras: Vec<usize> = Vec::new();
+		let mut materials: Vec<usize> = Vec::new();
+		for track in &self.tracks {
+			match track.target {
+				Target::Node(ref name) => match scene.find_node_mut(name) {
+					Some(node) => pose_transform(&mut node.transform, track, time)?,
+					None => return Err(invalid(&format!("node {} does not exist", name))),
+				},
+				Target::Mesh(index) => match (scene.meshes.get_mut(index), &track.channel) {
+					(Some(mesh), &Channel::Weights(ref keys)) => mesh.morph_weights = sample(keys, track.interpolation, time)?,
+					(Some(mesh), _) => pose_transform(&mut mesh.model, track, time)?,
+					(None, _) => return Err(invalid(&format!("mesh {} does not exist", index))),
+				},
+				Target::Camera(index) => {
+					if index >= scene.cameras.len() {
+						return Err(invalid(&format!("camera {} does not exist", index)));
+					}
+					if !cameras.contains(&index) {
+						cameras.push(index);
+					}
+				},
+				Target::Material(index) => match (scene.materials.get_mut(index), &track.channel) {
+					(Some(material), &Channel::Color(ref keys)) => {
+						material.diffuse = sample(keys, track.interpolation, time)?;
+						materials.push(index);
+					},
+					(Some(_), _) => return Err(invalid(&format!("material {} has only a color", index))),
+					(None, _) => return Err(invalid(&format!("material {} does not exist", index))),
+				},
+			}
+		}
+		for index in cameras {
+			self.pose_camera(index, &mut scene.cameras[index], time)?;
+		}
+		//the vertex colors are what gets drawn
+		for index in materials {
+			scene.update_material_colors(index);
+		}
+		Ok(())
+	}
+	
+	//applies the tracks of Target::Camera(index) to any camera, e.g. the one of the Renderer
+	pub fn pose_camera(&self, index: usize, camera: &mut Camera, time: f32) -> RenderResult<()> {
+		let time: f32 = self._local_time(time);
+		let (mut from, mut to): (Vec3, Vec3) = (camera.from.clone(), camera.to.clone());
+		let mut posed: bool = false;
+		for track in self.tracks.iter().filter(|t| t.target == Target::Camera(index)) {
+			match track.channel {
+				Channel::CameraFrom(ref keys) => from = sample(keys, track.interpolation, time)?,
+				Channel::CameraTo(ref keys) => to = sample(keys, track.interpolation, time)?,
+				_ => return Err(invalid(&format!("camera {} has only from and to", index))),
+			}
+			posed = true;
+		}
+		if posed {
+			camera.look_at(&from, &to);
+		}
+		Ok(())
+	}
+}
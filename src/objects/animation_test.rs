@@ -0,0 +1,113 @@
+use objects::animation::*;
+use objects::scene::*;
+use objects::node::*;
+use objects::mesh::*;
+use objects::material::*;
+use quaternion::*;
+use camera::*;
+use color::*;
+use error::*;
+use math::vec::vec3::*;
+use test_util::*;
+
+#[test]
+fn animation_sample_interpolations() {
+	let keys: Vec<Keyframe<Vec3>> = vec![Keyframe::new(1.0, Vec3::new(0.0, 0.0, 0.0)), Keyframe::new(3.0, Vec3::new(4.0, 0.0, -2.0))];
+	assert_close(&sample(&keys, Interpolation::Linear, 0.0).unwrap(), &Vec3::new(0.0, 0.0, 0.0), 1e-4);
+	assert_close(&sample(&keys, Interpolation::Linear, 2.5).unwrap(), &Vec3::new(3.0, 0.0, -1.5), 1e-4);
+	assert_close(&sample(&keys, Interpolation::Linear, 9.0).unwrap(), &Vec3::new(4.0, 0.0, -2.0), 1e-4);
+	assert_close(&sample(&keys, Interpolation::Step, 2.9).unwrap(), &Vec3::new(0.0, 0.0, 0.0), 1e-4);
+	assert_close(&sample(&keys, Interpolation::Step, 3.0).unwrap(), &Vec3::new(4.0, 0.0, -2.0), 1e-4);
+	
+	//linear control points give linear timing, ease in starts slower and both meet in the middle when symmetric
+	let linear: Vec3 = sample(&keys, Interpolation::Bezier(1.0 / 3.0, 1.0 / 3.0, 2.0 / 3.0, 2.0 / 3.0), 1.5).unwrap();
+	assert_close(&linear, &Vec3::new(1.0, 0.0, -0.5), 1e-4);
+	let ease: Interpolation = Interpolation::Bezier(0.42, 0.0, 0.58, 1.0);
+	assert_eq!(sample(&keys, ease, 1.5).unwrap().x < 1.0, true);
+	assert_close(&sample(&keys, ease, 2.0).unwrap(), &Vec3::new(2.0, 0.0, -1.0), 1e-4);
+	
+	let rotations: Vec<Keyframe<Quaternion>> = vec![Keyframe::new(0.0, Quaternion::new_identity()),
+		Keyframe::new(1.0, Quaternion::from_axis_angle(&Vec3::new(0.0, 1.0, 0.0), 90.0))];
+	let half: Quaternion = sample(&rotations, Interpolation::Linear, 0.5).unwrap();
+	assert_eq!((half.dot(&Quaternion::from_axis_angle(&Vec3::new(0.0, 1.0, 0.0), 45.0)) - 1.0).abs() < 1e-5, true);
+	
+	let unsorted: Vec<Keyframe<Vec3>> = vec![Keyframe::new(1.0, Vec3::new_empty()), Keyframe::new(0.5, Vec3::new_empty())];
+	match sample(&unsorted, Interpolation::Linear, 0.7) {
+		Err(RenderError::InvalidAnimation(_)) => {},
+		_ => panic!("unsorted keys must be rejected"),
+	}
+	let nan: Vec<Keyframe<Vec3>> = vec![Keyframe::new(0.0, Vec3::new_empty()), Keyframe::new(::std::f32::NAN, Vec3::new_empty())];
+	match sample(&nan, Interpolation::Linear, 0.7) {
+		Err(RenderError::InvalidAnimation(_)) => {},
+		_ => panic!("keys without time must be rejected"),
+	}
+}
+
+#[test]
+fn animation_clip_poses_scene() {
+	let mut scene: Scene = Scene::new(1);
+	scene.meshes.push(MeshBuilder::cube(&Vec3::new_empty(), 1.0));
+	let mut node: Node = Node::new_mesh("spinner", 0);
+	node.scale(2.0, 2.0, 2.0);
+	scene.nodes.push(node);
+	scene.materials.push(Material::new("paint"));
+	let from: Vec3 = Vec3::new(0.0, 0.0, 5.0);
+	let to: Vec3 = Vec3::new_empty();
+	let mut camera: Camera = Camera::new_init(&from, &to, -1.0, 1.0, 1.0, -1.0, 1.0, 10.0);
+	camera.to_perspective(&from, &to, -1.0, 1.0, 1.0, -1.0, 1.0, 10.0);
+	scene.cameras.push(camera);
+	
+	let mut clip: Clip = Clip::new("turntable");
+	let y: Vec3 = Vec3::new(0.0, 1.0, 0.0);
+	clip.add(Target::Node("spinner".to_string()), Channel::Rotation(vec![Keyframe::new(0.0, Quaternion::new_identity()),
+		Keyframe::new(2.0, Quaternion::from_axis_angle(&y, 90.0)), Keyframe::new(4.0, Quaternion::from_axis_angle(&y, 180.0))]),
+		Interpolation::Linear);
+	clip.add(Target::Mesh(0), Channel::Position(vec![Keyframe::new(0.0, Vec3::new_empty()),
+		Keyframe::new(2.0, Vec3::new(0.0, 1.0, 0.0))]), Interpolation::Linear);
+	clip.add(Target::Camera(0), Channel::CameraFrom(vec![Keyframe::new(0.0, from.clone()),
+		Keyframe::new(4.0, Vec3::new(5.0, 0.0, 0.0))]), Interpolation::Step);
+	clip.add(Target::Material(0), Channel::Color(vec![Keyframe::new(0.0, BLACK.clone()), Keyframe::new(4.0, WHITE.clone())]),
+		Interpolation::Linear);
+	clip.looping = true;
+	assert_eq!(clip.duration(), 4.0);
+	
+	//the rotation track keeps the scale of the node
+	clip.apply(&mut scene, 6.0).unwrap();
+	let mut vec: Vec3 = Vec3::new(1.0, 0.0, 0.0);
+	transform_point(&scene.nodes[0].transform, &mut vec);
+	assert_close(&vec, &Vec3::new(0.0, 0.0, -2.0), 1e-4);
+	assert_eq!(scene.meshes[0].model._24, 1.0);
+	assert_eq!(scene.materials[0].diffuse == Color::new(127.5, 127.5, 127.5), true);
+	assert_close(&scene.cameras[0].from, &from, 1e-4);
+	
+	clip.looping = false;
+	clip.apply(&mut scene, 4.0).unwrap();
+	assert_close(&scene.cameras[0].from, &Vec3::new(5.0, 0.0, 0.0), 1e-4);
+	let mut center: Vec3 = Vec3::new_empty();
+	transform_point(&scene.cameras[0].view, &mut center);
+	assert_close(&center, &Vec3::new(0.0, 0.0, -5.0), 1e-4);
+	
+	clip.add(Target::Node("missing".to_string()), Channel::Scale(vec![Keyframe::new(0.0, Vec3::new_empty())]), Interpolation::Step);
+	match clip.apply(&mut scene, 0.0) {
+		Err(RenderError::InvalidAnimation(_)) => {},
+		_ => panic!("tracks of missing nodes must be rejected"),
+	}
+	clip.tracks.pop();
+	clip.add(Target::Material(0), Channel::Position(vec![Keyframe::new(0.0, Vec3::new_empty())]), Interpolation::Step);
+	match clip.apply(&mut scene, 0.0) {
+		Err(RenderError::InvalidAnimation(_)) => {},
+		_ => panic!("materials can not be moved"),
+	}
+	
+	//scaling after a rotation skews the node, a track can not keep that
+	clip.tracks.pop();
+	let mut skewed: Node = Node::new("skewed");
+	skewed.rotate_quaternion(&Quaternion::from_axis_angle(&Vec3::new(0.0, 0.0, 1.0), 45.0));
+	skewed.scale(2.0, 1.0, 1.0);
+	scene.nodes.push(skewed);
+	clip.add(Target::Node("skewed".to_string()), Channel::Position(vec![Keyframe::new(0.0, Vec3::new(1.0, 0.0, 0.0))]), Interpolation::Step);
+	match clip.apply(&mut scene, 0.0) {
+		Err(RenderError::InvalidAnimation(_)) => {},
+		_ => panic!("transforms that are no translation * rotation * scale must be rejected"),
+	}
+}
@@ -0,0 +1,693 @@
+/*
+	glTF 2.0 reader for .gltf files with external or base64 embedded buffers and binary .glb files.
+	Meshes stay in local space and are shared by the nodes using them, the renderer composes the node transformations.
+	Primitives of all modes become shapes, base colors are multiplied into the vertex colors.
+	Embedded images are decoded if they are png, bmp or tga, other images keep the texture unused.
+	Skins reference their joints by node name, joints without a unique name are named node<index>.
+	Morph targets keep position and normal deltas, the names come from extras.targetNames if present.
+*/
+use std::io::Read;
+use std::collections::HashMap;
+use math::mat::mat4::*;
+use math::vec::vec2::*;
+use math::vec::vec3::*;
+use objects::shape::*;
+use objects::mesh::*;
+use objects::scene::*;
+use objects::material::*;
+use objects::node::*;
+use objects::skin::*;
+use objects::morph::*;
+use objects::format::json::*;
+use texture::*;
+use texture::format::png::*;
+use texture::format::bmp::*;
+use texture::format::tga::*;
+use camera::*;
+use quaternion::*;
+use color::*;
+use error::*;
+
+fn invalid(message: &str) -> RenderError {
+	RenderError::InvalidModel(format!("gltf: {}", message))
+}
+
+fn read_u32(data: &[u8], pos: usize) -> u32 {
+	(data[pos] as u32) | ((data[pos + 1] as u32) << 8) | ((data[pos + 2] as u32) << 16) | ((data[pos + 3] as u32) << 24)
+}
+
+fn base64_decode(text: &str) -> RenderResult<Vec<u8>> {
+	let mut out: Vec<u8> = Vec::with_capacity(text.len() * 3 / 4);
+	let mut bits: u32 = 0;
+	let mut count: u32 = 0;
+	for ch in text.bytes() {
+		let value: u32 = match ch {
+			b'A'..=b'Z' => (ch - b'A') as u32,
+			b'a'..=b'z' => (ch - b'a') as u32 + 26,
+			b'0'..=b'9' => (ch - b'0') as u32 + 52,
+			b'+' | b'-' => 62,
+			b'/' | b'_' => 63,
+			b'=' => break,
+			b' ' | b'\t' | b'\r' | b'\n' => continue,
+			_ => return Err(invalid("invalid base64 data")),
+		};
+		bits = (bits << 6) | value;
+		count += 6;
+		if count >= 8 {
+			count -= 8;
+			out.push((bits >> count) as u8);
+		}
+	}
+	Ok(out)
+}
+
+fn percent_decode(uri: &str) -> String {
+	let bytes: &[u8] = uri.as_bytes();
+	let mut out: Vec<u8> = Vec::with_capacity(bytes.len());
+	let mut i: usize = 0;
+	while i < bytes.len() {
+		if bytes[i] == b'%' && i + 2 < bytes.len() {
+			let hex: String = bytes[i + 1..i + 3].iter().map(|b| *b as char).collect();
+			if let Ok(value) = u8::from_str_radix(&hex, 16) {
+				out.push(value);
+				i += 3;
+				continue;
+			}
+		}
+		out.push(bytes[i]);
+		i += 1;
+	}
+	String::from_utf8_lossy(&out).into_owned()
+}
+
+//data uris are decoded here, only relative paths are handed to load_uri
+fn load_data<F: FnMut(&str) -> RenderResult<Vec<u8>>>(uri: &str, load_uri: &mut F) -> RenderResult<Vec<u8>> {
+	if uri.starts_with("data:") {
+		return match uri.find(";base64,") {
+			Some(pos) => base64_decode(&uri[pos + 8..]),
+			None => Err(invalid("only base64 data uris are supported")),
+		};
+	}
+	if uri.contains("://") {
+		return Err(invalid(&format!("uri {} is not a relative path", uri)));
+	}
+	load_uri(&percent_decode(uri))
+}
+
+fn decode_image(data: &[u8]) -> Option<Texture> {
+	let mut r: &[u8] = data;
+	if data.starts_with(&[0x89, b'P', b'N', b'G']) {
+		read_png(&mut r).ok()
+	} else if data.starts_with(b"BM") {
+		read_bmp(&mut r).ok()
+	} else if data.len() > 18 && [1, 2, 3, 9, 10, 11].contains(&data[2]) {
+		read_tga(&mut r).ok()
+	} else {
+		None
+	}
+}
+
+fn column_major(m: &[f32]) -> Mat4 {
+	Mat4::new(m[0], m[4], m[8], m[12],
+			  m[1], m[5], m[9], m[13],
+			  m[2], m[6], m[10], m[14],
+			  m[3], m[7], m[11], m[15])
+}
+
+//column major matrix or translation, rotation and scale of a node
+fn node_transform(node: &JsonValue) -> Mat4 {
+	let m: Vec<f32> = node.get("matrix").f32_array();
+	if m.len() == 16 {
+		return column_major(&m);
+	}
+	let mut t: Vec<f32> = node.get("translation").f32_array();
+	let mut r: Vec<f32> = node.get("rotation").f32_array();
+	let mut s: Vec<f32> = node.get("scale").f32_array();
+	if t.len() != 3 { t = vec![0.0, 0.0, 0.0]; }
+	if r.len() != 4 { r = vec![0.0, 0.0, 0.0, 1.0]; }
+	if s.len() != 3 { s = vec![1.0, 1.0, 1.0]; }
+	let mut q: Quaternion = Quaternion::new(r[3], r[0], r[1], r[2]);
+	q.normalize();
+	compose(&Vec3::new(t[0], t[1], t[2]), &q, &Vec3::new(s[0], s[1], s[2]))
+}
+
+//the node tree is read recursively, deeper hierarchies are rejected
+const MAX_NODE_DEPTH: usize = 256;
+
+struct GltfReader<'a> {
+	json: &'a JsonValue,
+	buffers: Vec<Vec<u8>>,
+	node_names: Vec<String>,
+	visited: Vec<bool>, //glTF nodes form strict trees, so every node is read once
+	scene: Scene,
+}
+
+impl<'a> GltfReader<'a> {
+	fn element(&self, array: &str, index: usize) -> RenderResult<&'a JsonValue> {
+		match self.json.get(array).members().get(index) {
+			Some(value) => Ok(value),
+			None => Err(invalid(&format!("{} {} does not exist", array, index))),
+		}
+	}
+	
+	fn index(value: &JsonValue, what: &str) -> RenderResult<usize> {
+		match value.as_usize() {
+			Some(index) => Ok(index),
+			None => Err(invalid(&format!("missing or invalid {}", what))),
+		}
+	}
+	
+	//bytes of a buffer view
+	fn view(&self, index: usize) -> RenderResult<(&[u8], usize)> {
+		let view: &JsonValue = self.element("bufferViews", index)?;
+		let buffer: &Vec<u8> = match self.buffers.get(Self::index(view.get("buffer"), "buffer")?) {
+			Some(buffer) => buffer,
+			None => return Err(invalid("buffer view references a missing buffer")),
+		};
+		let offset: usize = view.get("byteOffset").as_usize().unwrap_or(0);
+		let length: usize = Self::index(view.get("byteLength"), "byteLength")?;
+		if offset.checked_add(length).map_or(true, |end| end > buffer.len()) {
+			return Err(invalid(&format!("buffer view {} exceeds its buffer", index)));
+		}
+		Ok((&buffer[offset..offset + length], view.get("byteStride").as_usize().unwrap_or(0)))
+	}
+	
+	fn component(data: &[u8], pos: usize, component_type: usize, normalized: bool) -> f64 {
+		match component_type {
+			5120 => {
+				let v: f64 = data[pos] as i8 as f64;
+				if normalized { (v / 127.0).max(-1.0) } else { v }
+			},
+			5121 => {
+				let v: f64 = data[pos] as f64;
+				if normalized { v / 255.0 } else { v }
+			},
+			5122 => {
+				let v: f64 = ((data[pos] as u16) | ((data[pos + 1] as u16) << 8)) as i16 as f64;
+				if normalized { (v / 32767.0).max(-1.0) } else { v }
+			},
+			5123 => {
+				let v: f64 = ((data[pos] as u16) | ((data[pos + 1] as u16) << 8)) as f64;
+				if normalized { v / 65535.0 } else { v }
+			},
+			5125 => read_u32(data, pos) as f64,
+			_ => f32::from_bits(read_u32(data, pos)) as f64,
+		}
+	}
+	
+	fn component_size(component_type: usize) -> RenderResult<usize> {
+		match component_type {
+			5120 | 5121 => Ok(1),
+			5122 | 5123 => Ok(2),
+			5125 | 5126 => Ok(4),
+			_ => Err(invalid(&format!("unknown component type {}", component_type))),
+		}
+	}
+	
+	//reads count elements from a buffer view, stride 0 means tightly packed
+	fn read_elements(&self, view: usize, offset: usize, count: usize, components: usize, component_type: usize, normalized: bool, out: &mut Vec<f64>) -> RenderResult<()> {
+		let (data, stride) = self.view(view)?;
+		let size: usize = Self::component_size(component_type)?;
+		let stride: usize = if stride == 0 { size * components } else { stride };
+		//counts come from the file, so the end is computed without overflow before anything is read
+		let end: Option<usize> = match count {
+			0 => Some(0),
+			_ => (count - 1).checked_mul(stride).and_then(|last| last.checked_add(offset)).and_then(|last| last.checked_add(components * size)),
+		};
+		if end.map_or(true, |end| end > data.len()) {
+			return Err(invalid(&format!("accessor exceeds buffer view {}", view)));
+		}
+		out.reserve(count * components);
+		for i in 0..count {
+			for c in 0..components {
+				out.push(Self::component(data, offset + i * stride + c * size, component_type, normalized));
+			}
+		}
+		Ok(())
+	}
+	
+	//values of an accessor and the count of components per element
+	fn accessor(&self, index: usize) -> RenderResult<(Vec<f64>, usize)> {
+		let accessor: &JsonValue = self.element("accessors", index)?;
+		let count: usize = Self::index(accessor.get("count"), "accessor count")?;
+		let component_type: usize = Self::index(accessor.get("componentType"), "componentType")?;
+		let normalized: bool = accessor.get("normalized").as_bool().unwrap_or(false);
+		let components: usize = match accessor.get("type").as_str() {
+			Some("SCALAR") => 1,
+			Some("VEC2") => 2,
+			Some("VEC3") => 3,
+			Some("VEC4") | Some("MAT2") => 4,
+			Some("MAT3") => 9,
+			Some("MAT4") => 16,
+			_ => return Err(invalid(&format!("accessor {} has an invalid type", index))),
+		};
+		
+		let mut values: Vec<f64> = Vec::new();
+		match accessor.get("bufferView").as_usize() {
+			Some(view) => self.read_elements(view, accessor.get("byteOffset").as_usize().unwrap_or(0), count, components, component_type, normalized, &mut values)?,
+			None => {
+				//zeros for sparse accessors, limited to what the buffers of the file could hold
+				let size: usize = count.checked_mul(components * Self::component_size(component_type)?).unwrap_or(::std::usize::MAX);
+				if size > self.buffers.iter().map(|b| b.len()).sum::<usize>() {
+					return Err(invalid(&format!("accessor {} is larger than the buffers", index)));
+				}
+				values.resize(count * components, 0.0);
+			},
+		}
+		
+		//sparse accessors replace single elements
+		let sparse: &JsonValue = accessor.get("sparse");
+		if !sparse.is_null() {
+			let sparse_count: usize = Self::index(sparse.get("count"), "sparse count")?;
+			let (indices, replacements) = (sparse.get("indices"), sparse.get("values"));
+			let mut targets: Vec<f64> = Vec::new();
+			self.read_elements(Self::index(indices.get("bufferView"), "sparse indices")?, indices.get("byteOffset").as_usize().unwrap_or(0),
+							   sparse_count, 1, Self::index(indices.get("componentType"), "sparse componentType")?, false, &mut targets)?;
+			let mut new_values: Vec<f64> = Vec::new();
+			self.read_elements(Self::index(replacements.get("bufferView"), "sparse values")?, replacements.get("byteOffset").as_usize().unwrap_or(0),
+							   sparse_count, components, component_type, normalized, &mut new_values)?;
+			for (i, target) in targets.iter().enumerate() {
+				let target: usize = *target as usize;
+				if target >= count {
+					return Err(invalid("sparse index out of range"));
+				}
+				values[target * components..(target + 1) * components].copy_from_slice(&new_values[i * components..(i + 1) * components]);
+			}
+		}
+		Ok((values, components))
+	}
+	
+	fn attribute(&self, attributes: &JsonValue, name: &str, count: usize) -> RenderResult<Option<(Vec<f64>, usize)>> {
+		match attributes.get(name).as_usize() {
+			Some(index) => {
+				let (values, components) = self.accessor(index)?;
+				if values.len() != count * components {
+					return Err(invalid(&format!("attribute {} has a different count than POSITION", name)));
+				}
+				Ok(Some((values, components)))
+			},
+			None => Ok(None),
+		}
+	}
+	
+	//images are added to the scene textures once, on first use by a material
+	fn texture(&mut self, index: usize, image_ids: &mut HashMap<usize, i32>, image_path: &mut dyn FnMut(&str) -> String) -> RenderResult<(i32, Option<String>)> {
+		let image: usize = match self.element("textures", index)?.get("source").as_usize() {
+			Some(image) => image,
+			None => return Ok((-1, None)),
+		};
+		let json: &JsonValue = self.element("images", image)?;
+		let path: Option<String> = match json.get("uri").as_str() {
+			Some(uri) if !uri.starts_with("data:") => Some(image_path(&percent_decode(uri))),
+			_ => None,
+		};
+		if let Some(tex_id) = image_ids.get(&image) {
+			return Ok((*tex_id, path));
+		}
+		
+		let texture: Option<SceneTexture> = match (path.clone(), json.get("uri").as_str(), json.get("bufferView").as_usize()) {
+			(Some(path), _, _) => Some(SceneTexture::File(path)),
+			(None, Some(uri), _) => decode_image(&base64_decode(&uri[uri.find(',').unwrap_or(0) + 1..])?).map(SceneTexture::Image),
+			(None, None, Some(view)) => decode_image(self.view(view)?.0).map(SceneTexture::Image),
+			_ => None,
+		};
+		let tex_id: i32 = match texture {
+			Some(texture) => {
+				self.scene.textures.push(texture);
+				self.scene.textures.len() as i32 - 1
+			},
+			None => -1,
+		};
+		image_ids.insert(image, tex_id);
+		Ok((tex_id, path))
+	}
+	
+	fn read_materials(&mut self, image_path: &mut dyn FnMut(&str) -> String) -> RenderResult<()> {
+		let mut image_ids: HashMap<usize, i32> = HashMap::new();
+		let json: &JsonValue = self.json;
+		for (i, m) in json.get("materials").members().iter().enumerate() {
+			let mut material: Material = Material::new(m.get("name").as_str().unwrap_or(&format!("material_{}", i)));
+			let pbr: &JsonValue = m.get("pbrMetallicRoughness");
+			let mut base: Vec<f32> = pbr.get("baseColorFactor").f32_array();
+			if base.len() != 4 { base = vec![1.0, 1.0, 1.0, 1.0]; }
+			material.diffuse.set_from_rgb(base[0] * 255.0, base[1] * 255.0, base[2] * 255.0);
+			material.opacity = base[3];
+			material.metallic = pbr.get("metallicFactor").as_f64().unwrap_or(1.0) as f32;
+			material.roughness = pbr.get("roughnessFactor").as_f64().unwrap_or(1.0) as f32;
+			
+			//closest phong terms: dielectrics reflect 4% white, metals their base color
+			let specular = |c: f32| -> f32 { (0.04 + (c - 0.04) * material.metallic) * 255.0 };
+			let (sr, sg, sb) = (specular(base[0]), specular(base[1]), specular(base[2]));
+			material.specular.set_from_rgb(sr, sg, sb);
+			let alpha: f32 = (material.roughness * material.roughness).max(0.01);
+			material.shininess = (2.0 / (alpha * alpha) - 2.0).max(0.0).min(1000.0);
+			
+			let emissive: Vec<f32> = m.get("emissiveFactor").f32_array();
+			if emissive.len() == 3 {
+				material.emissive.set_from_rgb(emissive[0] * 255.0, emissive[1] * 255.0, emissive[2] * 255.0);
+			}
+			if let Some(index) = pbr.get("baseColorTexture").get("index").as_usize() {
+				let (tex_id, path) = self.texture(index, &mut image_ids, image_path)?;
+				material.tex_id = tex_id;
+				material.diffuse_map = path;
+			}
+			self.scene.materials.push(material);
+		}
+		Ok(())
+	}
+	
+	fn vertex(i: usize, positions: &[f64], normals: &Option<(Vec<f64>, usize)>, uvs: &Option<(Vec<f64>, usize)>, colors: &Option<(Vec<f64>, usize)>, base: &Color) -> Vertex {
+		let mut color: Color = base.clone();
+		if let Some((ref values, components)) = *colors {
+			let c: &[f64] = &values[i * components..];
+			color.set_from_rgb(base.r * c[0] as f32, base.g * c[1] as f32, base.b * c[2] as f32);
+		}
+		let tex_coord: Vec2 = match *uvs {
+			Some((ref values, _)) => Vec2::new(values[i * 2] as f32, values[i * 2 + 1] as f32),
+			None => Vec2::new_empty(),
+		};
+		let mut vertex: Vertex = Vertex::new_init(Vec3::new(positions[i * 3] as f32, positions[i * 3 + 1] as f32, positions[i * 3 + 2] as f32), color, tex_coord);
+		if let Some((ref values, _)) = *normals {
+			vertex.normal.set(values[i * 3] as f32, values[i * 3 + 1] as f32, values[i * 3 + 2] as f32);
+		}
+		vertex
+	}
+	
+	fn read_mesh(&self, index: usize) -> RenderResult<Mesh> {
+		let json: &JsonValue = self.element("meshes", index)?;
+		let mut mesh: Mesh = Mesh::new(0);
+		mesh.name = json.get("name").as_str().unwrap_or("").to_string();
+		let default: Material = Material::new("default");
+		let cnt_targets: usize = json.get("primitives").members().first().map_or(0, |p| p.get("targets").members().len());
+		let names: &[JsonValue] = json.get("extras").get("targetNames").members();
+		for t in 0..cnt_targets {
+			mesh.morph_targets.push(MorphTarget::new(names.get(t).and_then(|n| n.as_str()).unwrap_or("")));
+		}
+		mesh.morph_weights = json.get("weights").f32_array();
+		mesh.morph_weights.resize(cnt_targets, 0.0);
+		
+		for primitive in json.get("primitives").members() {
+			let attributes: &JsonValue = primitive.get("attributes");
+			let (positions, components) = self.accessor(Self::index(attributes.get("POSITION"), "POSITION")?)?;
+			if components != 3 {
+				return Err(invalid("POSITION must be VEC3"));
+			}
+			let count: usize = positions.len() / 3;
+			let normals = self.attribute(attributes, "NORMAL", count)?;
+			let uvs = self.attribute(attributes, "TEXCOORD_0", count)?;
+			let colors = self.attribute(attributes, "COLOR_0", count)?;
+			if normals.as_ref().map_or(false, |n| n.1 != 3) || uvs.as_ref().map_or(false, |t| t.1 != 2) || colors.as_ref().map_or(false, |c| c.1 < 3) {
+				return Err(invalid("invalid NORMAL, TEXCOORD_0 or COLOR_0 type"));
+			}
+			let mut deltas: Vec<(Option<(Vec<f64>, usize)>, Option<(Vec<f64>, usize)>)> = Vec::with_capacity(cnt_targets);
+			if primitive.get("targets").members().len() != cnt_targets {
+				return Err(invalid("all primitives need the same morph targets"));
+			}
+			for target in primitive.get("targets").members() {
+				let (position, normal) = (self.attribute(target, "POSITION", count)?, self.attribute(target, "NORMAL", count)?);
+				if position.as_ref().map_or(false, |p| p.1 != 3) || normal.as_ref().map_or(false, |n| n.1 != 3) {
+					return Err(invalid("morph target deltas must be VEC3"));
+				}
+				deltas.push((position, normal));
+			}
+			let joints = self.attribute(attributes, "JOINTS_0", count)?;
+			let weights = self.attribute(attributes, "WEIGHTS_0", count)?;
+			if joints.as_ref().map(|j| j.1) != weights.as_ref().map(|w| w.1) || joints.as_ref().map_or(false, |j| j.1 != 4) {
+				return Err(invalid("JOINTS_0 and WEIGHTS_0 must both be VEC4"));
+			}
+			
+			let material: &Material = match primitive.get("material").as_usize() {
+				Some(m) => match self.scene.materials.get(m) {
+					Some(material) => material,
+					None => return Err(invalid(&format!("material {} does not exist", m))),
+				},
+				None => &default,
+			};
+			let tex_id: i32 = if uvs.is_some() { material.tex_id } else { -1 };
+			
+			let indices: Vec<usize> = match primitive.get("indices").as_usize() {
+				Some(accessor) => self.accessor(accessor)?.0.iter().map(|i| *i as usize).collect(),
+				None => (0..count).collect(),
+			};
+			if indices.iter().any(|i| *i >= count) {
+				return Err(invalid("index out of range"));
+			}
+			
+			let mut groups: Vec<Vec<usize>> = Vec::new();
+			let n: usize = indices.len();
+			match primitive.get("mode").as_usize().unwrap_or(4) {
+				0 => groups.extend(indices.iter().map(|i| vec![*i])),
+				1 => groups.extend(indices.chunks(2).filter(|c| c.len() == 2).map(|c| c.to_vec())),
+				2 | 3 => {
+					for i in 1..n {
+						groups.push(vec![indices[i - 1], indices[i]]);
+					}
+					if primitive.get("mode").as_usize() == Some(2) && n > 2 {
+						groups.push(vec![indices[n - 1], indices[0]]);
+					}
+				},
+				4 => groups.extend(indices.chunks(3).filter(|c| c.len() == 3).map(|c| c.to_vec())),
+				//keep the winding of every second strip triangle
+				5 => for i in 2..n {
+					let odd: usize = i % 2;
+					groups.push(vec![indices[i - 2], indices[i - 1 + odd], indices[i - odd]]);
+				},
+				6 => for i in 2..n {
+					groups.push(vec![indices[i - 1], indices[i], indices[0]]);
+				},
+				mode => return Err(invalid(&format!("unknown primitive mode {}", mode))),
+			}
+			
+			for group in groups {
+				let mut vertices: Vec<Vertex> = group.iter().map(|i| Self::vertex(*i, &positions, &normals, &uvs, &colors, &material.diffuse)).collect();
+				//every target gets a delta for every vertex, so the shapes of all primitives stay in order
+				for (target, &(ref position, ref normal)) in mesh.morph_targets.iter_mut().zip(deltas.iter()) {
+					for i in group.iter() {
+						let delta = |values: &Option<(Vec<f64>, usize)>| match *values {
+							Some((ref v, _)) => Vec3::new(v[i * 3] as f32, v[i * 3 + 1] as f32, v[i * 3 + 2] as f32),
+							None => Vec3::new_empty(),
+						};
+						target.positions.push(delta(position));
+						target.normals.push(delta(normal));
+					}
+				}
+				if let (&Some((ref j, _)), &Some((ref w, _))) = (&joints, &weights) {
+					for (vertex, i) in vertices.iter_mut().zip(group.iter()) {
+						for c in 0..4 {
+							vertex.joints[c] = j[i * 4 + c] as u16;
+							vertex.weights[c] = w[i * 4 + c] as f32;
+						}
+					}
+				}
+				let mut shape: Shape = Shape::new_init(if vertices.len() == 3 { tex_id } else { -1 }, &vertices);
+				//COLOR_0 is multiplied with the material color, so only plain material colors can follow it
+				shape.material = if colors.is_none() { primitive.get("material").as_usize() } else { None };
+				mesh.shapes.push(shape);
+			}
+		}
+		Ok(mesh)
+	}
+	
+	fn camera(&self, index: usize, world: &Mat4) -> RenderResult<Camera> {
+		let json: &JsonValue = self.element("cameras", index)?;
+		//glTF cameras look along -z of their node
+		let mut from: Vec3 = Vec3::new_empty();
+		transform_point(world, &mut from);
+		let mut to: Vec3 = Vec3::new(0.0, 0.0, -1.0);
+		transform_point(world, &mut to);
+		
+		let mut camera: Camera = Camera::new_init(&from, &to, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0);
+		let get = |object: &JsonValue, key: &str| -> RenderResult<f32> {
+			match object.get(key).as_f64() {
+				Some(value) => Ok(value as f32),
+				None => Err(invalid(&format!("camera {} without {}", index, key))),
+			}
+		};
+		match json.get("type").as_str() {
+			Some("perspective") => {
+				let p: &JsonValue = json.get("perspective");
+				let n: f32 = get(p, "znear")?;
+				//infinite projections are cut off far away
+				let f: f32 = get(p, "zfar").unwrap_or(n * 10000.0);
+				//without aspectRatio the aspect of the image is used
+				let aspect: Option<f32> = get(p, "aspectRatio").ok();
+				let mut up: Vec3 = Vec3::new(0.0, 1.0, 0.0);
+				transform_point(world, &mut up);
+				up -= &from;
+				camera = Camera::perspective_fov(&from, &to, &up, get(p, "yfov")?.to_degrees(), aspect.unwrap_or(1.0), n, f);
+				camera.fit_aspect = aspect.is_none();
+			},
+			Some("orthographic") => {
+				let o: &JsonValue = json.get("orthographic");
+				let (x, y) = (get(o, "xmag")?, get(o, "ymag")?);
+				camera.to_ortho(&from, &to, -x, x, y, -y, get(o, "znear")?, get(o, "zfar")?);
+			},
+			_ => return Err(invalid(&format!("camera {} has an unknown type", index))),
+		}
+		Ok(camera)
+	}
+	
+	fn read_skin(&self, index: usize) -> RenderResult<Skin> {
+		let json: &JsonValue = self.element("skins", index)?;
+		let mut skin: Skin = Skin::new(json.get("name").as_str().unwrap_or(""));
+		let mut joints: Vec<usize> = Vec::new();
+		for joint in json.get("joints").members() {
+			joints.push(Self::index(joint, "joint")?);
+		}
+		//without inverse bind matrices the joints are bound with identity
+		let matrices: Vec<f32> = match json.get("inverseBindMatrices").as_usize() {
+			Some(accessor) => {
+				let (values, components) = self.accessor(accessor)?;
+				if components != 16 || values.len() != joints.len() * 16 {
+					return Err(invalid(&format!("skin {} needs one MAT4 per joint", index)));
+				}
+				values.iter().map(|v| *v as f32).collect()
+			},
+			None => joints.iter().flat_map(|_| vec![1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0]).collect(),
+		};
+		for (i, joint) in joints.iter().enumerate() {
+			match self.node_names.get(*joint) {
+				Some(name) => skin.add_joint(name, column_major(&matrices[i * 16..(i + 1) * 16])),
+				None => return Err(invalid(&format!("joint node {} does not exist", joint))),
+			}
+		}
+		Ok(skin)
+	}
+	
+	fn read_node(&mut self, index: usize, parent: &Mat4, depth: usize) -> RenderResult<Node> {
+		if depth >= MAX_NODE_DEPTH {
+			return Err(invalid(&format!("node hierarchy is deeper than {}", MAX_NODE_DEPTH)));
+		}
+		let json: &JsonValue = self.element("nodes", index)?;
+		if self.visited[index] {
+			return Err(invalid(&format!("node {} has more than one parent or is its own ancestor", index)));
+		}
+		self.visited[index] = true;
+		let mut node: Node = Node::new(&self.node_names[index]);
+		node.transform = node_transform(json);
+		let mut world: Mat4 = Mat4::new_empty();
+		world.set_from(parent);
+		world *= &node.transform;
+		
+		if let Some(m) = json.get("mesh").as_usize() {
+			if m >= self.scene.meshes.len() {
+				return Err(invalid(&format!("mesh {} does not exist", m)));
+			}
+			node.mesh = Some(m);
+		}
+		if let Some(s) = json.get("skin").as_usize() {
+			if s >= self.scene.skins.len() {
+				return Err(invalid(&format!("skin {} does not exist", s)));
+			}
+			node.skin = Some(s);
+		}
+		if let Some(c) = json.get("camera").as_usize() {
+			let camera: Camera = self.camera(c, &world)?;
+			self.scene.cameras.push(camera);
+			node.camera = Some(self.scene.cameras.len() - 1);
+		}
+		for child in json.get("children").members() {
+			let child: usize = Self::index(child, "child node")?;
+			let child_node: Node = self.read_node(child, &world, depth + 1)?;
+			node.children.push(child_node);
+		}
+		Ok(node)
+	}
+}
+
+//load_uri reads buffers referenced by relative path, image_path turns image uris into texture paths
+pub fn read_gltf<R: Read, F: FnMut(&str) -> RenderResult<Vec<u8>>, U: FnMut(&str) -> String>(r: &mut R, mut load_uri: F, mut image_path: U) -> RenderResult<Scene> {
+	let mut data: Vec<u8> = Vec::new();
+	r.read_to_end(&mut data)?;
+	
+	//binary container with a json and an optional binary chunk
+	let mut bin: Option<Vec<u8>> = None;
+	let json: JsonValue = if data.starts_with(b"glTF") {
+		if data.len() < 20 || read_u32(&data, 4) != 2 {
+			return Err(invalid("unsupported glb version"));
+		}
+		let length: usize = (read_u32(&data, 8) as usize).min(data.len());
+		let mut json: Option<JsonValue> = None;
+		let mut pos: usize = 12;
+		while pos + 8 <= length {
+			let chunk_length: usize = read_u32(&data, pos) as usize;
+			let chunk_type: u32 = read_u32(&data, pos + 4);
+			if pos + 8 + chunk_length > length {
+				return Err(invalid("truncated glb chunk"));
+			}
+			let chunk: &[u8] = &data[pos + 8..pos + 8 + chunk_length];
+			match chunk_type {
+				0x4e4f534a => json = Some(parse_json(chunk)?),
+				0x004e4942 => bin = Some(chunk.to_vec()),
+				_ => {},
+			}
+			pos += 8 + chunk_length;
+		}
+		match json {
+			Some(json) => json,
+			None => return Err(invalid("glb without json chunk")),
+		}
+	} else {
+		parse_json(&data)?
+	};
+	
+	match json.get("asset").get("version").as_str() {
+		Some(version) if version.starts_with("2.") => {},
+		_ => return Err(invalid("only glTF 2.0 is supported")),
+	}
+	
+	let mut buffers: Vec<Vec<u8>> = Vec::new();
+	for (i, buffer) in json.get("buffers").members().iter().enumerate() {
+		let bytes: Vec<u8> = match (buffer.get("uri").as_str(), i) {
+			(Some(uri), _) => load_data(uri, &mut load_uri)?,
+			(None, 0) => bin.take().unwrap_or(Vec::new()),
+			(None, _) => return Err(invalid(&format!("buffer {} without uri", i))),
+		};
+		if let Some(length) = buffer.get("byteLength").as_usize() {
+			if bytes.len() < length {
+				return Err(invalid(&format!("buffer {} is shorter than its byteLength", i)));
+			}
+		}
+		buffers.push(bytes);
+	}
+	
+	//joints are found by name, so they need unique names
+	let nodes: &[JsonValue] = json.get("nodes").members();
+	let names: Vec<String> = nodes.iter().map(|n| n.get("name").as_str().unwrap_or("").to_string()).collect();
+	let mut node_names: Vec<String> = names.clone();
+	for skin in json.get("skins").members() {
+		for joint in skin.get("joints").members().iter().filter_map(|j| j.as_usize()).filter(|j| *j < names.len()) {
+			if names[joint].is_empty() || names.iter().filter(|n| **n == names[joint]).count() > 1 {
+				node_names[joint] = format!("node{}", joint);
+			}
+		}
+	}
+	
+	let mut reader: GltfReader = GltfReader { json: &json, buffers, node_names, visited: vec![false; nodes.len()], scene: Scene::new(0) };
+	reader.read_materials(&mut image_path)?;
+	//scene meshes have the indices of the glTF meshes
+	for m in 0..json.get("meshes").members().len() {
+		let mut mesh: Mesh = reader.read_mesh(m)?;
+		mesh.compute_bbox();
+		reader.scene.meshes.push(mesh);
+	}
+	for s in 0..json.get("skins").members().len() {
+		let skin: Skin = reader.read_skin(s)?;
+		reader.scene.skins.push(skin);
+	}
+	
+	//without scenes every node that is nobody's child is a root
+	let roots: Vec<usize> = match json.get("scenes").members().get(json.get("scene").as_usize().unwrap_or(0)) {
+		Some(scene) => scene.get("nodes").members().iter().filter_map(|n| n.as_usize()).collect(),
+		None => {
+			let children: Vec<usize> = json.get("nodes").members().iter().flat_map(|n| n.get("children").members().iter().filter_map(|c| c.as_usize())).collect();
+			(0..json.get("nodes").members().len()).filter(|n| !children.contains(n)).collect()
+		},
+	};
+	let root_transform: Mat4 = identity();
+	for root in roots {
+		let node: Node = reader.read_node(root, &root_transform, 0)?;
+		reader.scene.nodes.push(node);
+	}
+	Ok(reader.scene)
+}
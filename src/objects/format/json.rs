@@ -0,0 +1,251 @@
+/*
+	Small JSON parser as needed by the glTF loader. Objects keep the order of their members.
+*/
+use error::*;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum JsonValue {
+	Null,
+	Bool(bool),
+	Number(f64),
+	String(String),
+	Array(Vec<JsonValue>),
+	Object(Vec<(String, JsonValue)>),
+}
+
+static NULL: JsonValue = JsonValue::Null;
+
+impl JsonValue {
+	//member of an object, Null if it does not exist
+	pub fn get(&self, key: &str) -> &JsonValue {
+		match *self {
+			JsonValue::Object(ref members) => match members.iter().find(|m| m.0 == key) {
+				Some(member) => &member.1,
+				None => &NULL,
+			},
+			_ => &NULL,
+		}
+	}
+	
+	pub fn is_null(&self) -> bool {
+		*self == JsonValue::Null
+	}
+	
+	pub fn as_f64(&self) -> Option<f64> {
+		match *self {
+			JsonValue::Number(value) => Some(value),
+			_ => None,
+		}
+	}
+	
+	pub fn as_usize(&self) -> Option<usize> {
+		match *self {
+			JsonValue::Number(value) if value >= 0.0 && value.fract() == 0.0 => Some(value as usize),
+			_ => None,
+		}
+	}
+	
+	pub fn as_bool(&self) -> Option<bool> {
+		match *self {
+			JsonValue::Bool(value) => Some(value),
+			_ => None,
+		}
+	}
+	
+	pub fn as_str(&self) -> Option<&str> {
+		match *self {
+			JsonValue::String(ref value) => Some(value),
+			_ => None,
+		}
+	}
+	
+	//empty for everything but arrays
+	pub fn members(&self) -> &[JsonValue] {
+		match *self {
+			JsonValue::Array(ref values) => values,
+			_ => &[],
+		}
+	}
+	
+	pub fn f32_array(&self) -> Vec<f32> {
+		self.members().iter().filter_map(|v| v.as_f64()).map(|v| v as f32).collect()
+	}
+}
+
+//deeper arrays and objects are rejected instead of overflowing the stack
+const MAX_DEPTH: usize = 128;
+
+struct Parser<'a> {
+	data: &'a [u8],
+	pos: usize,
+}
+
+fn invalid(pos: usize, message: &str) -> RenderError {
+	RenderError::InvalidModel(format!("json at byte {}: {}", pos, message))
+}
+
+impl<'a> Parser<'a> {
+	fn skip_whitespace(&mut self) {
+		while self.pos < self.data.len() && (self.data[self.pos] as char).is_whitespace() {
+			self.pos += 1;
+		}
+	}
+	
+	fn expect(&mut self, literal: &str) -> RenderResult<()> {
+		if self.data[self.pos..].starts_with(literal.as_bytes()) {
+			self.pos += literal.len();
+			Ok(())
+		} else {
+			Err(invalid(self.pos, &format!("expected {}", literal)))
+		}
+	}
+	
+	fn value(&mut self, depth: usize) -> RenderResult<JsonValue> {
+		self.skip_whitespace();
+		match self.data.get(self.pos) {
+			Some(&b'{') | Some(&b'[') if depth >= MAX_DEPTH => Err(invalid(self.pos, &format!("nested deeper than {}", MAX_DEPTH))),
+			Some(&b'{') => self.object(depth + 1),
+			Some(&b'[') => self.array(depth + 1),
+			Some(&b'"') => Ok(JsonValue::String(self.string()?)),
+			Some(&b't') => self.expect("true").map(|_| JsonValue::Bool(true)),
+			Some(&b'f') => self.expect("false").map(|_| JsonValue::Bool(false)),
+			Some(&b'n') => self.expect("null").map(|_| JsonValue::Null),
+			Some(_) => self.number(),
+			None => Err(invalid(self.pos, "unexpected end")),
+		}
+	}
+	
+	fn object(&mut self, depth: usize) -> RenderResult<JsonValue> {
+		let mut members: Vec<(String, JsonValue)> = Vec::new();
+		self.pos += 1;
+		self.skip_whitespace();
+		if self.data.get(self.pos) == Some(&b'}') {
+			self.pos += 1;
+			return Ok(JsonValue::Object(members));
+		}
+		loop {
+			self.skip_whitespace();
+			if self.data.get(self.pos) != Some(&b'"') {
+				return Err(invalid(self.pos, "expected member name"));
+			}
+			let key: String = self.string()?;
+			self.skip_whitespace();
+			self.expect(":")?;
+			let value: JsonValue = self.value(depth)?;
+			members.push((key, value));
+			self.skip_whitespace();
+			match self.data.get(self.pos) {
+				Some(&b',') => self.pos += 1,
+				Some(&b'}') => { self.pos += 1; return Ok(JsonValue::Object(members)); },
+				_ => return Err(invalid(self.pos, "expected , or }")),
+			}
+		}
+	}
+	
+	fn array(&mut self, depth: usize) -> RenderResult<JsonValue> {
+		let mut values: Vec<JsonValue> = Vec::new();
+		self.pos += 1;
+		self.skip_whitespace();
+		if self.data.get(self.pos) == Some(&b']') {
+			self.pos += 1;
+			return Ok(JsonValue::Array(values));
+		}
+		loop {
+			values.push(self.value(depth)?);
+			self.skip_whitespace();
+			match self.data.get(self.pos) {
+				Some(&b',') => self.pos += 1,
+				Some(&b']') => { self.pos += 1; return Ok(JsonValue::Array(values)); },
+				_ => return Err(invalid(self.pos, "expected , or ]")),
+			}
+		}
+	}
+	
+	fn hex4(&mut self) -> RenderResult<u32> {
+		if self.pos + 4 > self.data.len() {
+			return Err(invalid(self.pos, "unexpected end in escape"));
+		}
+		let text: String = self.data[self.pos..self.pos + 4].iter().map(|b| *b as char).collect();
+		self.pos += 4;
+		match u32::from_str_radix(&text, 16) {
+			Ok(value) => Ok(value),
+			Err(_) => Err(invalid(self.pos, "invalid unicode escape")),
+		}
+	}
+	
+	fn string(&mut self) -> RenderResult<String> {
+		self.pos += 1;
+		let mut bytes: Vec<u8> = Vec::new();
+		loop {
+			let byte: u8 = match self.data.get(self.pos) {
+				Some(byte) => *byte,
+				None => return Err(invalid(self.pos, "unterminated string")),
+			};
+			self.pos += 1;
+			match byte {
+				b'"' => break,
+				b'\\' => {
+					let escape: u8 = match self.data.get(self.pos) {
+						Some(escape) => *escape,
+						None => return Err(invalid(self.pos, "unterminated string")),
+					};
+					self.pos += 1;
+					let ch: char = match escape {
+						b'"' => '"',
+						b'\\' => '\\',
+						b'/' => '/',
+						b'b' => '\u{8}',
+						b'f' => '\u{c}',
+						b'n' => '\n',
+						b'r' => '\r',
+						b't' => '\t',
+						b'u' => {
+							let mut code: u32 = self.hex4()?;
+							//surrogate pairs
+							if code >= 0xd800 && code < 0xdc00 && self.data[self.pos..].starts_with(b"\\u") {
+								self.pos += 2;
+								let low: u32 = self.hex4()?;
+								code = 0x10000 + ((code - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff);
+							}
+							::std::char::from_u32(code).unwrap_or('\u{fffd}')
+						},
+						_ => return Err(invalid(self.pos, "invalid escape")),
+					};
+					let mut buffer: [u8; 4] = [0; 4];
+					bytes.extend(ch.encode_utf8(&mut buffer).as_bytes());
+				},
+				_ => bytes.push(byte),
+			}
+		}
+		match String::from_utf8(bytes) {
+			Ok(text) => Ok(text),
+			Err(_) => Err(invalid(self.pos, "string is not valid utf-8")),
+		}
+	}
+	
+	fn number(&mut self) -> RenderResult<JsonValue> {
+		let start: usize = self.pos;
+		while self.pos < self.data.len() && b"+-0123456789.eE".contains(&self.data[self.pos]) {
+			self.pos += 1;
+		}
+		let text: String = self.data[start..self.pos].iter().map(|b| *b as char).collect();
+		match text.parse::<f64>() {
+			Ok(value) if !text.is_empty() => Ok(JsonValue::Number(value)),
+			_ => Err(invalid(start, &format!("invalid value {}", text))),
+		}
+	}
+}
+
+pub fn parse_json(data: &[u8]) -> RenderResult<JsonValue> {
+	let mut parser: Parser = Parser { data, pos: 0 };
+	//utf-8 byte order mark
+	if data.starts_with(&[0xef, 0xbb, 0xbf]) {
+		parser.pos = 3;
+	}
+	let value: JsonValue = parser.value(0)?;
+	parser.skip_whitespace();
+	if parser.pos != data.len() {
+		return Err(invalid(parser.pos, "trailing characters"));
+	}
+	Ok(value)
+}
@@ -0,0 +1,5 @@
+pub mod obj;
+pub mod stl;
+pub mod ply;
+pub mod json;
+pub mod gltf;
@@ -0,0 +1,535 @@
+/*
+	Wavefront OBJ and MTL reader and writer. Every object or group becomes its own mesh, polygons are
+	triangulated by ear clipping and negative indices count back from the last defined element.
+	Vertex colors (v x y z r g b) are used if present, otherwise the diffuse color of the material.
+	The writer welds equal vertices and emits one material per texture id.
+*/
+use std::io::{BufRead, Write};
+use std::collections::HashMap;
+use math::vec::vec2::*;
+use math::vec::vec3::*;
+use objects::shape::*;
+use objects::mesh::*;
+use objects::scene::*;
+use objects::material::*;
+use color::*;
+use error::*;
+
+fn invalid(line: usize, message: &str) -> RenderError {
+	RenderError::InvalidModel(format!("line {}: {}", line, message))
+}
+
+//calls f with the tokens of every logical line, comments are removed and lines ending with \ are joined
+fn for_each_line<R: BufRead, F>(r: &mut R, mut f: F) -> RenderResult<()>
+	where F: FnMut(usize, &[&str]) -> RenderResult<()> {
+	let mut buffer: String = String::new();
+	let mut logical: String = String::new();
+	let mut line: usize = 0;
+	let mut start_line: usize = 0;
+	
+	loop {
+		buffer.clear();
+		let eof: bool = r.read_line(&mut buffer)? == 0;
+		if !eof {
+			line += 1;
+			let mut text: &str = buffer.trim_end_matches(|c| c == '\n' || c == '\r');
+			if let Some(pos) = text.find('#') {
+				text = &text[..pos];
+			}
+			if logical.is_empty() {
+				start_line = line;
+			}
+			if text.ends_with('\\') {
+				logical.push_str(&text[..text.len() - 1]);
+				logical.push(' ');
+				continue;
+			}
+			logical.push_str(text);
+		}
+		
+		{
+			let tokens: Vec<&str> = logical.split_whitespace().collect();
+			if !tokens.is_empty() {
+				f(start_line, &tokens)?;
+			}
+		}
+		logical.clear();
+		if eof { break; }
+	}
+	Ok(())
+}
+
+fn parse_f32(token: &str, line: usize) -> RenderResult<f32> {
+	match token.parse::<f32>() {
+		Ok(value) => Ok(value),
+		Err(_) => Err(invalid(line, &format!("invalid number {}", token))),
+	}
+}
+
+fn parse_floats(tokens: &[&str], min: usize, line: usize) -> RenderResult<Vec<f32>> {
+	if tokens.len() < min {
+		return Err(invalid(line, &format!("expected at least {} values", min)));
+	}
+	let mut values: Vec<f32> = Vec::with_capacity(tokens.len());
+	for token in tokens {
+		values.push(parse_f32(token, line)?);
+	}
+	Ok(values)
+}
+
+//Ka, Kd, Ks and Ke with one (gray) or three values, spectral and xyz colors are ignored
+fn parse_color(tokens: &[&str], color: &mut Color, line: usize) -> RenderResult<()> {
+	if tokens.first() == Some(&"spectral") || tokens.first() == Some(&"xyz") {
+		return Ok(());
+	}
+	let values: Vec<f32> = parse_floats(tokens, 1, line)?;
+	if values.len() >= 3 {
+		color.set_from_rgb(values[0] * 255.0, values[1] * 255.0, values[2] * 255.0);
+	} else {
+		color.set_all(values[0] * 255.0);
+	}
+	Ok(())
+}
+
+//skips the options of a texture map statement, the rest is the file name
+fn parse_map(tokens: &[&str], line: usize) -> RenderResult<String> {
+	let mut i: usize = 0;
+	while i < tokens.len() && tokens[i].starts_with('-') {
+		let args: usize = match tokens[i] {
+			"-mm" => 2,
+			"-o" | "-s" | "-t" => 3,
+			_ => 1,
+		};
+		i += 1;
+		let mut cnt: usize = 0;
+		//-o, -s and -t have one to three numbers
+		while cnt < args && i < tokens.len() && (args != 3 || cnt == 0 || tokens[i].parse::<f32>().is_ok()) {
+			i += 1;
+			cnt += 1;
+		}
+	}
+	if i >= tokens.len() {
+		return Err(invalid(line, "missing texture file name"));
+	}
+	Ok(tokens[i..].join(" "))
+}
+
+pub fn read_mtl<R: BufRead>(r: &mut R) -> RenderResult<Vec<Material>> {
+	let mut materials: Vec<Material> = Vec::new();
+	
+	for_each_line(r, |line, tokens| {
+		if tokens[0] == "newmtl" {
+			materials.push(Material::new(&tokens[1..].join(" ")));
+			return Ok(());
+		}
+		
+		let material: &mut Material = match materials.last_mut() {
+			Some(material) => material,
+			None => return Err(invalid(line, &format!("{} before newmtl", tokens[0]))),
+		};
+		let args: &[&str] = &tokens[1..];
+		match tokens[0] {
+			"Ka" => parse_color(args, &mut material.ambient, line)?,
+			"Kd" => parse_color(args, &mut material.diffuse, line)?,
+			"Ks" => parse_color(args, &mut material.specular, line)?,
+			"Ke" => parse_color(args, &mut material.emissive, line)?,
+			"Ns" => material.shininess = parse_floats(args, 1, line)?[0],
+			"d" => material.opacity = parse_floats(&args[args.len().saturating_sub(1)..], 1, line)?[0], //skips -halo
+			"Tr" => material.opacity = 1.0 - parse_floats(args, 1, line)?[0],
+			"map_Kd" => material.diffuse_map = Some(parse_map(args, line)?),
+			_ => {},
+		}
+		Ok(())
+	})?;
+	
+	Ok(materials)
+}
+
+//converts a 1 based or negative index to a position in a list of count elements
+fn resolve_index(token: &str, count: usize, line: usize) -> RenderResult<usize> {
+	let index: i64 = match token.parse::<i64>() {
+		Ok(index) => index,
+		Err(_) => return Err(invalid(line, &format!("invalid index {}", token))),
+	};
+	//i64::MIN has no positive counterpart
+	match index.checked_neg() {
+		_ if index > 0 && index as usize <= count => Ok(index as usize - 1),
+		Some(back) if back > 0 && back as usize <= count => Ok(count - back as usize),
+		_ => Err(invalid(line, &format!("index {} out of range", index))),
+	}
+}
+
+fn cross_2d(a: &(f32, f32), b: &(f32, f32), c: &(f32, f32)) -> f32 {
+	(b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+}
+
+//ear clipping in the plane of the polygon, degenerated polygons fall back to a fan
+pub fn triangulate(points: &[Vec3]) -> Vec<[usize; 3]> {
+	let cnt: usize = points.len();
+	let mut triangles: Vec<[usize; 3]> = Vec::with_capacity(cnt.max(2) - 2);
+	if cnt < 3 {
+		return triangles;
+	}
+	
+	//newell normal, the polygon is projected along its dominant axis
+	let (mut nx, mut ny, mut nz) = (0.0f32, 0.0f32, 0.0f32);
+	for i in 0..cnt {
+		let a: &Vec3 = &points[i];
+		let b: &Vec3 = &points[(i + 1) % cnt];
+		nx += (a.y - b.y) * (a.z + b.z);
+		ny += (a.z - b.z) * (a.x + b.x);
+		nz += (a.x - b.x) * (a.y + b.y);
+	}
+	let (ax, ay, az) = (nx.abs(), ny.abs(), nz.abs());
+	let (projected, sign): (Vec<(f32, f32)>, f32) = if az >= ax && az >= ay {
+		(points.iter().map(|p| (p.x, p.y)).collect(), nz.signum())
+	} else if ax >= ay {
+		(points.iter().map(|p| (p.y, p.z)).collect(), nx.signum())
+	} else {
+		(points.iter().map(|p| (p.z, p.x)).collect(), ny.signum())
+	};
+	
+	let mut remaining: Vec<usize> = (0..cnt).collect();
+	while remaining.len() > 3 {
+		let len: usize = remaining.len();
+		let mut ear: Option<usize> = None;
+		for i in 0..len {
+			let (ia, ib, ic) = (remaining[(i + len - 1) % len], remaining[i], remaining[(i + 1) % len]);
+			let (a, b, c) = (&projected[ia], &projected[ib], &projected[ic]);
+			if cross_2d(a, b, c) * sign <= 0.0 {
+				continue;
+			}
+			let blocked: bool = remaining.iter().any(|&j| {
+				j != ia && j != ib && j != ic &&
+				cross_2d(a, b, &projected[j]) * sign >= 0.0 &&
+				cross_2d(b, c, &projected[j]) * sign >= 0.0 &&
+				cross_2d(c, a, &projected[j]) * sign >= 0.0
+			});
+			if !blocked {
+				triangles.push([ia, ib, ic]);
+				ear = Some(i);
+				break;
+			}
+		}
+		match ear {
+			Some(i) => { remaining.remove(i); },
+			None => break,
+		}
+	}
+	
+	for i in 1..remaining.len() - 1 {
+		triangles.push([remaining[0], remaining[i], remaining[i + 1]]);
+	}
+	triangles
+}
+
+struct ObjReader {
+	scene: Scene,
+	positions: Vec<Vec3>,
+	colors: Vec<Option<Color>>,
+	tex_coords: Vec<Vec2>,
+	normals: Vec<Vec3>,
+	mesh: Mesh,
+	material: Option<usize>,
+}
+
+impl ObjReader {
+	fn finish_mesh(&mut self, name: &str) {
+		if !self.mesh.shapes.is_empty() {
+			let mut mesh: Mesh = Mesh::new(0);
+			mesh.name = name.to_string();
+			::std::mem::swap(&mut mesh, &mut self.mesh);
+			mesh.compute_bbox();
+			self.scene.meshes.push(mesh);
+		} else {
+			self.mesh.name = name.to_string();
+		}
+	}
+	
+	fn add_materials(&mut self, materials: Vec<Material>) {
+		for mut material in materials {
+			if let Some(ref path) = material.diffuse_map {
+				material.tex_id = match self.scene.textures.iter().position(|t| t.path() == Some(path.as_str())) {
+					Some(id) => id as i32,
+					None => {
+						self.scene.textures.push(SceneTexture::File(path.clone()));
+						self.scene.textures.len() as i32 - 1
+					},
+				};
+			}
+			self.scene.materials.push(material);
+		}
+	}
+	
+	//vertex of a face, line or point statement in the forms v, v/vt, v//vn or v/vt/vn
+	fn vertex(&self, token: &str, line: usize) -> RenderResult<(Vertex, bool)> {
+		let parts: Vec<&str> = token.split('/').collect();
+		let index: usize = resolve_index(parts[0], self.positions.len(), line)?;
+		
+		let mut vertex: Vertex = Vertex::new_init(self.positions[index].clone(), WHITE.clone(), Vec2::new_empty());
+		match (&self.colors[index], self.material) {
+			(&Some(ref color), _) => vertex.color.set_from_color(color),
+			(&None, Some(material)) => vertex.color.set_from_color(&self.scene.materials[material].diffuse),
+			_ => {},
+		}
+		
+		let has_tex_coord: bool = parts.len() > 1 && !parts[1].is_empty();
+		if has_tex_coord {
+			//obj has its origin bottom left, textures top left
+			let tex_coord: &Vec2 = &self.tex_coords[resolve_index(parts[1], self.tex_coords.len(), line)?];
+			vertex.tex_coord.x = tex_coord.x;
+			vertex.tex_coord.y = 1.0 - tex_coord.y;
+		}
+		if parts.len() > 2 && !parts[2].is_empty() {
+			vertex.normal.set_from(&self.normals[resolve_index(parts[2], self.normals.len(), line)?]);
+		}
+		Ok((vertex, has_tex_coord))
+	}
+	
+	//the material the vertices got their color from, None if any of them has its own color
+	fn color_material(&self, tokens: &[&str], line: usize) -> RenderResult<Option<usize>> {
+		for token in tokens {
+			let index: usize = resolve_index(token.split('/').next().unwrap_or(""), self.positions.len(), line)?;
+			if self.colors[index].is_some() {
+				return Ok(None);
+			}
+		}
+		Ok(self.material)
+	}
+	
+	fn face(&mut self, tokens: &[&str], line: usize) -> RenderResult<()> {
+		if tokens.len() < 3 {
+			return Err(invalid(line, "face with less than 3 vertices"));
+		}
+		let mut vertices: Vec<Vertex> = Vec::with_capacity(tokens.len());
+		let mut textured: bool = true;
+		for token in tokens {
+			let (vertex, has_tex_coord) = self.vertex(token, line)?;
+			textured &= has_tex_coord;
+			vertices.push(vertex);
+		}
+		
+		let tex_id: i32 = match self.material {
+			Some(material) if textured => self.scene.materials[material].tex_id,
+			_ => -1,
+		};
+		let material: Option<usize> = self.color_material(tokens, line)?;
+		let points: Vec<Vec3> = vertices.iter().map(|v| v.vec.clone()).collect();
+		for triangle in triangulate(&points) {
+			let shape_vertices: Vec<Vertex> = triangle.iter().map(|i| vertices[*i].clone()).collect();
+			let mut shape: Shape = Shape::new_init(tex_id, &shape_vertices);
+			shape.material = material;
+			self.mesh.shapes.push(shape);
+		}
+		Ok(())
+	}
+}
+
+//load_mtl is called for every mtllib file name and returns its materials
+pub fn read_obj<R: BufRead, F>(r: &mut R, mut load_mtl: F) -> RenderResult<Scene>
+	where F: FnMut(&str) -> RenderResult<Vec<Material>> {
+	let mut reader: ObjReader = ObjReader {
+		scene: Scene::new(1),
+		positions: Vec::new(),
+		colors: Vec::new(),
+		tex_coords: Vec::new(),
+		normals: Vec::new(),
+		mesh: Mesh::new(0),
+		material: None,
+	};
+	
+	for_each_line(r, |line, tokens| {
+		let args: &[&str] = &tokens[1..];
+		match tokens[0] {
+			"v" => {
+				let values: Vec<f32> = parse_floats(args, 3, line)?;
+				reader.positions.push(Vec3::new(values[0], values[1], values[2]));
+				reader.colors.push(if values.len() >= 6 {
+					let c: &[f32] = &values[values.len() - 3..];
+					Some(Color::new(c[0] * 255.0, c[1] * 255.0, c[2] * 255.0))
+				} else {
+					None
+				});
+			},
+			"vt" => {
+				let values: Vec<f32> = parse_floats(args, 1, line)?;
+				reader.tex_coords.push(Vec2::new(values[0], if values.len() > 1 { values[1] } else { 0.0 }));
+			},
+			"vn" => {
+				let values: Vec<f32> = parse_floats(args, 3, line)?;
+				reader.normals.push(Vec3::new(values[0], values[1], values[2]));
+			},
+			"f" => reader.face(args, line)?,
+			"l" => {
+				if args.len() < 2 {
+					return Err(invalid(line, "line with less than 2 vertices"));
+				}
+				for pair in args.windows(2) {
+					let start: Vertex = reader.vertex(pair[0], line)?.0;
+					let end: Vertex = reader.vertex(pair[1], line)?.0;
+					let mut shape: Shape = Shape::new_init(-1, &vec![start, end]);
+					shape.material = reader.color_material(pair, line)?;
+					reader.mesh.shapes.push(shape);
+				}
+			},
+			"p" => {
+				for token in args {
+					let point: Vertex = reader.vertex(token, line)?.0;
+					let mut shape: Shape = Shape::new_init(-1, &vec![point]);
+					shape.material = reader.color_material(&[*token], line)?;
+					reader.mesh.shapes.push(shape);
+				}
+			},
+			"o" | "g" => reader.finish_mesh(&args.join(" ")),
+			"mtllib" => {
+				for name in args {
+					let materials: Vec<Material> = load_mtl(name)?;
+					reader.add_materials(materials);
+				}
+			},
+			"usemtl" => {
+				let name: String = args.join(" ");
+				//unknown materials fall back to the default white
+				reader.material = reader.scene.materials.iter().position(|m| m.name == name);
+			},
+			_ => {},
+		}
+		Ok(())
+	})?;
+	
+	let name: String = String::new();
+	reader.finish_mesh(&name);
+	Ok(reader.scene)
+}
+
+fn key_of(values: &[f32]) -> Vec<u32> {
+	values.iter().map(|v| v.to_bits()).collect()
+}
+
+//returns the 1 based index of values, writes a new statement if they are not known yet
+fn weld<W: Write>(w: &mut W, known: &mut HashMap<Vec<u32>, usize>, statement: &str, values: &[f32]) -> RenderResult<usize> {
+	let key: Vec<u32> = key_of(values);
+	if let Some(index) = known.get(&key) {
+		return Ok(*index);
+	}
+	let index: usize = known.len() + 1;
+	let text: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+	writeln!(w, "{} {}", statement, text.join(" "))?;
+	known.insert(key, index);
+	Ok(index)
+}
+
+//texture ids in order of appearance and whether untextured shapes exist
+fn used_tex_ids(scene: &Scene) -> (Vec<i32>, bool) {
+	let mut tex_ids: Vec<i32> = Vec::new();
+	let mut untextured: bool = false;
+	for mesh in &scene.meshes {
+		for shape in &mesh.shapes {
+			if shape.tex_id < 0 {
+				untextured = true;
+			} else if !tex_ids.contains(&shape.tex_id) {
+				tex_ids.push(shape.tex_id);
+			}
+		}
+	}
+	(tex_ids, untextured)
+}
+
+fn material_name(scene: &Scene, tex_id: i32) -> String {
+	if tex_id < 0 {
+		return "default".to_string();
+	}
+	match scene.materials.iter().find(|m| m.tex_id == tex_id) {
+		Some(material) => material.name.clone(),
+		None => format!("texture_{}", tex_id),
+	}
+}
+
+//mtllib is the file name of the material library written by write_mtl, without it no materials are referenced
+pub fn write_obj<W: Write>(scene: &Scene, w: &mut W, mtllib: Option<&str>) -> RenderResult<()> {
+	if let Some(name) = mtllib {
+		writeln!(w, "mtllib {}", name)?;
+	}
+	
+	let mut positions: HashMap<Vec<u32>, usize> = HashMap::new();
+	let mut tex_coords: HashMap<Vec<u32>, usize> = HashMap::new();
+	let mut normals: HashMap<Vec<u32>, usize> = HashMap::new();
+	let mut material: Option<i32> = None;
+	
+	for (i, mesh) in scene.meshes.iter().enumerate() {
+		if mesh.name.is_empty() {
+			writeln!(w, "o mesh_{}", i)?;
+		} else {
+			writeln!(w, "o {}", mesh.name)?;
+		}
+		
+		//vertex data is written first, the elements of the mesh follow
+		let mut elements: Vec<u8> = Vec::new();
+		for shape in &mesh.shapes {
+			if mtllib.is_some() && material != Some(shape.tex_id) {
+				writeln!(elements, "usemtl {}", material_name(scene, shape.tex_id))?;
+				material = Some(shape.tex_id);
+			}
+			
+			let mut refs: Vec<String> = Vec::with_capacity(shape.vertices.len());
+			for vertex in &shape.vertices {
+				let c: &Color = &vertex.color;
+				let v: usize = weld(w, &mut positions, "v", &[vertex.vec.x, vertex.vec.y, vertex.vec.z, c.r / 255.0, c.g / 255.0, c.b / 255.0])?;
+				let vt: Option<usize> = if shape.tex_id >= 0 {
+					Some(weld(w, &mut tex_coords, "vt", &[vertex.tex_coord.x, 1.0 - vertex.tex_coord.y])?)
+				} else {
+					None
+				};
+				let n: &Vec3 = &vertex.normal;
+				let vn: Option<usize> = if n.x != 0.0 || n.y != 0.0 || n.z != 0.0 {
+					Some(weld(w, &mut normals, "vn", &[n.x, n.y, n.z])?)
+				} else {
+					None
+				};
+				refs.push(match (vt, vn) {
+					(Some(vt), Some(vn)) => format!("{}/{}/{}", v, vt, vn),
+					(Some(vt), None) => format!("{}/{}", v, vt),
+					(None, Some(vn)) => format!("{}//{}", v, vn),
+					(None, None) => v.to_string(),
+				});
+			}
+			
+			let statement: &str = match refs.len() {
+				1 => "p",
+				2 => "l",
+				_ => "f",
+			};
+			writeln!(elements, "{} {}", statement, refs.join(" "))?;
+		}
+		w.write_all(&elements)?;
+	}
+	Ok(())
+}
+
+//textures without known path are referenced as texture_<tex_id>.png
+pub fn write_mtl<W: Write>(scene: &Scene, w: &mut W) -> RenderResult<()> {
+	let (tex_ids, untextured) = used_tex_ids(scene);
+	if untextured {
+		writeln!(w, "newmtl default\nKd 1 1 1\n")?;
+	}
+	
+	for tex_id in tex_ids {
+		let name: String = material_name(scene, tex_id);
+		let default: Material = Material::new(&name);
+		let material: &Material = scene.materials.iter().find(|m| m.tex_id == tex_id).unwrap_or(&default);
+		let map: String = match material.diffuse_map {
+			Some(ref path) => path.clone(),
+			None => match scene.textures.get(tex_id as usize) {
+				Some(&SceneTexture::File(ref path)) => path.clone(),
+				_ => format!("texture_{}.png", tex_id),
+			},
+		};
+		
+		writeln!(w, "newmtl {}", name)?;
+		for &(statement, color) in [("Ka", &material.ambient), ("Kd", &material.diffuse), ("Ks", &material.specular)].iter() {
+			writeln!(w, "{} {} {} {}", statement, color.r / 255.0, color.g / 255.0, color.b / 255.0)?;
+		}
+		writeln!(w, "Ns {}\nd {}\nmap_Kd {}\n", material.shininess, material.opacity, map)?;
+	}
+	Ok(())
+}
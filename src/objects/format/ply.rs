@@ -0,0 +1,445 @@
+/*
+	PLY reader and writer for ASCII and binary little and big endian files. Vertices carry position,
+	normal, color (uchar 0 - 255 or float 0.0 - 1.0) and texture coordinates, faces of any size are
+	triangulated and edges become lines. Point shapes of meshes with faces or edges are kept in the element
+	point, a file without faces, edges and points is a point cloud and every vertex becomes a point shape.
+*/
+use std::io::{Read, Write};
+use std::collections::HashMap;
+use std::str::SplitWhitespace;
+use math::vec::vec2::*;
+use math::vec::vec3::*;
+use objects::shape::*;
+use objects::mesh::*;
+use objects::format::obj::triangulate;
+use color::*;
+use error::*;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PlyFormat {
+	Ascii,
+	BinaryLittleEndian,
+	BinaryBigEndian,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum PlyType {
+	Int8,
+	UInt8,
+	Int16,
+	UInt16,
+	Int32,
+	UInt32,
+	Float32,
+	Float64,
+}
+
+impl PlyType {
+	fn parse(name: &str) -> RenderResult<PlyType> {
+		match name {
+			"char" | "int8" => Ok(PlyType::Int8),
+			"uchar" | "uint8" => Ok(PlyType::UInt8),
+			"short" | "int16" => Ok(PlyType::Int16),
+			"ushort" | "uint16" => Ok(PlyType::UInt16),
+			"int" | "int32" => Ok(PlyType::Int32),
+			"uint" | "uint32" => Ok(PlyType::UInt32),
+			"float" | "float32" => Ok(PlyType::Float32),
+			"double" | "float64" => Ok(PlyType::Float64),
+			_ => Err(invalid(&format!("unknown property type {}", name))),
+		}
+	}
+	
+	fn size(&self) -> usize {
+		match *self {
+			PlyType::Int8 | PlyType::UInt8 => 1,
+			PlyType::Int16 | PlyType::UInt16 => 2,
+			PlyType::Int32 | PlyType::UInt32 | PlyType::Float32 => 4,
+			PlyType::Float64 => 8,
+		}
+	}
+}
+
+struct Property {
+	name: String,
+	value_type: PlyType,
+	list_type: Option<PlyType>, //type of the item count for list properties
+}
+
+struct Element {
+	name: String,
+	count: usize,
+	properties: Vec<Property>,
+}
+
+fn invalid(message: &str) -> RenderError {
+	RenderError::InvalidModel(format!("ply: {}", message))
+}
+
+//vertex indices are read as f64, negative and fractional values are no index
+fn vertex_index(value: f64) -> RenderResult<usize> {
+	if value >= 0.0 && value.fract() == 0.0 && value <= ::std::u32::MAX as f64 {
+		Ok(value as usize)
+	} else {
+		Err(invalid(&format!("invalid vertex index {}", value)))
+	}
+}
+
+//body values of ascii or binary files
+enum Values<'a> {
+	Ascii(SplitWhitespace<'a>),
+	Binary { data: &'a [u8], pos: usize, big_endian: bool },
+}
+
+impl<'a> Values<'a> {
+	fn next(&mut self, value_type: PlyType) -> RenderResult<f64> {
+		match *self {
+			Values::Ascii(ref mut tokens) => match tokens.next().map(|t| t.parse::<f64>()) {
+				Some(Ok(value)) => Ok(value),
+				_ => Err(invalid("invalid or missing value")),
+			},
+			Values::Binary { data, ref mut pos, big_endian } => {
+				let size: usize = value_type.size();
+				if data.len() < *pos + size {
+					return Err(invalid("unexpected end of data"));
+				}
+				let mut bits: u64 = 0;
+				for i in 0..size {
+					let byte: u64 = data[*pos + if big_endian { i } else { size - 1 - i }] as u64;
+					bits = (bits << 8) | byte;
+				}
+				*pos += size;
+				Ok(match value_type {
+					PlyType::Int8 => bits as u8 as i8 as f64,
+					PlyType::UInt8 => bits as u8 as f64,
+					PlyType::Int16 => bits as u16 as i16 as f64,
+					PlyType::UInt16 => bits as u16 as f64,
+					PlyType::Int32 => bits as u32 as i32 as f64,
+					PlyType::UInt32 => bits as u32 as f64,
+					PlyType::Float32 => f32::from_bits(bits as u32) as f64,
+					PlyType::Float64 => f64::from_bits(bits),
+				})
+			},
+		}
+	}
+}
+
+fn parse_header(header: &str) -> RenderResult<(PlyFormat, Vec<Element>)> {
+	let mut lines = header.lines().map(|l| l.trim());
+	if lines.next() != Some("ply") {
+		return Err(invalid("missing magic number"));
+	}
+	
+	let mut format: Option<PlyFormat> = None;
+	let mut elements: Vec<Element> = Vec::new();
+	for line in lines {
+		let tokens: Vec<&str> = line.split_whitespace().collect();
+		match tokens.first() {
+			Some(&"format") if tokens.len() >= 2 => {
+				format = Some(match tokens[1] {
+					"ascii" => PlyFormat::Ascii,
+					"binary_little_endian" => PlyFormat::BinaryLittleEndian,
+					"binary_big_endian" => PlyFormat::BinaryBigEndian,
+					_ => return Err(invalid(&format!("unknown format {}", tokens[1]))),
+				});
+			},
+			Some(&"element") if tokens.len() == 3 => {
+				let count: usize = match tokens[2].parse::<usize>() {
+					Ok(count) => count,
+					Err(_) => return Err(invalid(&format!("invalid element count {}", tokens[2]))),
+				};
+				elements.push(Element { name: tokens[1].to_string(), count, properties: Vec::new() });
+			},
+			Some(&"property") => {
+				let property: Property = if tokens.len() == 5 && tokens[1] == "list" {
+					Property { name: tokens[4].to_string(), value_type: PlyType::parse(tokens[3])?, list_type: Some(PlyType::parse(tokens[2])?) }
+				} else if tokens.len() == 3 {
+					Property { name: tokens[2].to_string(), value_type: PlyType::parse(tokens[1])?, list_type: None }
+				} else {
+					return Err(invalid(&format!("invalid property {}", line)));
+				};
+				match elements.last_mut() {
+					Some(element) => element.properties.push(property),
+					None => return Err(invalid("property before element")),
+				}
+			},
+			Some(&"comment") | Some(&"obj_info") | Some(&"end_header") | None => {},
+			_ => return Err(invalid(&format!("unknown header line {}", line))),
+		}
+	}
+	
+	match format {
+		Some(format) => Ok((format, elements)),
+		None => Err(invalid("missing format")),
+	}
+}
+
+pub fn read_ply<R: Read>(r: &mut R) -> RenderResult<Mesh> {
+	let mut data: Vec<u8> = Vec::new();
+	r.read_to_end(&mut data)?;
+	
+	//the header ends with the line end_header
+	let marker: &[u8] = b"end_header";
+	let end: usize = match data.windows(marker.len()).position(|w| w == marker) {
+		Some(pos) => pos + marker.len(),
+		None => return Err(invalid("missing end_header")),
+	};
+	let body_start: usize = match data[end..].iter().position(|b| *b == b'\n') {
+		Some(pos) => end + pos + 1,
+		None => data.len(),
+	};
+	let header: &str = match ::std::str::from_utf8(&data[..end]) {
+		Ok(header) => header,
+		Err(_) => return Err(invalid("header is not valid text")),
+	};
+	let (format, elements) = parse_header(header)?;
+	
+	let mut values: Values = match format {
+		PlyFormat::Ascii => match ::std::str::from_utf8(&data[body_start..]) {
+			Ok(text) => Values::Ascii(text.split_whitespace()),
+			Err(_) => return Err(invalid("ascii body is not valid text")),
+		},
+		_ => Values::Binary { data: &data[body_start..], pos: 0, big_endian: format == PlyFormat::BinaryBigEndian },
+	};
+	
+	let mut vertices: Vec<Vertex> = Vec::new();
+	let mut polygons: Vec<Vec<usize>> = Vec::new();
+	let mut edges: Vec<(usize, usize)> = Vec::new();
+	let mut points: Vec<usize> = Vec::new();
+	let mut row: Vec<f64> = Vec::new();
+	let mut list: Vec<f64> = Vec::new();
+	
+	for element in &elements {
+		let position = |name: &str| element.properties.iter().position(|p| p.name == name);
+		let xyz: [Option<usize>; 3] = [position("x"), position("y"), position("z")];
+		let normal: [Option<usize>; 3] = [position("nx"), position("ny"), position("nz")];
+		let rgb: [Option<usize>; 3] = [position("red").or(position("r")), position("green").or(position("g")), position("blue").or(position("b"))];
+		let uv: [Option<usize>; 2] = [position("s").or(position("u")).or(position("texture_u")), position("t").or(position("v")).or(position("texture_v"))];
+		let indices: Option<usize> = position("vertex_indices").or(position("vertex_index"));
+		let edge: [Option<usize>; 2] = [position("vertex1"), position("vertex2")];
+		let point: Option<usize> = position("vertex");
+		
+		for _i in 0..element.count {
+			row.clear();
+			list.clear();
+			for (p, property) in element.properties.iter().enumerate() {
+				match property.list_type {
+					Some(list_type) => {
+						let cnt: usize = values.next(list_type)? as usize;
+						for _j in 0..cnt {
+							let value: f64 = values.next(property.value_type)?;
+							if Some(p) == indices { list.push(value); }
+						}
+						row.push(cnt as f64);
+					},
+					None => row.push(values.next(property.value_type)?),
+				}
+			}
+			
+			let get = |index: Option<usize>| -> f32 { index.map_or(0.0, |i| row[i] as f32) };
+			match element.name.as_str() {
+				"vertex" => {
+					let mut vertex: Vertex = Vertex::new_init(Vec3::new(get(xyz[0]), get(xyz[1]), get(xyz[2])), WHITE.clone(), Vec2::new(get(uv[0]), get(uv[1])));
+					vertex.normal.set(get(normal[0]), get(normal[1]), get(normal[2]));
+					if let (Some(r), Some(g), Some(b)) = (rgb[0], rgb[1], rgb[2]) {
+						//integer colors are 0 - 255, float colors 0.0 - 1.0
+						let scale: f32 = match element.properties[r].value_type {
+							PlyType::Float32 | PlyType::Float64 => 255.0,
+							_ => 1.0,
+						};
+						vertex.color.set_from_rgb(row[r] as f32 * scale, row[g] as f32 * scale, row[b] as f32 * scale);
+					}
+					vertices.push(vertex);
+				},
+				"face" if indices.is_some() => {
+					let mut polygon: Vec<usize> = Vec::with_capacity(list.len());
+					for value in &list {
+						polygon.push(vertex_index(*value)?);
+					}
+					polygons.push(polygon);
+				},
+				"edge" => if let (Some(start), Some(end)) = (edge[0], edge[1]) {
+					edges.push((vertex_index(row[start])?, vertex_index(row[end])?));
+				},
+				"point" => if let Some(vertex) = point {
+					points.push(vertex_index(row[vertex])?);
+				},
+				_ => {},
+			}
+		}
+	}
+	
+	let mut mesh: Mesh = Mesh::new(polygons.len() + edges.len() + points.len());
+	let check = |index: usize| -> RenderResult<usize> {
+		if index < vertices.len() { Ok(index) } else { Err(invalid(&format!("vertex index {} out of range", index))) }
+	};
+	for polygon in &polygons {
+		if polygon.len() < 3 {
+			return Err(invalid(&format!("face with {} vertices", polygon.len())));
+		}
+		let mut points: Vec<Vec3> = Vec::with_capacity(polygon.len());
+		for index in polygon {
+			points.push(vertices[check(*index)?].vec.clone());
+		}
+		for triangle in triangulate(&points) {
+			let corners: Vec<Vertex> = triangle.iter().map(|i| vertices[polygon[*i]].clone()).collect();
+			mesh.shapes.push(Shape::new_init(-1, &corners));
+		}
+	}
+	for &(start, end) in &edges {
+		let line: Vec<Vertex> = vec![vertices[check(start)?].clone(), vertices[check(end)?].clone()];
+		mesh.shapes.push(Shape::new_init(-1, &line));
+	}
+	for point in &points {
+		mesh.shapes.push(Shape::new_init(-1, &vec![vertices[check(*point)?].clone()]));
+	}
+	if polygons.is_empty() && edges.is_empty() && points.is_empty() {
+		for vertex in vertices.iter() {
+			mesh.shapes.push(Shape::new_init(-1, &vec![vertex.clone()]));
+		}
+	}
+	
+	mesh.compute_bbox();
+	Ok(mesh)
+}
+
+//body writer for the three formats
+struct ValueWriter {
+	format: PlyFormat,
+	out: Vec<u8>,
+}
+
+impl ValueWriter {
+	fn write_f32(&mut self, value: f32) {
+		let bits: u32 = value.to_bits();
+		self.write_bytes(&[(bits >> 24) as u8, (bits >> 16) as u8, (bits >> 8) as u8, bits as u8], &value.to_string());
+	}
+	
+	fn write_u32(&mut self, value: u32) {
+		self.write_bytes(&[(value >> 24) as u8, (value >> 16) as u8, (value >> 8) as u8, value as u8], &value.to_string());
+	}
+	
+	fn write_u8(&mut self, value: u8) {
+		self.write_bytes(&[value], &value.to_string());
+	}
+	
+	//big endian bytes or the ascii text of a value
+	fn write_bytes(&mut self, bytes: &[u8], text: &str) {
+		match self.format {
+			PlyFormat::Ascii => {
+				if !self.out.is_empty() && *self.out.last().unwrap() != b'\n' {
+					self.out.push(b' ');
+				}
+				self.out.extend(text.as_bytes());
+			},
+			PlyFormat::BinaryBigEndian => self.out.extend(bytes),
+			PlyFormat::BinaryLittleEndian => self.out.extend(bytes.iter().rev()),
+		}
+	}
+	
+	fn end_element(&mut self) {
+		if self.format == PlyFormat::Ascii {
+			self.out.push(b'\n');
+		}
+	}
+}
+
+//equal vertices are written once, points only add their vertex unless faces or edges need the point element
+pub fn write_ply<W: Write>(mesh: &Mesh, w: &mut W, format: PlyFormat) -> RenderResult<()> {
+	let mut known: HashMap<Vec<u32>, u32> = HashMap::new();
+	let mut vertices: Vec<&Vertex> = Vec::new();
+	let mut faces: Vec<[u32; 3]> = Vec::new();
+	let mut edges: Vec<[u32; 2]> = Vec::new();
+	let mut points: Vec<u32> = Vec::new();
+	let mut with_normals: bool = false;
+	let mut with_uvs: bool = false;
+	
+	for shape in &mesh.shapes {
+		let mut indices: Vec<u32> = Vec::with_capacity(shape.vertices.len());
+		for vertex in &shape.vertices {
+			let (v, n, c, t) = (&vertex.vec, &vertex.normal, &vertex.color, &vertex.tex_coord);
+			with_normals |= n.x != 0.0 || n.y != 0.0 || n.z != 0.0;
+			with_uvs |= t.x != 0.0 || t.y != 0.0;
+			let key: Vec<u32> = [v.x, v.y, v.z, n.x, n.y, n.z, c.r, c.g, c.b, t.x, t.y].iter().map(|f| f.to_bits()).collect();
+			let next: u32 = vertices.len() as u32;
+			let index: u32 = *known.entry(key).or_insert(next);
+			if index == next {
+				vertices.push(vertex);
+			}
+			indices.push(index);
+		}
+		match indices.len() {
+			1 => points.push(indices[0]),
+			2 => edges.push([indices[0], indices[1]]),
+			3 => faces.push([indices[0], indices[1], indices[2]]),
+			cnt => return Err(RenderError::InvalidShape(cnt)),
+		}
+	}
+	
+	let mut header: String = format!("ply\nformat {} 1.0\n", match format {
+		PlyFormat::Ascii => "ascii",
+		PlyFormat::BinaryLittleEndian => "binary_little_endian",
+		PlyFormat::BinaryBigEndian => "binary_big_endian",
+	});
+	header.push_str(&format!("element vertex {}\nproperty float x\nproperty float y\nproperty float z\n", vertices.len()));
+	if with_normals {
+		header.push_str("property float nx\nproperty float ny\nproperty float nz\n");
+	}
+	header.push_str("property uchar red\nproperty uchar green\nproperty uchar blue\n");
+	if with_uvs {
+		header.push_str("property float s\nproperty float t\n");
+	}
+	if !faces.is_empty() {
+		header.push_str(&format!("element face {}\nproperty list uchar uint vertex_indices\n", faces.len()));
+	}
+	if !edges.is_empty() {
+		header.push_str(&format!("element edge {}\nproperty uint vertex1\nproperty uint vertex2\n", edges.len()));
+	}
+	//without faces and edges the vertices alone are the point cloud
+	if faces.is_empty() && edges.is_empty() {
+		points.clear();
+	}
+	if !points.is_empty() {
+		header.push_str(&format!("element point {}\nproperty uint vertex\n", points.len()));
+	}
+	header.push_str("end_header\n");
+	
+	let mut body: ValueWriter = ValueWriter { format, out: Vec::new() };
+	for vertex in &vertices {
+		body.write_f32(vertex.vec.x);
+		body.write_f32(vertex.vec.y);
+		body.write_f32(vertex.vec.z);
+		if with_normals {
+			body.write_f32(vertex.normal.x);
+			body.write_f32(vertex.normal.y);
+			body.write_f32(vertex.normal.z);
+		}
+		body.write_u8(vertex.color.r.max(0.0).min(255.0) as u8);
+		body.write_u8(vertex.color.g.max(0.0).min(255.0) as u8);
+		body.write_u8(vertex.color.b.max(0.0).min(255.0) as u8);
+		if with_uvs {
+			body.write_f32(vertex.tex_coord.x);
+			body.write_f32(vertex.tex_coord.y);
+		}
+		body.end_element();
+	}
+	for face in &faces {
+		body.write_u8(3);
+		for index in face {
+			body.write_u32(*index);
+		}
+		body.end_element();
+	}
+	for edge in &edges {
+		body.write_u32(edge[0]);
+		body.write_u32(edge[1]);
+		body.end_element();
+	}
+	for point in &points {
+		body.write_u32(*point);
+		body.end_element();
+	}
+	
+	w.write_all(header.as_bytes())?;
+	w.write_all(&body.out)?;
+	Ok(())
+}
@@ -0,0 +1,172 @@
+/*
+	STL reader and writer for ASCII and binary files. Facet normals are stored in the vertex normals,
+	missing (zero) normals are computed from the winding. Only triangle shapes can be written.
+*/
+use std::io::{Read, Write};
+use std::str::SplitWhitespace;
+use math::vec::vec2::*;
+use math::vec::vec3::*;
+use objects::shape::*;
+use objects::mesh::*;
+use color::*;
+use error::*;
+
+fn invalid(message: &str) -> RenderError {
+	RenderError::InvalidModel(format!("stl: {}", message))
+}
+
+fn read_f32(data: &[u8], pos: usize) -> f32 {
+	f32::from_bits((data[pos] as u32) | ((data[pos + 1] as u32) << 8) | ((data[pos + 2] as u32) << 16) | ((data[pos + 3] as u32) << 24))
+}
+
+fn write_f32(out: &mut Vec<u8>, value: f32) {
+	let bits: u32 = value.to_bits();
+	out.extend(&[bits as u8, (bits >> 8) as u8, (bits >> 16) as u8, (bits >> 24) as u8]);
+}
+
+fn push_facet(mesh: &mut Mesh, normal: &Vec3, corners: &[Vec3]) {
+	let vertices: Vec<Vertex> = corners.iter().map(|c| Vertex::new_init(c.clone(), WHITE.clone(), Vec2::new_empty())).collect();
+	let mut shape: Shape = Shape::new_init(-1, &vertices);
+	let mut facet_normal: Vec3 = normal.clone();
+	if facet_normal.x == 0.0 && facet_normal.y == 0.0 && facet_normal.z == 0.0 {
+		shape.face_normal(&mut facet_normal);
+	}
+	for vertex in shape.vertices.iter_mut() {
+		vertex.normal.set_from(&facet_normal);
+	}
+	mesh.shapes.push(shape);
+}
+
+fn read_binary(data: &[u8], count: usize) -> Mesh {
+	let mut mesh: Mesh = Mesh::new(count);
+	let mut corners: Vec<Vec3> = Vec::with_capacity(3);
+	for i in 0..count {
+		let facet: usize = 84 + i * 50;
+		let value = |n: usize| -> f32 { read_f32(data, facet + n * 4) };
+		corners.clear();
+		for corner in 1..4 {
+			corners.push(Vec3::new(value(corner * 3), value(corner * 3 + 1), value(corner * 3 + 2)));
+		}
+		push_facet(&mut mesh, &Vec3::new(value(0), value(1), value(2)), &corners);
+	}
+	mesh
+}
+
+fn next_f32(tokens: &mut SplitWhitespace) -> RenderResult<f32> {
+	match tokens.next().map(|t| t.parse::<f32>()) {
+		Some(Ok(value)) => Ok(value),
+		_ => Err(invalid("invalid number")),
+	}
+}
+
+fn read_ascii(text: &str) -> RenderResult<Mesh> {
+	let mut mesh: Mesh = Mesh::new(0);
+	let mut normal: Vec3 = Vec3::new_empty();
+	let mut corners: Vec<Vec3> = Vec::with_capacity(3);
+	
+	//the name is the rest of the solid line
+	let first_line: &str = text.lines().next().unwrap_or("");
+	mesh.name = first_line["solid".len()..].trim().to_string();
+	let mut tokens: SplitWhitespace = text[first_line.len()..].split_whitespace();
+	
+	while let Some(token) = tokens.next() {
+		match token {
+			"facet" => {
+				if tokens.next() != Some("normal") {
+					return Err(invalid("facet without normal"));
+				}
+				let (x, y, z) = (next_f32(&mut tokens)?, next_f32(&mut tokens)?, next_f32(&mut tokens)?);
+				normal.set(x, y, z);
+				corners.clear();
+			},
+			"vertex" => {
+				let (x, y, z) = (next_f32(&mut tokens)?, next_f32(&mut tokens)?, next_f32(&mut tokens)?);
+				corners.push(Vec3::new(x, y, z));
+			},
+			"endfacet" => {
+				if corners.len() != 3 {
+					return Err(invalid(&format!("facet with {} vertices", corners.len())));
+				}
+				push_facet(&mut mesh, &normal, &corners);
+			},
+			"endsolid" => break,
+			_ => {},
+		}
+	}
+	Ok(mesh)
+}
+
+//binary files are detected by their size, some exporters start them with "solid" too
+pub fn read_stl<R: Read>(r: &mut R) -> RenderResult<Mesh> {
+	let mut data: Vec<u8> = Vec::new();
+	r.read_to_end(&mut data)?;
+	
+	let mut mesh: Mesh = if data.len() >= 84 && {
+		let count: usize = (data[80] as usize) | ((data[81] as usize) << 8) | ((data[82] as usize) << 16) | ((data[83] as usize) << 24);
+		84 + count * 50 == data.len()
+	} {
+		read_binary(&data, (data.len() - 84) / 50)
+	} else if data.starts_with(b"solid") {
+		match ::std::str::from_utf8(&data) {
+			Ok(text) => read_ascii(text)?,
+			Err(_) => return Err(invalid("ascii file is not valid utf-8")),
+		}
+	} else {
+		return Err(invalid("neither ascii nor binary stl"));
+	};
+	
+	mesh.compute_bbox();
+	Ok(mesh)
+}
+
+fn check_triangles(mesh: &Mesh) -> RenderResult<()> {
+	for (i, shape) in mesh.shapes.iter().enumerate() {
+		if shape.vertices.len() != 3 {
+			return Err(invalid(&format!("shape {} has {} vertices, only triangles can be written", i, shape.vertices.len())));
+		}
+	}
+	Ok(())
+}
+
+pub fn write_stl<W: Write>(mesh: &Mesh, w: &mut W, ascii: bool) -> RenderResult<()> {
+	check_triangles(mesh)?;
+	let mut normal: Vec3 = Vec3::new_empty();
+	let name: &str = if mesh.name.is_empty() { "mesh" } else { &mesh.name };
+	
+	if ascii {
+		let mut out: String = format!("solid {}\n", name);
+		for shape in &mesh.shapes {
+			shape.face_normal(&mut normal);
+			out.push_str(&format!("  facet normal {:e} {:e} {:e}\n    outer loop\n", normal.x, normal.y, normal.z));
+			for vertex in &shape.vertices {
+				out.push_str(&format!("      vertex {:e} {:e} {:e}\n", vertex.vec.x, vertex.vec.y, vertex.vec.z));
+			}
+			out.push_str("    endloop\n  endfacet\n");
+		}
+		out.push_str(&format!("endsolid {}\n", name));
+		w.write_all(out.as_bytes())?;
+		return Ok(());
+	}
+	
+	//the header must not start with solid
+	let mut out: Vec<u8> = Vec::with_capacity(84 + mesh.shapes.len() * 50);
+	let header: String = format!("binary stl {}", name);
+	out.extend(header.as_bytes().iter().take(80));
+	out.resize(80, 0);
+	let count: u32 = mesh.shapes.len() as u32;
+	out.extend(&[count as u8, (count >> 8) as u8, (count >> 16) as u8, (count >> 24) as u8]);
+	for shape in &mesh.shapes {
+		shape.face_normal(&mut normal);
+		for value in &[normal.x, normal.y, normal.z] {
+			write_f32(&mut out, *value);
+		}
+		for vertex in &shape.vertices {
+			write_f32(&mut out, vertex.vec.x);
+			write_f32(&mut out, vertex.vec.y);
+			write_f32(&mut out, vertex.vec.z);
+		}
+		out.extend(&[0, 0]);
+	}
+	w.write_all(&out)?;
+	Ok(())
+}
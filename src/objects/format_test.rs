@@ -0,0 +1,601 @@
+use objects::format::obj::*;
+use objects::format::stl::*;
+use objects::format::ply::*;
+use objects::format::gltf::*;
+use objects::format::json::*;
+use objects::material::*;
+use objects::scene::*;
+use objects::shape::*;
+use objects::mesh::*;
+use objects::node::*;
+use objects::skin::*;
+use texture::*;
+use texture::format::png::*;
+use math::vec::vec3::*;
+use error::*;
+use color::*;
+use std::io::Write;
+use std::fs::File;
+
+static TEST_MTL: &'static str = "
+# two materials
+newmtl red
+Kd 1.0 0.0 0.0
+Ns 10
+d -halo 0.5
+
+newmtl textured
+Kd 0.5
+map_Kd -s 1 1 1 -clamp on textures/stone wall.png
+";
+
+static TEST_OBJ: &'static str = "
+mtllib test.mtl
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+v 0 0 1 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+
+o quad
+usemtl textured
+f 1/1/1 2/2/1 3/3/1 4/4/1
+
+g red_part
+usemtl red
+f -5 -4 \\
+  -1
+l 1 2 3
+";
+
+fn triangle_area(a: &Vec3, b: &Vec3, c: &Vec3) -> f32 {
+	((b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)) * 0.5
+}
+
+#[test]
+fn obj_read_groups_and_materials() {
+	let mut mtl_names: Vec<String> = Vec::new();
+	let scene: Scene = read_obj(&mut TEST_OBJ.as_bytes(), |name| {
+		mtl_names.push(name.to_string());
+		read_mtl(&mut TEST_MTL.as_bytes())
+	}).unwrap();
+	
+	assert_eq!(mtl_names, vec!["test.mtl".to_string()]);
+	assert_eq!(scene.materials.len(), 2);
+	assert_eq!(scene.materials[0].diffuse == RED, true);
+	assert_eq!(scene.materials[0].shininess, 10.0);
+	assert_eq!(scene.materials[0].opacity, 0.5);
+	assert_eq!(scene.materials[1].diffuse_map, Some("textures/stone wall.png".to_string()));
+	assert_eq!(scene.textures.len(), 1);
+	assert_eq!(scene.textures[0].path(), Some("textures/stone wall.png"));
+	
+	assert_eq!(scene.meshes.len(), 2);
+	assert_eq!(scene.meshes[0].name, "quad");
+	assert_eq!(scene.meshes[0].shapes.len(), 2);
+	let shape: &Shape = &scene.meshes[0].shapes[0];
+	assert_eq!(shape.tex_id, 0);
+	assert_eq!(shape.material, Some(1));
+	assert_eq!(shape.vertices[0].color == Color::new(127.5, 127.5, 127.5), true);
+	//texture coordinates are flipped to a top left origin
+	for vertex in &shape.vertices {
+		assert_eq!(vertex.tex_coord.y, 1.0 - vertex.vec.y);
+	}
+	assert_eq!(shape.vertices[0].normal == Vec3::new(0.0, 0.0, 1.0), true);
+	
+	assert_eq!(scene.meshes[1].name, "red_part");
+	assert_eq!(scene.meshes[1].shapes.len(), 3);
+	let shape: &Shape = &scene.meshes[1].shapes[0];
+	assert_eq!(shape.tex_id, -1);
+	assert_eq!(shape.vertices[0].color == RED, true);
+	//vertex colors win over the material
+	assert_eq!(shape.vertices[2].color == LIME, true);
+	assert_eq!(shape.vertices[2].vec == Vec3::new(0.0, 0.0, 1.0), true);
+	assert_eq!(shape.material, None);
+	assert_eq!(scene.meshes[1].shapes[1].vertices.len(), 2);
+	assert_eq!(scene.meshes[1].shapes[1].material, Some(0));
+	assert_eq!(scene.meshes[1].bbox.created, true);
+}
+
+#[test]
+fn obj_triangulate_concave_polygon() {
+	//L shape, a fan from the first vertex would leave the polygon
+	let points: Vec<Vec3> = vec![Vec3::new(2.0, 1.0, 0.0), Vec3::new(1.0, 1.0, 0.0), Vec3::new(1.0, 2.0, 0.0),
+								 Vec3::new(0.0, 2.0, 0.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(2.0, 0.0, 0.0)];
+	let triangles: Vec<[usize; 3]> = triangulate(&points);
+	assert_eq!(triangles.len(), 4);
+	
+	let mut area: f32 = 0.0;
+	for t in &triangles {
+		let a: f32 = triangle_area(&points[t[0]], &points[t[1]], &points[t[2]]);
+		assert_eq!(a > 0.0, true);
+		area += a;
+	}
+	assert_eq!(area, 3.0);
+}
+
+#[test]
+fn obj_invalid_index() {
+	match read_obj(&mut "v 0 0 0\nv 1 0 0\nf 1 2 3\n".as_bytes(), |_| Ok(Vec::new())) {
+		Err(RenderError::InvalidModel(msg)) => assert_eq!(msg.starts_with("line 3"), true),
+		_ => panic!("index out of range must be rejected"),
+	}
+	match read_obj(&mut "v 0 0 0\nv 1 0 0\nf 1 2 -9223372036854775808\n".as_bytes(), |_| Ok(Vec::new())) {
+		Err(RenderError::InvalidModel(_)) => {},
+		_ => panic!("i64::MIN must be rejected"),
+	}
+	match read_mtl(&mut "Kd 1 1 1\n".as_bytes()) {
+		Err(RenderError::InvalidModel(_)) => {},
+		_ => panic!("material statement without newmtl must be rejected"),
+	}
+}
+
+#[test]
+fn obj_load_resolves_paths() {
+	let dir = ::std::env::temp_dir().join("renderer_obj_load");
+	::std::fs::create_dir_all(dir.join("materials")).unwrap();
+	File::create(dir.join("model.obj")).unwrap().write_all(b"mtllib materials/model.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl m\nf 1 2 3\n").unwrap();
+	File::create(dir.join("materials/model.mtl")).unwrap().write_all(b"newmtl m\nmap_Kd tex.png\n").unwrap();
+	
+	let scene: Scene = Scene::load_obj(dir.join("model.obj")).unwrap();
+	assert_eq!(scene.meshes[0].shapes.len(), 1);
+	let material: &Material = &scene.materials[0];
+	assert_eq!(material.diffuse_map, Some(dir.join("materials").join("tex.png").to_string_lossy().into_owned()));
+	
+	match scene.load_textures() {
+		Err(RenderError::Io(_)) => {},
+		_ => panic!("missing texture must be reported"),
+	}
+}
+
+#[test]
+fn obj_write_welds_vertices() {
+	let mut scene: Scene = Scene::new(1);
+	scene.meshes.push(MeshBuilder::cube(&Vec3::new_empty(), 1.0));
+	scene.meshes[0].compute_bbox();
+	scene.meshes[0].color_by_bbox();
+	scene.meshes[0].name = "cube".to_string();
+	
+	let mut data: Vec<u8> = Vec::new();
+	write_obj(&scene, &mut data, None).unwrap();
+	let text: String = String::from_utf8(data).unwrap();
+	assert_eq!(text.lines().filter(|l| l.starts_with("v ")).count(), 8);
+	assert_eq!(text.lines().filter(|l| l.starts_with("f ")).count(), 12);
+	assert_eq!(text.contains("usemtl"), false);
+	
+	let loaded: Scene = read_obj(&mut text.as_bytes(), |_| Ok(Vec::new())).unwrap();
+	assert_eq!(loaded.meshes[0].name, "cube");
+	let (original, read) = (&scene.meshes[0].shapes, &loaded.meshes[0].shapes);
+	assert_eq!(read.len(), original.len());
+	for (a, b) in original.iter().zip(read.iter()) {
+		for (va, vb) in a.vertices.iter().zip(b.vertices.iter()) {
+			assert_eq!(va.vec == vb.vec, true);
+			assert_eq!((va.color.r - vb.color.r).abs() < 0.0001, true);
+		}
+	}
+}
+
+#[test]
+fn obj_write_materials_for_textures() {
+	let mut scene: Scene = Scene::new(2);
+	scene.meshes.push(MeshBuilder::cube(&Vec3::new_empty(), 1.0));
+	for shape in scene.meshes[0].shapes.iter_mut() {
+		shape.tex_id = 0;
+		for (i, vertex) in shape.vertices.iter_mut().enumerate() {
+			vertex.tex_coord.x = i as f32 * 0.5;
+			vertex.tex_coord.y = 0.25;
+		}
+	}
+	scene.meshes.push(MeshBuilder::triangle(&Vec3::new(0.0, 0.0, 0.0), &Vec3::new(1.0, 0.0, 0.0), &Vec3::new(0.0, 1.0, 0.0)));
+	
+	let mut obj: Vec<u8> = Vec::new();
+	write_obj(&scene, &mut obj, Some("scene.mtl")).unwrap();
+	let mut mtl: Vec<u8> = Vec::new();
+	write_mtl(&scene, &mut mtl).unwrap();
+	
+	let materials: Vec<Material> = read_mtl(&mut &mtl[..]).unwrap();
+	assert_eq!(materials.len(), 2);
+	assert_eq!(materials[1].name, "texture_0");
+	assert_eq!(materials[1].diffuse_map, Some("texture_0.png".to_string()));
+	
+	let loaded: Scene = read_obj(&mut &obj[..], |name| {
+		assert_eq!(name, "scene.mtl");
+		Ok(materials.clone())
+	}).unwrap();
+	assert_eq!(loaded.meshes.len(), 2);
+	let shape: &Shape = &loaded.meshes[0].shapes[0];
+	assert_eq!(shape.tex_id, 0);
+	for (a, b) in scene.meshes[0].shapes[0].vertices.iter().zip(shape.vertices.iter()) {
+		assert_eq!(a.tex_coord.x == b.tex_coord.x && a.tex_coord.y == b.tex_coord.y, true);
+	}
+	assert_eq!(loaded.meshes[1].shapes[0].tex_id, -1);
+}
+
+#[test]
+fn stl_roundtrip_ascii_and_binary() {
+	let mut mesh: Mesh = MeshBuilder::block(&Vec3::new(0.5, 0.25, 0.0), 1.0, 0.5, 2.0, 2, 1, 3);
+	mesh.name = "block".to_string();
+	
+	for ascii in [true, false].iter() {
+		let mut data: Vec<u8> = Vec::new();
+		write_stl(&mesh, &mut data, *ascii).unwrap();
+		if !*ascii {
+			assert_eq!(data.len(), 84 + mesh.shapes.len() * 50);
+			assert_eq!(data.starts_with(b"solid"), false);
+		}
+		
+		let loaded: Mesh = read_stl(&mut &data[..]).unwrap();
+		assert_eq!(loaded.shapes.len(), mesh.shapes.len());
+		assert_eq!(loaded.bbox.created, true);
+		let mut normal: Vec3 = Vec3::new_empty();
+		for (a, b) in mesh.shapes.iter().zip(loaded.shapes.iter()) {
+			a.face_normal(&mut normal);
+			assert_eq!(b.vertices[0].normal == normal, true);
+			for (va, vb) in a.vertices.iter().zip(b.vertices.iter()) {
+				assert_eq!(va.vec == vb.vec, true);
+			}
+		}
+		if *ascii {
+			assert_eq!(loaded.name, "block");
+		}
+	}
+}
+
+#[test]
+fn stl_read_ascii_without_normals() {
+	let text: &str = "solid part\n facet normal 0 0 0\n  outer loop\n   vertex 0 0 0\n   vertex 1 0 0\n   vertex 0 1 0\n  endloop\n endfacet\nendsolid part\n";
+	let mesh: Mesh = read_stl(&mut text.as_bytes()).unwrap();
+	assert_eq!(mesh.name, "part");
+	assert_eq!(mesh.shapes[0].vertices[2].normal == Vec3::new(0.0, 0.0, 1.0), true);
+	
+	match read_stl(&mut "solid x\n facet normal 0 0 1\n vertex 0 0 0\n endfacet\n".as_bytes()) {
+		Err(RenderError::InvalidModel(_)) => {},
+		_ => panic!("facet with one vertex must be rejected"),
+	}
+}
+
+#[test]
+fn stl_write_rejects_points_and_lines() {
+	let mut mesh: Mesh = MeshBuilder::cube(&Vec3::new_empty(), 1.0);
+	mesh.shapes.push(Shape::new_line(&Vec3::new_empty(), &Vec3::new(1.0, 0.0, 0.0)));
+	
+	let mut data: Vec<u8> = Vec::new();
+	match write_stl(&mesh, &mut data, false) {
+		Err(RenderError::InvalidModel(msg)) => assert_eq!(msg.contains("shape 12 has 2 vertices"), true),
+		_ => panic!("lines must be rejected"),
+	}
+	assert_eq!(data.len(), 0);
+}
+
+#[test]
+fn ply_roundtrip_all_formats() {
+	let mut mesh: Mesh = MeshBuilder::cube(&Vec3::new_empty(), 1.0);
+	mesh.compute_bbox();
+	mesh.color_by_bbox();
+	for shape in mesh.shapes.iter_mut() {
+		for vertex in shape.vertices.iter_mut() {
+			vertex.color.r *= 255.0;
+			vertex.color.g = 200.0;
+			vertex.color.b = 7.0;
+			vertex.tex_coord.x = vertex.vec.x + 0.5;
+		}
+	}
+	mesh.shapes.push(Shape::new_line(&Vec3::new(-0.5, -0.5, -0.5), &Vec3::new(2.0, 0.0, 0.0)));
+	mesh.shapes.push(Shape::new_point(&Vec3::new(3.0, 3.0, 3.0)));
+	
+	for format in [PlyFormat::Ascii, PlyFormat::BinaryLittleEndian, PlyFormat::BinaryBigEndian].iter() {
+		let mut data: Vec<u8> = Vec::new();
+		write_ply(&mesh, &mut data, *format).unwrap();
+		let loaded: Mesh = read_ply(&mut &data[..]).unwrap();
+		assert_eq!(loaded.shapes.len(), 14);
+		for (a, b) in mesh.shapes.iter().zip(loaded.shapes.iter()) {
+			assert_eq!(a.vertices.len(), b.vertices.len());
+			for (va, vb) in a.vertices.iter().zip(b.vertices.iter()) {
+				assert_eq!(va.vec == vb.vec, true);
+				assert_eq!(va.tex_coord.x == vb.tex_coord.x && va.tex_coord.y == vb.tex_coord.y, true);
+				assert_eq!(va.color.r.floor() == vb.color.r && va.color.g == vb.color.g && va.color.b == vb.color.b, true);
+			}
+		}
+	}
+}
+
+#[test]
+fn ply_read_point_cloud_and_polygons() {
+	let cloud: &str = "ply\nformat ascii 1.0\ncomment scan\nelement vertex 3\nproperty float x\nproperty float y\nproperty float z\n\
+					   property float red\nproperty float green\nproperty float blue\nend_header\n\
+					   0 0 0 1 0 0\n1 0 0 0 1 0\n0 1 0 0 0 1\n";
+	let mesh: Mesh = read_ply(&mut cloud.as_bytes()).unwrap();
+	assert_eq!(mesh.shapes.len(), 3);
+	assert_eq!(mesh.shapes[0].vertices.len(), 1);
+	assert_eq!(mesh.shapes[1].vertices[0].color == LIME, true);
+	
+	//binary big endian pentagon with a double coordinate and an extra element
+	let mut data: Vec<u8> = b"ply\nformat binary_big_endian 1.0\nelement vertex 5\nproperty double x\nproperty short y\nproperty uchar z\n\
+							  element face 1\nproperty list uchar int vertex_indices\nproperty uchar flags\nelement unknown 1\nproperty int value\nend_header\n".to_vec();
+	for &(x, y) in [(0.0f64, 0i16), (2.0, 0), (2.0, 2), (1.0, 3), (0.0, 2)].iter() {
+		let bits: u64 = x.to_bits();
+		for i in 0..8 { data.push((bits >> (56 - i * 8)) as u8); }
+		data.extend(&[(y >> 8) as u8, y as u8, 1]);
+	}
+	data.push(5);
+	for i in 0..5u8 { data.extend(&[0, 0, 0, i]); }
+	data.extend(&[9, 0, 0, 0, 1]);
+	
+	let mesh: Mesh = read_ply(&mut &data[..]).unwrap();
+	assert_eq!(mesh.shapes.len(), 3);
+	assert_eq!(mesh.shapes[0].vertices[0].vec.z, 1.0);
+	assert_eq!(mesh.bbox.max.y, 3.0);
+	
+	let short: &[u8] = &data[..data.len() - 3];
+	match read_ply(&mut &short[..]) {
+		Err(RenderError::InvalidModel(_)) => {},
+		_ => panic!("truncated data must be rejected"),
+	}
+	
+	let negative: &str = "ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\nproperty float y\nproperty float z\n\
+						  element face 1\nproperty list uchar int vertex_indices\nend_header\n0 0 0\n1 0 0\n0 1 0\n3 0 1 -1\n";
+	match read_ply(&mut negative.as_bytes()) {
+		Err(RenderError::InvalidModel(_)) => {},
+		_ => panic!("negative indices must be rejected"),
+	}
+}
+
+fn base64(data: &[u8]) -> String {
+	let table: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+	let mut out: String = String::new();
+	for chunk in data.chunks(3) {
+		let bits: u32 = chunk.iter().enumerate().fold(0, |bits, (i, b)| bits | (*b as u32) << (16 - i * 8));
+		for i in 0..4 {
+			out.push(if i <= chunk.len() { table[(bits >> (18 - i * 6)) as usize & 63] as char } else { '=' });
+		}
+	}
+	out
+}
+
+fn push_f32(data: &mut Vec<u8>, values: &[f32]) {
+	for value in values {
+		let bits: u32 = value.to_bits();
+		data.extend(&[bits as u8, (bits >> 8) as u8, (bits >> 16) as u8, (bits >> 24) as u8]);
+	}
+}
+
+fn push_u32(data: &mut Vec<u8>, value: u32) {
+	data.extend(&[value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]);
+}
+
+#[test]
+fn json_values_and_errors() {
+	let value: JsonValue = parse_json(b"\xef\xbb\xbf { \"a\": [1, -2.5e1, true, null], \"b\": \"x\\\"\\u00e9\\ud83d\\ude00\\n\" }").unwrap();
+	assert_eq!(value.get("a").members().len(), 4);
+	assert_eq!(value.get("a").members()[1].as_f64(), Some(-25.0));
+	assert_eq!(value.get("a").members()[2].as_bool(), Some(true));
+	assert_eq!(value.get("a").members()[3].is_null(), true);
+	assert_eq!(value.get("b").as_str(), Some("x\"\u{e9}\u{1f600}\n"));
+	assert_eq!(value.get("missing").get("deeper").is_null(), true);
+	
+	for text in ["{\"a\": 1,}", "[1 2]", "\"open", "{} {}", "-"].iter() {
+		match parse_json(text.as_bytes()) {
+			Err(RenderError::InvalidModel(_)) => {},
+			_ => panic!("{} must be rejected", text),
+		}
+	}
+}
+
+#[test]
+fn json_rejects_deep_nesting() {
+	let nested = |depth: usize| format!("{}1{}", "[".repeat(depth), "]".repeat(depth));
+	assert_eq!(parse_json(nested(128).as_bytes()).is_ok(), true);
+	for depth in [129, 100000].iter() {
+		match parse_json(nested(*depth).as_bytes()) {
+			Err(RenderError::InvalidModel(_)) => {},
+			_ => panic!("{} nested arrays must be rejected", depth),
+		}
+	}
+	match parse_json("{\"a\": ".repeat(100000).as_bytes()) {
+		Err(RenderError::InvalidModel(_)) => {},
+		_ => panic!("deeply nested objects must be rejected"),
+	}
+}
+
+#[test]
+fn gltf_read_hierarchy_materials_and_camera() {
+	let mut buffer: Vec<u8> = Vec::new();
+	push_f32(&mut buffer, &[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+	buffer.extend(&[0, 0, 1, 0, 2, 0, 0, 0]);
+	let gltf: String = format!(r#"{{
+		"asset": {{"version": "2.0"}}, "scene": 0, "scenes": [{{"nodes": [0]}}],
+		"nodes": [{{"name": "root", "translation": [1, 0, 0], "children": [1, 2]}},
+				  {{"name": "tri", "scale": [2, 2, 2], "mesh": 0}},
+				  {{"camera": 0, "matrix": [1,0,0,0, 0,1,0,0, 0,0,1,0, 0,0,5,1]}}],
+		"cameras": [{{"type": "perspective", "perspective": {{"yfov": 1.0, "znear": 0.1, "zfar": 100}}}}],
+		"meshes": [{{"name": "triangle", "primitives": [{{"attributes": {{"POSITION": 0}}, "indices": 1, "material": 0}}]}}],
+		"materials": [{{"name": "red", "pbrMetallicRoughness": {{"baseColorFactor": [1, 0, 0, 0.5], "metallicFactor": 0}}}}],
+		"buffers": [{{"byteLength": 44, "uri": "data:application/octet-stream;base64,{}"}}],
+		"bufferViews": [{{"buffer": 0, "byteLength": 36}}, {{"buffer": 0, "byteOffset": 36, "byteLength": 6}}],
+		"accessors": [{{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3"}},
+					  {{"bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR"}}]
+	}}"#, base64(&buffer));
+	
+	let scene: Scene = read_gltf(&mut gltf.as_bytes(), |uri| panic!("unexpected uri {}", uri), |uri| uri.to_string()).unwrap();
+	assert_eq!(scene.meshes.len(), 1);
+	assert_eq!(scene.meshes[0].name, "triangle");
+	let vertices: &Vec<Vertex> = &scene.meshes[0].shapes[0].vertices;
+	assert_eq!(vertices[1].vec == Vec3::new(1.0, 0.0, 0.0), true);
+	assert_eq!(vertices[2].vec == Vec3::new(0.0, 1.0, 0.0), true);
+	assert_eq!(vertices[0].color == RED, true);
+	assert_eq!(scene.meshes[0].shapes[0].material, Some(0));
+	assert_eq!(scene.materials[0].name, "red");
+	assert_eq!(scene.materials[0].opacity, 0.5);
+	
+	assert_eq!(scene.nodes.len(), 1);
+	assert_eq!(scene.nodes[0].transform._14, 1.0);
+	assert_eq!(scene.nodes[0].children.len(), 2);
+	assert_eq!(scene.nodes[0].children[0].mesh, Some(0));
+	assert_eq!(scene.nodes[0].children[0].transform._11, 2.0);
+	let mut world: Vec<Vec3> = Vec::new();
+	scene.visit_nodes(|node, transform| if node.mesh == Some(0) {
+		let mut vec: Vec3 = Vec3::new(1.0, 1.0, 0.0);
+		transform_point(transform, &mut vec);
+		world.push(vec);
+	});
+	assert_eq!(world == vec![Vec3::new(3.0, 2.0, 0.0)], true);
+	
+	assert_eq!(scene.cameras.len(), 1);
+	assert_eq!(scene.nodes[0].children[1].camera, Some(0));
+	assert_eq!(scene.cameras[0].from == Vec3::new(1.0, 0.0, 5.0), true);
+	assert_eq!(scene.cameras[0].to == Vec3::new(1.0, 0.0, 4.0), true);
+	assert_eq!((scene.cameras[0].t - 0.1 * (0.5f32).tan()).abs() < 1e-6, true);
+	assert_eq!(scene.cameras[0].fit_aspect, true);
+	
+	match read_gltf(&mut gltf.replace("\"count\": 3, \"type\": \"SCALAR\"", "\"count\": 4, \"type\": \"SCALAR\"").as_bytes(), |_| Ok(Vec::new()), |uri| uri.to_string()) {
+		Err(RenderError::InvalidModel(_)) => {},
+		_ => panic!("accessors beyond their buffer view must be rejected"),
+	}
+	//sizes from the file must neither overflow nor allocate before they are checked
+	let huge: [(&str, &str); 3] = [("\"count\": 3, \"type\": \"SCALAR\"", "\"count\": 10000000000000000000, \"type\": \"SCALAR\""),
+								   ("\"byteOffset\": 36, \"byteLength\": 6", "\"byteOffset\": 18446744073709551615, \"byteLength\": 6"),
+								   ("{\"bufferView\": 0, \"componentType\": 5126, \"count\": 3", "{\"componentType\": 5126, \"count\": 1000000000000000")];
+	for &(from, to) in huge.iter() {
+		match read_gltf(&mut gltf.replace(from, to).as_bytes(), |_| Ok(Vec::new()), |uri| uri.to_string()) {
+			Err(RenderError::InvalidModel(_)) => {},
+			_ => panic!("oversized accessors must be rejected"),
+		}
+	}
+}
+
+#[test]
+fn gltf_read_rejects_invalid_node_trees() {
+	let read = |nodes: &str, roots: &str| read_gltf(&mut format!(r#"{{"asset": {{"version": "2.0"}}, "scenes": [{{"nodes": [{}]}}], "nodes": [{}]}}"#, roots, nodes).as_bytes(), |_| Ok(Vec::new()), |uri| uri.to_string());
+	let chain = |len: usize| (0..len).map(|n| if n + 1 < len { format!("{{\"children\": [{}]}}", n + 1) } else { "{}".to_string() }).collect::<Vec<String>>().join(", ");
+	
+	let scene: Scene = read(&chain(100), "0").unwrap();
+	let mut cnt: usize = 0;
+	scene.visit_nodes(|_, _| cnt += 1);
+	assert_eq!(cnt, 100);
+	
+	let invalid: [(String, &str); 6] = [(r#"{"children": [1]}, {"children": [0]}"#.to_string(), "0"),
+										 (r#"{"children": [0]}"#.to_string(), "0"),
+										 (r#"{"children": [2]}, {"children": [2]}, {}"#.to_string(), "0, 1"),
+										 (r#"{"children": [1, 1]}, {}"#.to_string(), "0"),
+										 (r#"{"children": [1]}, {}"#.to_string(), "0, 1"),
+										 (chain(100000), "0")];
+	for &(ref nodes, roots) in invalid.iter() {
+		match read(nodes, roots) {
+			Err(RenderError::InvalidModel(_)) => {},
+			_ => panic!("nodes {} must be rejected", &nodes[..nodes.len().min(40)]),
+		}
+	}
+}
+
+#[test]
+fn gltf_read_glb_strip_sparse_and_embedded_image() {
+	let mut image: Texture = Texture::new(1, 1);
+	image.buffer[0].set_from_rgb(10.0, 20.0, 30.0);
+	let mut png: Vec<u8> = Vec::new();
+	write_png(&image, &mut png).unwrap();
+	
+	//positions only exist as sparse values on a zero filled accessor
+	let mut bin: Vec<u8> = vec![1, 2, 3, 0];
+	push_f32(&mut bin, &[1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0]);
+	bin.extend(&[255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 255, 255, 255, 255]);
+	bin.extend(&png);
+	while bin.len() % 4 != 0 { bin.push(0); }
+	
+	let mut json: String = format!(r#"{{"asset": {{"version": "2.0"}},
+		"nodes": [{{"mesh": 0}}, {{"mesh": 0, "translation": [0, 0, -2]}}],
+		"meshes": [{{"primitives": [{{"attributes": {{"POSITION": 0, "COLOR_0": 1}}, "mode": 5, "material": 0}}]}}],
+		"materials": [{{"pbrMetallicRoughness": {{"baseColorTexture": {{"index": 0}}}}}}],
+		"textures": [{{"source": 0}}], "images": [{{"bufferView": 3, "mimeType": "image/png"}}],
+		"buffers": [{{"byteLength": {}}}],
+		"bufferViews": [{{"buffer": 0, "byteLength": 3}}, {{"buffer": 0, "byteOffset": 4, "byteLength": 36}},
+						{{"buffer": 0, "byteOffset": 40, "byteLength": 16}}, {{"buffer": 0, "byteOffset": 56, "byteLength": {}}}],
+		"accessors": [{{"componentType": 5126, "count": 4, "type": "VEC3",
+						"sparse": {{"count": 3, "indices": {{"bufferView": 0, "componentType": 5121}}, "values": {{"bufferView": 1}}}}}},
+					  {{"bufferView": 2, "componentType": 5121, "normalized": true, "count": 4, "type": "VEC4"}}]}}"#, bin.len(), png.len());
+	while json.len() % 4 != 0 { json.push(' '); }
+	
+	let mut glb: Vec<u8> = b"glTF".to_vec();
+	push_u32(&mut glb, 2);
+	push_u32(&mut glb, (12 + 8 + json.len() + 8 + bin.len()) as u32);
+	push_u32(&mut glb, json.len() as u32);
+	glb.extend(b"JSON");
+	glb.extend(json.as_bytes());
+	push_u32(&mut glb, bin.len() as u32);
+	glb.extend(b"BIN\0");
+	glb.extend(&bin);
+	
+	let scene: Scene = read_gltf(&mut &glb[..], |uri| panic!("unexpected uri {}", uri), |uri| uri.to_string()).unwrap();
+	assert_eq!(scene.nodes.len(), 2);
+	assert_eq!(scene.meshes.len(), 1);
+	assert_eq!(scene.nodes[0].mesh, Some(0));
+	assert_eq!(scene.nodes[1].mesh, Some(0));
+	assert_eq!(scene.nodes[1].transform._34, -2.0);
+	assert_eq!(scene.meshes[0].shapes.len(), 2);
+	
+	let second: &Vec<Vertex> = &scene.meshes[0].shapes[1].vertices;
+	assert_eq!(second[0].vec == Vec3::new(1.0, 0.0, 0.0), true);
+	assert_eq!(second[1].vec == Vec3::new(1.0, 1.0, 0.0), true);
+	assert_eq!(second[2].vec == Vec3::new(0.0, 1.0, 0.0), true);
+	assert_eq!(scene.meshes[0].shapes[0].vertices[0].color == RED, true);
+	assert_eq!(scene.meshes[0].shapes[0].vertices[0].vec == Vec3::new_empty(), true);
+	
+	//the material is textured but the primitive has no texture coordinates
+	assert_eq!(scene.materials[0].tex_id, 0);
+	assert_eq!(scene.meshes[0].shapes[0].tex_id, -1);
+	let textures: Vec<Texture> = scene.load_textures().unwrap();
+	assert_eq!(textures[0].buffer[0] == image.buffer[0], true);
+	assert_eq!(scene.textures[0].path(), None);
+}
+
+#[test]
+fn gltf_read_skin() {
+	let mut buffer: Vec<u8> = Vec::new();
+	push_f32(&mut buffer, &[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+	buffer.extend(&[0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 0]);
+	push_f32(&mut buffer, &[1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.25, 0.75, 0.0, 0.0]);
+	push_f32(&mut buffer, &[1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+							1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, -1.0, 0.0, 1.0]);
+	//the joints are unnamed or share their name, so they are named by index
+	let gltf: String = format!(r#"{{
+		"asset": {{"version": "2.0"}},
+		"nodes": [{{"name": "body", "mesh": 0, "skin": 0}}, {{"children": [2]}}, {{"name": "arm", "translation": [0, 1, 0]}}, {{"name": "arm"}}],
+		"skins": [{{"joints": [1, 2], "inverseBindMatrices": 3}}],
+		"meshes": [{{"primitives": [{{"attributes": {{"POSITION": 0, "JOINTS_0": 1, "WEIGHTS_0": 2}}}}]}}],
+		"buffers": [{{"byteLength": {}, "uri": "data:application/octet-stream;base64,{}"}}],
+		"bufferViews": [{{"buffer": 0, "byteLength": 36}}, {{"buffer": 0, "byteOffset": 36, "byteLength": 12}},
+						{{"buffer": 0, "byteOffset": 48, "byteLength": 48}}, {{"buffer": 0, "byteOffset": 96, "byteLength": 128}}],
+		"accessors": [{{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3"}},
+					  {{"bufferView": 1, "componentType": 5121, "count": 3, "type": "VEC4"}},
+					  {{"bufferView": 2, "componentType": 5126, "count": 3, "type": "VEC4"}},
+					  {{"bufferView": 3, "componentType": 5126, "count": 2, "type": "MAT4"}}]
+	}}"#, buffer.len(), base64(&buffer));
+	
+	let scene: Scene = read_gltf(&mut gltf.as_bytes(), |uri| panic!("unexpected uri {}", uri), |uri| uri.to_string()).unwrap();
+	assert_eq!(scene.nodes.len(), 3);
+	assert_eq!(scene.nodes[0].skin, Some(0));
+	assert_eq!(scene.nodes[1].name, "node1");
+	assert_eq!(scene.nodes[1].children[0].name, "node2");
+	assert_eq!(scene.nodes[2].name, "arm");
+	assert_eq!(scene.skins[0].joints, vec!["node1".to_string(), "node2".to_string()]);
+	assert_eq!(scene.skins[0].inverse_bind[1]._24, -1.0);
+	let vertices: &Vec<Vertex> = &scene.meshes[0].shapes[0].vertices;
+	assert_eq!(vertices[1].joints, [1, 0, 0, 0]);
+	assert_eq!(vertices[2].joints, [0, 1, 0, 0]);
+	assert_eq!(vertices[2].weights, [0.25, 0.75, 0.0, 0.0]);
+	
+	//the bind pose leaves the vertices where they are
+	let pose: SkinPose = scene.skins[0].pose(&scene).unwrap();
+	for vertex in vertices {
+		let vec: Vec3 = pose.skin_point(vertex).unwrap();
+		assert_eq!((vec.x - vertex.vec.x).abs() < 1e-6 && (vec.y - vertex.vec.y).abs() < 1e-6, true);
+	}
+	
+	match read_gltf(&mut gltf.replace("\"skin\": 0", "\"skin\": 1").as_bytes(), |_| Ok(Vec::new()), |uri| uri.to_string()) {
+		Err(RenderError::InvalidModel(_)) => {},
+		_ => panic!("missing skins must be rejected"),
+	}
+}
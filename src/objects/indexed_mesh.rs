@@ -0,0 +1,376 @@
+use math::mat::mat3::*;
+use objects::shape::*;
+use objects::mesh::*;
+use objects::morph::*;
+use error::*;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Topology {
+	Points,
+	Lines,
+	LineStrip,
+	Triangles,
+	TriangleStrip,
+	TriangleFan,
+}
+
+impl Topology {
+	pub fn from_vertex_count(cnt: usize) -> Option<Topology> {
+		match cnt {
+			1 => Some(Topology::Points),
+			2 => Some(Topology::Lines),
+			3 => Some(Topology::Triangles),
+			_ => None,
+		}
+	}
+	
+	//vertices of one primitive
+	pub fn vertex_count(&self) -> usize {
+		match *self {
+			Topology::Points => 1,
+			Topology::Lines | Topology::LineStrip => 2,
+			_ => 3,
+		}
+	}
+}
+
+//shared vertex buffer, the index buffer is read according to the topology
+#[derive(Clone)]
+pub struct IndexedMesh {
+	pub name: String,
+	pub topology: Topology,
+	pub tex_id: i32, //used by all triangles, -1 without texture
+	pub material: Option<usize>, //index in Scene::materials the vertex colors were copied from
+	pub vertices: Vec<Vertex>,
+	pub indices: Vec<u32>,
+	pub bbox: BoundingBox,
+	pub morph_targets: Vec<MorphTarget>, //deltas in the order of the vertex buffer
+	pub morph_weights: Vec<f32>,
+}
+
+//bit exact key to weld equal vertices
+fn vertex_key(vertex: &Vertex) -> [u32; 17] {
+	let (j, w) = (&vertex.joints, &vertex.weights);
+	[vertex.vec.x.to_bits(), vertex.vec.y.to_bits(), vertex.vec.z.to_bits(),
+	 vertex.color.r.to_bits(), vertex.color.g.to_bits(), vertex.color.b.to_bits(),
+	 vertex.tex_coord.x.to_bits(), vertex.tex_coord.y.to_bits(),
+	 vertex.normal.x.to_bits(), vertex.normal.y.to_bits(), vertex.normal.z.to_bits(),
+	 (j[0] as u32) << 16 | j[1] as u32, (j[2] as u32) << 16 | j[3] as u32,
+	 w[0].to_bits(), w[1].to_bits(), w[2].to_bits(), w[3].to_bits()]
+}
+
+impl IndexedMesh {
+	pub fn new(topology: Topology) -> IndexedMesh {
+		IndexedMesh { name: String::new(), topology, tex_id: -1, material: None, vertices: Vec::new(), indices: Vec::new(), bbox: BoundingBox::new_empty(),
+					  morph_targets: Vec::new(), morph_weights: Vec::new() }
+	}
+	
+	pub fn new_init(topology: Topology, vertices: &Vec<Vertex>, indices: &Vec<u32>) -> IndexedMesh {
+		IndexedMesh { name: String::new(), topology, tex_id: -1, material: None, vertices: vertices.to_vec(), indices: indices.to_vec(), bbox: BoundingBox::new_empty(),
+					  morph_targets: Vec::new(), morph_weights: Vec::new() }
+	}
+	
+	pub fn primitive_count(&self) -> usize {
+		let cnt: usize = self.indices.len();
+		match self.topology {
+			Topology::Points => cnt,
+			Topology::Lines => cnt / 2,
+			Topology::Triangles => cnt / 3,
+			Topology::LineStrip => if cnt < 2 { 0 } else { cnt - 1 },
+			Topology::TriangleStrip | Topology::TriangleFan => if cnt < 3 { 0 } else { cnt - 2 },
+		}
+	}
+	
+	//vertex indices of the primitive i, every second strip triangle is flipped to keep the winding
+	pub fn primitive(&self, i: usize, out: &mut [u32; 3]) -> usize {
+		let idx: &Vec<u32> = &self.indices;
+		match self.topology {
+			Topology::Points => out[0] = idx[i],
+			Topology::Lines => { out[0] = idx[i * 2]; out[1] = idx[i * 2 + 1]; },
+			Topology::LineStrip => { out[0] = idx[i]; out[1] = idx[i + 1]; },
+			Topology::Triangles => { out[0] = idx[i * 3]; out[1] = idx[i * 3 + 1]; out[2] = idx[i * 3 + 2]; },
+			Topology::TriangleStrip => {
+				let odd: usize = i % 2;
+				out[0] = idx[i];
+				out[1] = idx[i + 1 + odd];
+				out[2] = idx[i + 2 - odd];
+			},
+			Topology::TriangleFan => { out[0] = idx[0]; out[1] = idx[i + 1]; out[2] = idx[i + 2]; },
+		}
+		self.topology.vertex_count()
+	}
+	
+	pub fn check_indices(&self) -> RenderResult<()> {
+		match self.indices.iter().find(|i| **i as usize >= self.vertices.len()) {
+			Some(i) => Err(RenderError::InvalidIndex(*i)),
+			None => Ok(()),
+		}
+	}
+	
+	//the new target starts with weight 0
+	pub fn add_morph_target(&mut self, target: MorphTarget) -> RenderResult<()> {
+		target.check(self.vertices.len())?;
+		self.morph_weights.resize(self.morph_targets.len(), 0.0);
+		self.morph_targets.push(target);
+		self.morph_weights.push(0.0);
+		Ok(())
+	}
+	
+	pub fn transform(&mut self, mat: &Mat3) {
+		for vertex in self.vertices.iter_mut() {
+			vertex.vec *= mat;
+		}
+		for target in self.morph_targets.iter_mut() {
+			for delta in target.positions.iter_mut() {
+				*delta *= mat;
+			}
+		}
+	}
+	
+	pub fn scale(&mut self, x: f32, y: f32, z: f32) {
+		for vertex in self.vertices.iter_mut() {
+			vertex.vec.x *= x;
+			vertex.vec.y *= y;
+			vertex.vec.z *= z;
+		}
+		for target in self.morph_targets.iter_mut() {
+			for delta in target.positions.iter_mut() {
+				delta.x *= x;
+				delta.y *= y;
+				delta.z *= z;
+			}
+		}
+	}
+	
+	pub fn translate(&mut self, x: f32, y: f32, z: f32) {
+		for vertex in self.vertices.iter_mut() {
+			vertex.vec.x += x;
+			vertex.vec.y += y;
+			vertex.vec.z += z;
+		}
+	}
+	
+	pub fn compute_bbox(&mut self) {
+		for vertex in self.vertices.iter() {
+			self.bbox.min.x = self.bbox.min.x.min(vertex.vec.x);
+			self.bbox.min.y = self.bbox.min.y.min(vertex.vec.y);
+			self.bbox.min.z = self.bbox.min.z.min(vertex.vec.z);
+			self.bbox.max.x = self.bbox.max.x.max(vertex.vec.x);
+			self.bbox.max.y = self.bbox.max.y.max(vertex.vec.y);
+			self.bbox.max.z = self.bbox.max.z.max(vertex.vec.z);
+		}
+		self.bbox.created = true;
+	}
+	
+	//one indexed mesh per vertex count and tex_id in order of appearance, equal vertices with equal morph deltas are shared
+	pub fn from_mesh(mesh: &Mesh) -> RenderResult<Vec<IndexedMesh>> {
+		check_morph(&mesh.morph_targets, mesh.vertex_count())?;
+		let mut meshes: Vec<IndexedMesh> = Vec::new();
+		let mut welds: Vec<HashMap<([u32; 17], Vec<u32>), u32>> = Vec::new();
+		let mut sources: Vec<Vec<usize>> = Vec::new(); //vertex of the mesh for every vertex of the indexed meshes
+		let mut source: usize = 0;
+		for shape in &mesh.shapes {
+			let topology: Topology = match Topology::from_vertex_count(shape.vertices.len()) {
+				Some(topology) => topology,
+				None => return Err(RenderError::InvalidShape(shape.vertices.len())),
+			};
+			let tex_id: i32 = if topology == Topology::Triangles { shape.tex_id } else { -1 };
+			let m: usize = match meshes.iter().position(|m| m.topology == topology && m.tex_id == tex_id && m.material == shape.material) {
+				Some(m) => m,
+				None => {
+					let mut indexed: IndexedMesh = IndexedMesh::new(topology);
+					indexed.name = mesh.name.clone();
+					indexed.tex_id = tex_id;
+					indexed.material = shape.material;
+					meshes.push(indexed);
+					welds.push(HashMap::new());
+					sources.push(Vec::new());
+					meshes.len() - 1
+				},
+			};
+			for vertex in &shape.vertices {
+				let indexed: &mut IndexedMesh = &mut meshes[m];
+				let next: u32 = indexed.vertices.len() as u32;
+				let mut deltas: Vec<u32> = Vec::new();
+				for target in &mesh.morph_targets {
+					target.key(source, &mut deltas);
+				}
+				let index: u32 = *welds[m].entry((vertex_key(vertex), deltas)).or_insert(next);
+				if index == next {
+					indexed.vertices.push(vertex.clone());
+					sources[m].push(source);
+				}
+				indexed.indices.push(index);
+				source += 1;
+			}
+		}
+		for (indexed, sources) in meshes.iter_mut().zip(sources.iter()) {
+			indexed.compute_bbox();
+			indexed.morph_targets = mesh.morph_targets.iter().map(|t| t.remap(sources)).collect();
+			indexed.morph_weights = mesh.morph_weights.clone();
+		}
+		Ok(meshes)
+	}
+	
+	//reorders triangle lists for a better vertex cache usage, other topologies stay as they are
+	pub fn optimize_vertex_cache(&mut self) -> RenderResult<()> {
+		if self.topology == Topology::Triangles {
+			self.indices = optimize_vertex_cache(&self.indices, self.vertices.len())?;
+		}
+		Ok(())
+	}
+	
+	pub fn to_shapes(&self) -> RenderResult<Vec<Shape>> {
+		self.check_indices()?;
+		let mut shapes: Vec<Shape> = Vec::with_capacity(self.primitive_count());
+		let mut prim: [u32; 3] = [0; 3];
+		let tex_id: i32 = if self.topology.vertex_count() == 3 { self.tex_id } else { -1 };
+		for i in 0..self.primitive_count() {
+			let cnt: usize = self.primitive(i, &mut prim);
+			let vertices: Vec<Vertex> = prim[..cnt].iter().map(|v| self.vertices[*v as usize].clone()).collect();
+			let mut shape: Shape = Shape::new_init(tex_id, &vertices);
+			shape.material = self.material;
+			shapes.push(shape);
+		}
+		Ok(shapes)
+	}
+	
+	pub fn to_mesh(&self) -> RenderResult<Mesh> {
+		check_morph(&self.morph_targets, self.vertices.len())?;
+		let mut mesh: Mesh = Mesh::new(0);
+		mesh.name = self.name.clone();
+		mesh.shapes = self.to_shapes()?;
+		mesh.compute_bbox();
+		//every shape vertex takes the deltas of the buffer vertex it was read from
+		let mut sources: Vec<usize> = Vec::with_capacity(mesh.vertex_count());
+		let mut prim: [u32; 3] = [0; 3];
+		for i in 0..self.primitive_count() {
+			let cnt: usize = self.primitive(i, &mut prim);
+			sources.extend(prim[..cnt].iter().map(|v| *v as usize));
+		}
+		mesh.morph_targets = self.morph_targets.iter().map(|t| t.remap(&sources)).collect();
+		mesh.morph_weights = self.morph_weights.clone();
+		Ok(mesh)
+	}
+}
+
+//simulated vertex cache size and score weights of Tom Forsyth's linear-speed vertex cache optimisation
+const CACHE_SIZE: usize = 32;
+const CACHE_DECAY_POWER: f32 = 1.5;
+const LAST_TRI_SCORE: f32 = 0.75;
+const VALENCE_BOOST_SCALE: f32 = 2.0;
+const VALENCE_BOOST_POWER: f32 = 0.5;
+
+fn vertex_score(cache_pos: i32, remaining: usize) -> f32 {
+	if remaining == 0 {
+		return -1.0;
+	}
+	let mut score: f32 = 0.0;
+	if cache_pos >= 0 {
+		if cache_pos < 3 {
+			//the vertices of the last triangle are penalised a bit so strips do not wander off
+			score = LAST_TRI_SCORE;
+		} else {
+			score = (1.0 - (cache_pos - 3) as f32 / (CACHE_SIZE - 3) as f32).powf(CACHE_DECAY_POWER);
+		}
+	}
+	score + VALENCE_BOOST_SCALE * (remaining as f32).powf(-VALENCE_BOOST_POWER)
+}
+
+/*
+	reorders a triangle list for post transform vertex caches, the winding of every triangle is kept.
+	Trailing indices which do not form a triangle are appended unchanged. Every index must be below vertex_count.
+*/
+pub fn optimize_vertex_cache(indices: &[u32], vertex_count: usize) -> RenderResult<Vec<u32>> {
+	if let Some(i) = indices.iter().find(|i| **i as usize >= vertex_count) {
+		return Err(RenderError::InvalidIndex(*i));
+	}
+	let tri_count: usize = indices.len() / 3;
+	let mut vertex_tris: Vec<Vec<usize>> = vec![Vec::new(); vertex_count];
+	for t in 0..tri_count {
+		for i in &indices[t * 3..t * 3 + 3] {
+			vertex_tris[*i as usize].push(t);
+		}
+	}
+	
+	let mut cache_pos: Vec<i32> = vec![-1; vertex_count];
+	let mut scores: Vec<f32> = (0..vertex_count).map(|v| vertex_score(-1, vertex_tris[v].len())).collect();
+	let mut tri_scores: Vec<f32> = (0..tri_count).map(|t| indices[t * 3..t * 3 + 3].iter().map(|i| scores[*i as usize]).sum()).collect();
+	let mut emitted: Vec<bool> = vec![false; tri_count];
+	let mut cache: Vec<u32> = Vec::with_capacity(CACHE_SIZE + 3);
+	let mut out: Vec<u32> = Vec::with_capacity(indices.len());
+	let mut best: Option<usize> = None;
+	let mut scan_start: usize = 0;
+	
+	for _i in 0..tri_count {
+		//nothing in the cache is connected to a remaining triangle, take the best of all
+		let tri: usize = match best {
+			Some(tri) => tri,
+			None => {
+				while emitted[scan_start] { scan_start += 1; }
+				let mut tri: usize = scan_start;
+				for t in scan_start..tri_count {
+					if !emitted[t] && tri_scores[t] > tri_scores[tri] { tri = t; }
+				}
+				tri
+			},
+		};
+		emitted[tri] = true;
+		let corners: &[u32] = &indices[tri * 3..tri * 3 + 3];
+		out.extend(corners);
+		
+		for v in corners {
+			vertex_tris[*v as usize].retain(|t| *t != tri);
+		}
+		let mut new_cache: Vec<u32> = corners.to_vec();
+		new_cache.extend(cache.iter().filter(|v| !corners.contains(v)));
+		for v in new_cache.iter().skip(CACHE_SIZE) {
+			cache_pos[*v as usize] = -1;
+		}
+		new_cache.truncate(CACHE_SIZE);
+		for (pos, v) in new_cache.iter().enumerate() {
+			cache_pos[*v as usize] = pos as i32;
+		}
+		
+		//only vertices which moved in or out of the cache change their score
+		best = None;
+		let mut best_score: f32 = -1.0;
+		for v in cache.iter().chain(new_cache.iter()) {
+			let v: usize = *v as usize;
+			scores[v] = vertex_score(cache_pos[v], vertex_tris[v].len());
+		}
+		for v in cache.iter().chain(new_cache.iter()) {
+			for t in &vertex_tris[*v as usize] {
+				tri_scores[*t] = indices[t * 3..t * 3 + 3].iter().map(|i| scores[*i as usize]).sum();
+				if cache_pos[*v as usize] >= 0 && tri_scores[*t] > best_score {
+					best_score = tri_scores[*t];
+					best = Some(*t);
+				}
+			}
+		}
+		cache = new_cache;
+	}
+	out.extend(&indices[tri_count * 3..]);
+	Ok(out)
+}
+
+//average count of vertex transformations per triangle for a fifo cache of the given size, 0.5 is ideal and 3 the worst
+pub fn average_cache_miss_ratio(indices: &[u32], cache_size: usize) -> f32 {
+	let mut fifo: Vec<u32> = Vec::with_capacity(cache_size);
+	let mut misses: usize = 0;
+	for index in indices {
+		if !fifo.contains(index) {
+			misses += 1;
+			//a cache without entries misses every index
+			if cache_size == 0 {
+				continue;
+			}
+			if fifo.len() == cache_size {
+				fifo.remove(0);
+			}
+			fifo.push(*index);
+		}
+	}
+	if indices.len() < 3 { 0.0 } else { misses as f32 / (indices.len() / 3) as f32 }
+}
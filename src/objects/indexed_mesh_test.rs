@@ -0,0 +1,131 @@
+use objects::indexed_mesh::*;
+use objects::mesh::*;
+use objects::shape::*;
+use math::vec::vec2::*;
+use math::vec::vec3::*;
+use error::*;
+use color::*;
+
+fn vertices(cnt: usize) -> Vec<Vertex> {
+	(0..cnt).map(|i| Vertex::new_init(Vec3::new(i as f32, 0.0, 0.0), WHITE.clone(), Vec2::new_empty())).collect()
+}
+
+#[test]
+fn indexed_from_sphere_shares_vertices() {
+	let mut sphere: Mesh = MeshBuilder::sphere(0.2, 50, 50);
+	sphere.compute_bbox();
+	sphere.color_by_bbox();
+	let indexed: Vec<IndexedMesh> = IndexedMesh::from_mesh(&sphere).unwrap();
+	assert_eq!(indexed.len(), 1);
+	assert_eq!(indexed[0].topology, Topology::Triangles);
+	assert_eq!(indexed[0].indices.len(), sphere.shapes.len() * 3);
+	assert_eq!(indexed[0].vertices.len() * 4 < indexed[0].indices.len(), true);
+	
+	let mesh: Mesh = indexed[0].to_mesh().unwrap();
+	assert_eq!(mesh.shapes.len(), sphere.shapes.len());
+	for (a, b) in mesh.shapes.iter().zip(sphere.shapes.iter()) {
+		for (va, vb) in a.vertices.iter().zip(b.vertices.iter()) {
+			assert_eq!(va.vec == vb.vec && va.color == vb.color, true);
+		}
+	}
+}
+
+#[test]
+fn indexed_from_mixed_shapes() {
+	let mut mesh: Mesh = Mesh::new(4);
+	mesh.shapes.push(Shape::new_point(&Vec3::new(1.0, 1.0, 1.0)));
+	mesh.shapes.push(Shape::new_init(2, &vertices(3)));
+	mesh.shapes.push(Shape::new_line(&Vec3::new_empty(), &Vec3::new(1.0, 0.0, 0.0)));
+	mesh.shapes.push(Shape::new_init(-1, &vertices(3)));
+	let indexed: Vec<IndexedMesh> = IndexedMesh::from_mesh(&mesh).unwrap();
+	assert_eq!(indexed.len(), 4);
+	assert_eq!(indexed[1].tex_id, 2);
+	assert_eq!(indexed[1].to_shapes().unwrap()[0].tex_id, 2);
+	assert_eq!(indexed[2].topology, Topology::Lines);
+	assert_eq!(indexed[3].tex_id, -1);
+	
+	mesh.shapes.push(Shape::new_init(-1, &vertices(4)));
+	match IndexedMesh::from_mesh(&mesh) {
+		Err(RenderError::InvalidShape(4)) => {},
+		_ => panic!("quads have no topology"),
+	}
+}
+
+#[test]
+fn indexed_strips_and_fans() {
+	let mut strip: IndexedMesh = IndexedMesh::new_init(Topology::TriangleStrip, &vertices(5), &vec![0, 1, 2, 3, 4]);
+	let mut prim: [u32; 3] = [0; 3];
+	assert_eq!(strip.primitive_count(), 3);
+	strip.primitive(0, &mut prim);
+	assert_eq!(prim, [0, 1, 2]);
+	strip.primitive(1, &mut prim);
+	assert_eq!(prim, [1, 3, 2]);
+	strip.primitive(2, &mut prim);
+	assert_eq!(prim, [2, 3, 4]);
+	
+	strip.topology = Topology::TriangleFan;
+	assert_eq!(strip.primitive_count(), 3);
+	strip.primitive(2, &mut prim);
+	assert_eq!(prim, [0, 3, 4]);
+	
+	strip.topology = Topology::LineStrip;
+	assert_eq!(strip.primitive(3, &mut prim), 2);
+	assert_eq!(prim[..2], [3, 4]);
+	assert_eq!(strip.to_shapes().unwrap().len(), 4);
+	
+	strip.indices.push(5);
+	match strip.to_shapes() {
+		Err(RenderError::InvalidIndex(5)) => {},
+		_ => panic!("index 5 is beyond the vertex buffer"),
+	}
+}
+
+#[test]
+fn indexed_optimize_vertex_cache() {
+	//regular grid where every inner vertex is shared by six triangles
+	let n: u32 = 40;
+	let mut indices: Vec<u32> = Vec::new();
+	for y in 0..n - 1 {
+		for x in 0..n - 1 {
+			let i: u32 = y * n + x;
+			indices.extend(&[i, i + 1, i + n, i + 1, i + n + 1, i + n]);
+		}
+	}
+	
+	//scatter the triangles so the original order has no locality left
+	let tri_count: usize = indices.len() / 3;
+	let step: usize = 7919;
+	let scattered: Vec<u32> = (0..tri_count).flat_map(|t| {
+		let s: usize = (t * step) % tri_count;
+		indices[s * 3..s * 3 + 3].to_vec()
+	}).collect();
+	let mut grid: IndexedMesh = IndexedMesh::new_init(Topology::Triangles, &vertices((n * n) as usize), &scattered);
+	grid.optimize_vertex_cache().unwrap();
+	
+	let before: f32 = average_cache_miss_ratio(&scattered, 32);
+	let after: f32 = average_cache_miss_ratio(&grid.indices, 32);
+	assert_eq!(before > 2.5, true);
+	assert_eq!(after < 0.8, true);
+	assert_eq!(average_cache_miss_ratio(&grid.indices, 0), 3.0);
+	
+	//same triangles with the same winding
+	let mut a: Vec<[u32; 3]> = scattered.chunks(3).map(|c| [c[0], c[1], c[2]]).collect();
+	let mut b: Vec<[u32; 3]> = grid.indices.chunks(3).map(|c| [c[0], c[1], c[2]]).collect();
+	a.sort();
+	b.sort();
+	assert_eq!(a == b, true);
+	
+	assert_eq!(optimize_vertex_cache(&[0, 1, 2, 2, 1], 3).unwrap(), vec![0, 1, 2, 2, 1]);
+	assert_eq!(optimize_vertex_cache(&[], 0).unwrap().len(), 0);
+	
+	//indices are checked before anything is allocated for them
+	grid.indices[5] = ::std::u32::MAX;
+	match grid.optimize_vertex_cache() {
+		Err(RenderError::InvalidIndex(::std::u32::MAX)) => {},
+		_ => panic!("indices beyond the vertices must be rejected"),
+	}
+	match optimize_vertex_cache(&[0, 1, 2, 2, 1], 2) {
+		Err(RenderError::InvalidIndex(2)) => {},
+		_ => panic!("trailing indices beyond the vertices must be rejected"),
+	}
+}
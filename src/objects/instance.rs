@@ -0,0 +1,23 @@
+use math::mat::mat4::*;
+use objects::node::*;
+use color::*;
+
+//one placement of a shared mesh, the tint is multiplied into the drawn colors, WHITE keeps them
+pub struct Instance {
+	pub transform: Mat4,
+	pub tint: Color,
+}
+
+impl Instance {
+	pub fn new(transform: Mat4) -> Instance {
+		Instance { transform, tint: WHITE.clone() }
+	}
+	
+	pub fn new_tint(transform: Mat4, tint: &Color) -> Instance {
+		Instance { transform, tint: tint.clone() }
+	}
+	
+	pub fn new_translation(x: f32, y: f32, z: f32) -> Instance {
+		Instance::new(translation(x, y, z))
+	}
+}
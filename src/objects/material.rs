@@ -0,0 +1,35 @@
+use color::*;
+
+//colors use the same 0 - 255 range as vertex colors
+#[derive(Clone)]
+pub struct Material {
+	pub name: String,
+	pub ambient: Color,
+	pub diffuse: Color,
+	pub specular: Color,
+	pub emissive: Color,
+	pub shininess: f32,
+	pub opacity: f32,
+	pub metallic: f32, //pbr metallic-roughness parameters, kept as given by glTF assets
+	pub roughness: f32,
+	pub diffuse_map: Option<String>, //path of the texture as referenced by the asset
+	pub tex_id: i32, //index of diffuse_map in Scene::textures, -1 without texture
+}
+
+impl Material {
+	pub fn new(name: &str) -> Material {
+		Material {
+			name: name.to_string(),
+			ambient: BLACK.clone(),
+			diffuse: WHITE.clone(),
+			specular: BLACK.clone(),
+			emissive: BLACK.clone(),
+			shininess: 0.0,
+			opacity: 1.0,
+			metallic: 0.0,
+			roughness: 1.0,
+			diffuse_map: None,
+			tex_id: -1,
+		}
+	}
+}
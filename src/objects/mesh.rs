@@ -1,9 +1,19 @@
 use math::vec::vec3::*;
 use math::mat::mat3::*;
+use math::mat::mat4::*;
 use objects::shape::*;
+use objects::node::*;
+use objects::morph::*;
+use quaternion::*;
 use std::f32::{MIN, MAX};
 use math::utils::{interpolate_lin, rand_path_deg};
 use color::*;
+use objects::format::stl::*;
+use objects::format::ply::*;
+use error::*;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
 
 #[derive(Clone)]
 pub struct BoundingBox {
@@ -14,8 +24,12 @@ pub struct BoundingBox {
 
 #[derive(Clone)]
 pub struct Mesh {
+	pub name: String,
 	pub bbox: BoundingBox,
 	pub shapes: Vec<Shape>,
+	pub model: Mat4, //placement in the world, composed with the camera when drawing
+	pub morph_targets: Vec<MorphTarget>, //deltas for the vertices of all shapes in order
+	pub morph_weights: Vec<f32>, //weight per target, blended when drawing
 }
 
 impl BoundingBox {
@@ -55,22 +69,79 @@ impl BoundingBox {
 impl Mesh {
 
 	pub fn new( cnt_mesh: usize) -> Mesh {
-		Mesh { bbox: BoundingBox::new_empty(), shapes: Vec::with_capacity(cnt_mesh) }
+		Mesh { name: String::new(), bbox: BoundingBox::new_empty(), shapes: Vec::with_capacity(cnt_mesh), model: identity(),
+			   morph_targets: Vec::new(), morph_weights: Vec::new() }
 	}
 	
 	pub fn new_init(bbox: &BoundingBox, shapes: &Vec<Shape>) -> Mesh {
-		Mesh { bbox: bbox.clone() , shapes: shapes.to_vec() }
+		Mesh { name: String::new(), bbox: bbox.clone() , shapes: shapes.to_vec(), model: identity(),
+			   morph_targets: Vec::new(), morph_weights: Vec::new() }
 	}
 
+	pub fn load_stl<P: AsRef<Path>>(filename: P) -> RenderResult<Mesh> {
+		let mut f = BufReader::new(File::open(filename)?);
+		read_stl(&mut f)
+	}
+	
+	pub fn write_stl<P: AsRef<Path>>(&self, filename: P, ascii: bool) -> RenderResult<()> {
+		let mut f = BufWriter::new(File::create(filename)?);
+		write_stl(self, &mut f, ascii)
+	}
+	
+	pub fn load_ply<P: AsRef<Path>>(filename: P) -> RenderResult<Mesh> {
+		let mut f = BufReader::new(File::open(filename)?);
+		read_ply(&mut f)
+	}
+	
+	pub fn write_ply<P: AsRef<Path>>(&self, filename: P, format: PlyFormat) -> RenderResult<()> {
+		let mut f = BufWriter::new(File::create(filename)?);
+		write_ply(self, &mut f, format)
+	}
+
+	//vertices of all shapes, the count morph targets need
+	pub fn vertex_count(&self) -> usize {
+		self.shapes.iter().map(|s| s.vertices.len()).sum()
+	}
+	
+	//the new target starts with weight 0
+	pub fn add_morph_target(&mut self, target: MorphTarget) -> RenderResult<()> {
+		target.check(self.vertex_count())?;
+		self.morph_weights.resize(self.morph_targets.len(), 0.0);
+		self.morph_targets.push(target);
+		self.morph_weights.push(0.0);
+		Ok(())
+	}
+	
+	//transform, scale and translate bake into the vertices, the *_model methods below only change the model matrix
 	pub fn transform(&mut self, mat: &Mat3) {
+		let normal_mat: Mat4 = rotation(mat);
 		for shape in self.shapes.iter_mut() {
 			shape.transform(mat);
+			for vertex in shape.vertices.iter_mut() {
+				transform_normal(&normal_mat, &mut vertex.normal);
+			}
+		}
+		for target in self.morph_targets.iter_mut() {
+			for delta in target.positions.iter_mut() {
+				*delta *= mat;
+			}
 		}
 	}
 	
 	pub fn scale(&mut self, x: f32, y: f32, z: f32) {
+		let normal_mat: Mat4 = scaling(x, y, z);
 		for shape in self.shapes.iter_mut() {
 			shape.scale(x, y, z);
+			for vertex in shape.vertices.iter_mut() {
+				transform_normal(&normal_mat, &mut vertex.normal);
+			}
+		}
+		for target in self.morph_targets.iter_mut() {
+			for delta in target.positions.iter_mut() {
+				delta.x *= x;
+				delta.y *= y;
+				delta.z *= z;
+			}
 		}
 	}
 	
@@ -80,6 +151,42 @@ impl Mesh {
 		}
 	}
 	
+	//the following apply after the current model matrix
+	pub fn rotate_model(&mut self, mat: &Mat3) {
+		self._apply_model(rotation(mat));
+	}
+	
+	pub fn rotate_model_quaternion(&mut self, q: &Quaternion) {
+		self._apply_model(q.to_mat4());
+	}
+	
+	pub fn scale_model(&mut self, x: f32, y: f32, z: f32) {
+		self._apply_model(scaling(x, y, z));
+	}
+	
+	pub fn translate_model(&mut self, x: f32, y: f32, z: f32) {
+		self._apply_model(translation(x, y, z));
+	}
+	
+	fn _apply_model(&mut self, mut mat: Mat4) {
+		mat *= &self.model;
+		self.model = mat;
+	}
+	
+	//moves the model matrix into the vertices and resets it to identity
+	pub fn bake_model(&mut self) {
+		for shape in self.shapes.iter_mut() {
+			for vertex in shape.vertices.iter_mut() {
+				transform_point(&self.model, &mut vertex.vec);
+				transform_normal(&self.model, &mut vertex.normal);
+			}
+		}
+		for target in self.morph_targets.iter_mut() {
+			target.transform_positions(&self.model);
+		}
+		self.model = identity();
+	}
+	
 	pub fn compute_bbox(&mut self) {
 		for shape in self.shapes.iter_mut() {
 			for vertex in shape.vertices.iter() {
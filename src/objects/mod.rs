@@ -1,6 +1,24 @@
 pub mod shape;
 pub mod mesh;
+pub mod indexed_mesh;
 pub mod scene;
+pub mod material;
+pub mod node;
+pub mod instance;
+pub mod animation;
+pub mod skin;
+pub mod morph;
+pub mod format;
 
 #[cfg(test)]
 mod shape_test;
+#[cfg(test)]
+mod format_test;
+#[cfg(test)]
+mod indexed_mesh_test;
+#[cfg(test)]
+mod animation_test;
+#[cfg(test)]
+mod skin_test;
+#[cfg(test)]
+mod morph_test;
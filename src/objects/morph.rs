@@ -0,0 +1,112 @@
+/*
+	Morph targets (blend shapes). A target stores per vertex deltas of the position, normal and color, the renderer adds
+	the weighted deltas to a copy of the vertices of every draw, the mesh itself is never changed. Vertices are counted
+	in the order of Mesh::shapes or in the order of the vertex buffer of an IndexedMesh. Morphing happens before skinning.
+*/
+use math::vec::vec3::*;
+use math::mat::mat4::*;
+use objects::shape::*;
+use color::*;
+use error::*;
+
+#[derive(Clone)]
+pub struct MorphTarget {
+	pub name: String,
+	//one delta per vertex, or empty if the target does not change this attribute
+	pub positions: Vec<Vec3>,
+	pub normals: Vec<Vec3>,
+	pub colors: Vec<Color>,
+}
+
+impl MorphTarget {
+	pub fn new(name: &str) -> MorphTarget {
+		MorphTarget { name: name.to_string(), positions: Vec::new(), normals: Vec::new(), colors: Vec::new() }
+	}
+	
+	pub fn check(&self, cnt_vertex: usize) -> RenderResult<()> {
+		for &(attribute, len) in &[("positions", self.positions.len()), ("normals", self.normals.len()), ("colors", self.colors.len())] {
+			if len != 0 && len != cnt_vertex {
+				return Err(RenderError::InvalidModel(format!("morph target {} has {} {} for {} vertices", self.name, len, attribute, cnt_vertex)));
+			}
+		}
+		Ok(())
+	}
+	
+	//target for a new vertex order, sources holds the old index of every new vertex
+	pub fn remap(&self, sources: &[usize]) -> MorphTarget {
+		let pick = |deltas: &Vec<Vec3>| if deltas.is_empty() { Vec::new() } else { sources.iter().map(|s| deltas[*s].clone()).collect() };
+		MorphTarget {
+			name: self.name.clone(),
+			positions: pick(&self.positions),
+			normals: pick(&self.normals),
+			colors: if self.colors.is_empty() { Vec::new() } else { sources.iter().map(|s| self.colors[*s].clone()).collect() },
+		}
+	}
+	
+	//bit exact deltas of a vertex, used to keep vertices with different deltas apart when welding
+	pub fn key(&self, index: usize, key: &mut Vec<u32>) {
+		if let Some(p) = self.positions.get(index) {
+			key.extend(&[p.x.to_bits(), p.y.to_bits(), p.z.to_bits()]);
+		}
+		if let Some(n) = self.normals.get(index) {
+			key.extend(&[n.x.to_bits(), n.y.to_bits(), n.z.to_bits()]);
+		}
+		if let Some(c) = self.colors.get(index) {
+			key.extend(&[c.r.to_bits(), c.g.to_bits(), c.b.to_bits()]);
+		}
+	}
+	
+	//position deltas follow transformations baked into the vertices, translations do not change them
+	pub fn transform_positions(&mut self, mat: &Mat4) {
+		for delta in self.positions.iter_mut() {
+			let (x, y, z) = (delta.x, delta.y, delta.z);
+			delta.x = mat._11 * x + mat._12 * y + mat._13 * z;
+			delta.y = mat._21 * x + mat._22 * y + mat._23 * z;
+			delta.z = mat._31 * x + mat._32 * y + mat._33 * z;
+		}
+	}
+}
+
+//true if any target has a weight, missing weights count as zero
+pub fn is_morphed(targets: &[MorphTarget], weights: &[f32]) -> bool {
+	!targets.is_empty() && weights.iter().any(|w| *w != 0.0)
+}
+
+pub fn check_morph(targets: &[MorphTarget], cnt_vertex: usize) -> RenderResult<()> {
+	for target in targets {
+		target.check(cnt_vertex)?;
+	}
+	Ok(())
+}
+
+//adds the weighted deltas to vertices, first is the index of vertices[0] in the targets
+pub fn apply_morph(vertices: &mut [Vertex], first: usize, targets: &[MorphTarget], weights: &[f32]) {
+	for (target, weight) in targets.iter().zip(weights.iter()) {
+		if *weight == 0.0 { continue; }
+		for (i, vertex) in vertices.iter_mut().enumerate() {
+			if let Some(p) = target.positions.get(first + i) {
+				vertex.vec.x += weight * p.x;
+				vertex.vec.y += weight * p.y;
+				vertex.vec.z += weight * p.z;
+			}
+			if let Some(n) = target.normals.get(first + i) {
+				vertex.normal.x += weight * n.x;
+				vertex.normal.y += weight * n.y;
+				vertex.normal.z += weight * n.z;
+			}
+			if let Some(c) = target.colors.get(first + i) {
+				vertex.color.r += weight * c.r;
+				vertex.color.g += weight * c.g;
+				vertex.color.b += weight * c.b;
+			}
+		}
+	}
+	if targets.iter().any(|t| !t.normals.is_empty()) {
+		for vertex in vertices.iter_mut() {
+			let n: &Vec3 = &vertex.normal;
+			if n.x != 0.0 || n.y != 0.0 || n.z != 0.0 {
+				vertex.normal.normalize();
+			}
+		}
+	}
+}
@@ -0,0 +1,63 @@
+use objects::morph::*;
+use objects::mesh::*;
+use objects::indexed_mesh::*;
+use objects::scene::*;
+use objects::animation::*;
+use color::*;
+use error::*;
+use math::vec::vec3::*;
+
+#[test]
+fn morph_targets_blend_and_convert() {
+	//two triangles sharing an edge, the smile target moves the shared corner of the second one only
+	let mut mesh: Mesh = MeshBuilder::quad(&Vec3::new(0.0, 0.0, 0.0), &Vec3::new(1.0, 0.0, 0.0), &Vec3::new(0.0, 1.0, 0.0), &Vec3::new(1.0, 1.0, 0.0));
+	for shape in mesh.shapes.iter_mut() {
+		shape.set_color(&WHITE);
+	}
+	let cnt: usize = mesh.vertex_count();
+	assert_eq!(cnt, 6);
+	assert_eq!(IndexedMesh::from_mesh(&mesh).unwrap()[0].vertices.len(), 4);
+	let mut smile: MorphTarget = MorphTarget::new("smile");
+	smile.positions = vec![Vec3::new_empty(); cnt];
+	smile.positions[4] = Vec3::new(0.0, 2.0, 0.0);
+	let mut blush: MorphTarget = MorphTarget::new("blush");
+	blush.colors = vec![Color::new(100.0, 0.0, 0.0); cnt];
+	mesh.add_morph_target(smile).unwrap();
+	mesh.add_morph_target(blush).unwrap();
+	assert_eq!(mesh.morph_weights, vec![0.0, 0.0]);
+	match mesh.add_morph_target(MorphTarget { name: "short".to_string(), positions: vec![Vec3::new_empty()], normals: Vec::new(), colors: Vec::new() }) {
+		Err(RenderError::InvalidModel(_)) => {},
+		_ => panic!("targets need a delta for every vertex"),
+	}
+	
+	assert_eq!(is_morphed(&mesh.morph_targets, &mesh.morph_weights), false);
+	mesh.morph_weights = vec![0.5, 1.0];
+	let mut vertices = mesh.shapes[1].vertices.clone();
+	apply_morph(&mut vertices, 3, &mesh.morph_targets, &mesh.morph_weights);
+	assert_eq!(vertices[1].vec == Vec3::new(1.0, 1.0, 0.0), true);
+	assert_eq!(vertices[0].vec == mesh.shapes[1].vertices[0].vec, true);
+	assert_eq!(vertices[0].color == Color::new(355.0, 255.0, 255.0), true);
+	assert_eq!(mesh.shapes[1].vertices[1].vec == Vec3::new(1.0, 0.0, 0.0), true);
+	
+	//welding keeps the moving corner apart from an equal vertex without delta, the deltas survive both directions
+	let indexed: Vec<IndexedMesh> = IndexedMesh::from_mesh(&mesh).unwrap();
+	assert_eq!(indexed.len(), 1);
+	assert_eq!(indexed[0].vertices.len(), 5);
+	assert_eq!(indexed[0].morph_targets[0].positions.iter().filter(|d| d.y == 2.0).count(), 1);
+	assert_eq!(indexed[0].morph_weights, mesh.morph_weights);
+	let back: Mesh = indexed[0].to_mesh().unwrap();
+	for (a, b) in back.morph_targets[0].positions.iter().zip(mesh.morph_targets[0].positions.iter()) {
+		assert_eq!(a == b, true);
+	}
+	
+	//baked scales apply to the deltas
+	mesh.scale(1.0, 3.0, 1.0);
+	assert_eq!(mesh.morph_targets[0].positions[4].y, 6.0);
+	
+	let mut scene: Scene = Scene::new(1);
+	scene.meshes.push(mesh);
+	let mut clip: Clip = Clip::new("talk");
+	clip.add(Target::Mesh(0), Channel::Weights(vec![Keyframe::new(0.0, vec![0.0]), Keyframe::new(1.0, vec![1.0, 0.5])]), Interpolation::Linear);
+	clip.apply(&mut scene, 0.5).unwrap();
+	assert_eq!(scene.meshes[0].morph_weights, vec![0.5, 0.25]);
+}
@@ -0,0 +1,169 @@
+use math::mat::mat3::*;
+use math::mat::mat4::*;
+use math::vec::vec3::*;
+use quaternion::*;
+
+//a node of the scene hierarchy, transform is relative to the parent node
+pub struct Node {
+	pub name: String,
+	pub transform: Mat4,
+	pub mesh: Option<usize>, //index in Scene::meshes
+	pub indexed_mesh: Option<usize>, //index in Scene::indexed_meshes
+	pub camera: Option<usize>, //index in Scene::cameras
+	pub skin: Option<usize>, //index in Scene::skins, skins the mesh of this node
+	pub children: Vec<Node>,
+}
+
+impl Node {
+	pub fn new(name: &str) -> Node {
+		Node { name: name.to_string(), transform: identity(), mesh: None, indexed_mesh: None, camera: None, skin: None, children: Vec::new() }
+	}
+	
+	pub fn new_mesh(name: &str, mesh: usize) -> Node {
+		let mut node: Node = Node::new(name);
+		node.mesh = Some(mesh);
+		node
+	}
+	
+	//the following apply after the current transformation, like the baking methods of Mesh
+	pub fn translate(&mut self, x: f32, y: f32, z: f32) {
+		self._apply(translation(x, y, z));
+	}
+	
+	pub fn scale(&mut self, x: f32, y: f32, z: f32) {
+		self._apply(scaling(x, y, z));
+	}
+	
+	pub fn rotate(&mut self, mat: &Mat3) {
+		self._apply(rotation(mat));
+	}
+	
+	pub fn rotate_quaternion(&mut self, q: &Quaternion) {
+		self._apply(q.to_mat4());
+	}
+	
+	fn _apply(&mut self, mut mat: Mat4) {
+		mat *= &self.transform;
+		self.transform = mat;
+	}
+	
+	//depth first search in the subtree including this node
+	pub fn find(&self, name: &str) -> Option<&Node> {
+		if self.name == name {
+			return Some(self);
+		}
+		self.children.iter().filter_map(|c| c.find(name)).next()
+	}
+	
+	pub fn find_mut(&mut self, name: &str) -> Option<&mut Node> {
+		if self.name == name {
+			return Some(self);
+		}
+		for child in self.children.iter_mut() {
+			if let Some(node) = child.find_mut(name) {
+				return Some(node);
+			}
+		}
+		None
+	}
+	
+	//calls f for every node of the subtree with its world matrix, parents before their children
+	pub fn visit<F: FnMut(&Node, &Mat4)>(&self, parent: &Mat4, f: &mut F) {
+		let mut world: Mat4 = Mat4::new_empty();
+		world.set_from(parent);
+		world *= &self.transform;
+		f(self, &world);
+		for child in &self.children {
+			child.visit(&world, f);
+		}
+	}
+}
+
+pub fn identity() -> Mat4 {
+	Mat4::new(1.0, 0.0, 0.0, 0.0,
+			  0.0, 1.0, 0.0, 0.0,
+			  0.0, 0.0, 1.0, 0.0,
+			  0.0, 0.0, 0.0, 1.0)
+}
+
+pub fn translation(x: f32, y: f32, z: f32) -> Mat4 {
+	let mut mat: Mat4 = identity();
+	mat._14 = x;
+	mat._24 = y;
+	mat._34 = z;
+	mat
+}
+
+pub fn scaling(x: f32, y: f32, z: f32) -> Mat4 {
+	let mut mat: Mat4 = identity();
+	mat._11 = x;
+	mat._22 = y;
+	mat._33 = z;
+	mat
+}
+
+pub fn rotation(mat: &Mat3) -> Mat4 {
+	Mat4::new(mat._11, mat._12, mat._13, 0.0,
+			  mat._21, mat._22, mat._23, 0.0,
+			  mat._31, mat._32, mat._33, 0.0,
+			  0.0, 0.0, 0.0, 1.0)
+}
+
+//translation * rotation * scale, the order used by glTF and the animation tracks
+pub fn compose(translation: &Vec3, rotation: &Quaternion, scale: &Vec3) -> Mat4 {
+	let m: Mat3 = rotation.to_mat3();
+	Mat4::new(m._11 * scale.x, m._12 * scale.y, m._13 * scale.z, translation.x,
+			  m._21 * scale.x, m._22 * scale.y, m._23 * scale.z, translation.y,
+			  m._31 * scale.x, m._32 * scale.y, m._33 * scale.z, translation.z,
+			  0.0, 0.0, 0.0, 1.0)
+}
+
+//inverse of compose for matrices without shear, a negative determinant is moved into the x scale
+pub fn decompose(mat: &Mat4, translation: &mut Vec3, rotation: &mut Quaternion, scale: &mut Vec3) {
+	translation.set(mat._14, mat._24, mat._34);
+	let det: f32 = mat._11 * (mat._22 * mat._33 - mat._23 * mat._32)
+				 - mat._12 * (mat._21 * mat._33 - mat._23 * mat._31)
+				 + mat._13 * (mat._21 * mat._32 - mat._22 * mat._31);
+	let sign: f32 = if det < 0.0 { -1.0 } else { 1.0 };
+	scale.set(sign * (mat._11 * mat._11 + mat._21 * mat._21 + mat._31 * mat._31).sqrt(),
+			  (mat._12 * mat._12 + mat._22 * mat._22 + mat._32 * mat._32).sqrt(),
+			  (mat._13 * mat._13 + mat._23 * mat._23 + mat._33 * mat._33).sqrt());
+	if scale.x == 0.0 || scale.y == 0.0 || scale.z == 0.0 {
+		rotation.set_from(&Quaternion::new_identity());
+		return;
+	}
+	rotation.set_from(&Quaternion::from_mat3(&Mat3::new(mat._11 / scale.x, mat._12 / scale.y, mat._13 / scale.z,
+														mat._21 / scale.x, mat._22 / scale.y, mat._23 / scale.z,
+														mat._31 / scale.x, mat._32 / scale.y, mat._33 / scale.z)));
+}
+
+pub fn transform_point(mat: &Mat4, vec: &mut Vec3) {
+	let (x, y, z) = (vec.x, vec.y, vec.z);
+	vec.x = mat._11 * x + mat._12 * y + mat._13 * z + mat._14;
+	vec.y = mat._21 * x + mat._22 * y + mat._23 * z + mat._24;
+	vec.z = mat._31 * x + mat._32 * y + mat._33 * z + mat._34;
+}
+
+//normals use the inverse transpose of the upper 3x3 part, so non uniform scales keep them perpendicular
+pub fn transform_normal(mat: &Mat4, normal: &mut Vec3) {
+	let (x, y, z) = (normal.x, normal.y, normal.z);
+	if x == 0.0 && y == 0.0 && z == 0.0 {
+		return;
+	}
+	//the cofactor matrix equals the inverse transpose up to the scale of the determinant
+	let c11: f32 = mat._22 * mat._33 - mat._23 * mat._32;
+	let c12: f32 = mat._23 * mat._31 - mat._21 * mat._33;
+	let c13: f32 = mat._21 * mat._32 - mat._22 * mat._31;
+	let c21: f32 = mat._13 * mat._32 - mat._12 * mat._33;
+	let c22: f32 = mat._11 * mat._33 - mat._13 * mat._31;
+	let c23: f32 = mat._12 * mat._31 - mat._11 * mat._32;
+	let c31: f32 = mat._12 * mat._23 - mat._13 * mat._22;
+	let c32: f32 = mat._13 * mat._21 - mat._11 * mat._23;
+	let c33: f32 = mat._11 * mat._22 - mat._12 * mat._21;
+	let det: f32 = mat._11 * c11 + mat._12 * c12 + mat._13 * c13;
+	let sign: f32 = if det < 0.0 { -1.0 } else { 1.0 };
+	normal.x = sign * (c11 * x + c12 * y + c13 * z);
+	normal.y = sign * (c21 * x + c22 * y + c23 * z);
+	normal.z = sign * (c31 * x + c32 * y + c33 * z);
+	normal.normalize();
+}
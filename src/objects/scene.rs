@@ -1,32 +1,169 @@
 use objects::mesh::*;
+use objects::indexed_mesh::*;
+use objects::material::*;
 use math::mat::mat3::*;
+use math::mat::mat4::*;
 use math::vec::vec3::*;
+use objects::node::*;
+use objects::skin::*;
+use objects::format::obj::*;
+use objects::format::gltf::*;
+use camera::*;
+use color::*;
+use texture::*;
+use error::*;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read};
+use std::path::Path;
+
+//textures are either referenced by path or already decoded, like images embedded in a glTF file
+pub enum SceneTexture {
+	File(String),
+	Image(Texture),
+}
+
+impl SceneTexture {
+	pub fn path(&self) -> Option<&str> {
+		match *self {
+			SceneTexture::File(ref path) => Some(path),
+			SceneTexture::Image(_) => None,
+		}
+	}
+}
 
 pub struct Scene {
 	pub meshes: Vec<Mesh>,
+	pub indexed_meshes: Vec<IndexedMesh>,
+	pub materials: Vec<Material>,
+	pub textures: Vec<SceneTexture>, //the position is the tex_id of the shapes
+	pub nodes: Vec<Node>, //root nodes, if there are any only the meshes referenced by nodes are drawn
+	pub cameras: Vec<Camera>,
+	pub skins: Vec<Skin>,
 }
 
 impl Scene {
 	pub fn new( cnt_mesh: usize) -> Scene {
-		Scene { meshes: Vec::with_capacity(cnt_mesh) }
+		Scene { meshes: Vec::with_capacity(cnt_mesh), indexed_meshes: Vec::new(), materials: Vec::new(), textures: Vec::new(), nodes: Vec::new(), cameras: Vec::new(), skins: Vec::new() }
+	}
+	
+	//every node with its world matrix, parents before their children
+	pub fn visit_nodes<F: FnMut(&Node, &Mat4)>(&self, mut f: F) {
+		let root: Mat4 = identity();
+		for node in &self.nodes {
+			node.visit(&root, &mut f);
+		}
+	}
+	
+	pub fn find_node(&self, name: &str) -> Option<&Node> {
+		self.nodes.iter().filter_map(|n| n.find(name)).next()
+	}
+	
+	pub fn find_node_mut(&mut self, name: &str) -> Option<&mut Node> {
+		for node in self.nodes.iter_mut() {
+			if let Some(found) = node.find_mut(name) {
+				return Some(found);
+			}
+		}
+		None
+	}
+	
+	//copies the diffuse color into the vertices of the shapes using the material again, e.g. after it was animated
+	pub fn update_material_colors(&mut self, material: usize) {
+		let color: Color = match self.materials.get(material) {
+			Some(material) => material.diffuse.clone(),
+			None => return,
+		};
+		for shape in self.meshes.iter_mut().flat_map(|m| m.shapes.iter_mut()).filter(|s| s.material == Some(material)) {
+			for vertex in shape.vertices.iter_mut() {
+				vertex.color.set_from_color(&color);
+			}
+		}
+		for mesh in self.indexed_meshes.iter_mut().filter(|m| m.material == Some(material)) {
+			for vertex in mesh.vertices.iter_mut() {
+				vertex.color.set_from_color(&color);
+			}
+		}
+	}
+	
+	//loads all referenced textures in tex_id order, ready to be used as Renderer::texture
+	pub fn load_textures(&self) -> RenderResult<Vec<Texture>> {
+		let mut textures: Vec<Texture> = Vec::with_capacity(self.textures.len());
+		for texture in &self.textures {
+			match *texture {
+				SceneTexture::File(ref path) => textures.push(Texture::load(path)?),
+				SceneTexture::Image(ref image) => textures.push(image.clone()),
+			}
+		}
+		Ok(textures)
+	}
+	
+	//mtllib and texture paths are resolved relative to the file referencing them
+	pub fn load_obj<P: AsRef<Path>>(filename: P) -> RenderResult<Scene> {
+		let dir: &Path = filename.as_ref().parent().unwrap_or(Path::new(""));
+		let mut f = BufReader::new(File::open(filename.as_ref())?);
+		read_obj(&mut f, |name| {
+			let mtl_path = dir.join(name);
+			let mut mtl = BufReader::new(File::open(&mtl_path)?);
+			let mut materials: Vec<Material> = read_mtl(&mut mtl)?;
+			let mtl_dir: &Path = mtl_path.parent().unwrap_or(Path::new(""));
+			for material in materials.iter_mut() {
+				if let Some(ref mut map) = material.diffuse_map {
+					*map = mtl_dir.join(&*map).to_string_lossy().into_owned();
+				}
+			}
+			Ok(materials)
+		})
+	}
+	
+	//.gltf with external or embedded buffers and binary .glb, relative uris are resolved against the file
+	pub fn load_gltf<P: AsRef<Path>>(filename: P) -> RenderResult<Scene> {
+		let dir: &Path = filename.as_ref().parent().unwrap_or(Path::new(""));
+		let mut f = BufReader::new(File::open(filename.as_ref())?);
+		read_gltf(&mut f, |uri| {
+			let mut data: Vec<u8> = Vec::new();
+			File::open(dir.join(uri))?.read_to_end(&mut data)?;
+			Ok(data)
+		}, |uri| dir.join(uri).to_string_lossy().into_owned())
+	}
+	
+	//with_mtl writes the materials of textured shapes next to the obj with the extension mtl
+	pub fn write_obj<P: AsRef<Path>>(&self, filename: P, with_mtl: bool) -> RenderResult<()> {
+		let mut mtllib: Option<String> = None;
+		if with_mtl {
+			let mtl_path = filename.as_ref().with_extension("mtl");
+			let mut f = BufWriter::new(File::create(&mtl_path)?);
+			write_mtl(self, &mut f)?;
+			mtllib = mtl_path.file_name().map(|name| name.to_string_lossy().into_owned());
+		}
+		let mut f = BufWriter::new(File::create(filename)?);
+		write_obj(self, &mut f, mtllib.as_ref().map(|name| name.as_str()))
 	}
 	
 	pub fn transform(&mut self, mat: &Mat3) {
 		for mesh in self.meshes.iter_mut() {
 			mesh.transform(mat);
 		}
+		for mesh in self.indexed_meshes.iter_mut() {
+			mesh.transform(mat);
+		}
 	}
 	
 	pub fn scale(&mut self, x: f32, y: f32, z: f32) {
 		for mesh in self.meshes.iter_mut() {
 			mesh.scale(x, y, z);
 		}
+		for mesh in self.indexed_meshes.iter_mut() {
+			mesh.scale(x, y, z);
+		}
 	}
 	
 	pub fn translate(&mut self, x: f32, y: f32, z: f32) {
 		for mesh in self.meshes.iter_mut() {
 			mesh.translate(x, y, z);
 		}
+		for mesh in self.indexed_meshes.iter_mut() {
+			mesh.translate(x, y, z);
+		}
 	}
 	
 }
@@ -8,11 +8,15 @@ pub struct Vertex {
 	pub vec: Vec3,
 	pub color: Color,
 	pub tex_coord: Vec2,
+	pub normal: Vec3, //zero if unknown
+	pub joints: [u16; 4], //indices in the joints of the skin
+	pub weights: [f32; 4], //all zero if the vertex is not skinned
 }
 
 #[derive(Clone)]
 pub struct Shape {
 	pub tex_id: i32,
+	pub material: Option<usize>, //index in Scene::materials the vertex colors were copied from
 	pub vertices: Vec<Vertex>,
 } 
 
@@ -22,23 +26,32 @@ impl Vertex {
 			vec: Vec3::new_empty(),
 			color: BLACK.clone(),
 			tex_coord: Vec2::new_empty(),
+			normal: Vec3::new_empty(),
+			joints: [0; 4],
+			weights: [0.0; 4],
 		}
 	}
 	
 	pub fn new_init(vec: Vec3, color: Color, tex_coord: Vec2) -> Vertex {
-		Vertex{ vec, color, tex_coord}
+		Vertex{ vec, color, tex_coord, normal: Vec3::new_empty(), joints: [0; 4], weights: [0.0; 4] }
 	}
 	
 	pub fn set_from(&mut self, vertex: &Vertex) {
 		self.vec.set_from(&vertex.vec);
 		self.tex_coord.set_from(&vertex.tex_coord);
 		self.color.set_from_color(&vertex.color);
+		self.normal.set_from(&vertex.normal);
+		self.joints = vertex.joints;
+		self.weights = vertex.weights;
 	}
 	
 	pub fn set_to(&self, vertex: &mut Vertex) {
 		vertex.vec.set_from(&self.vec);
 		vertex.tex_coord.set_from(&self.tex_coord);
 		vertex.color.set_from_color(&self.color);
+		vertex.normal.set_from(&self.normal);
+		vertex.joints = self.joints;
+		vertex.weights = self.weights;
 	}
 	
 }
@@ -46,11 +59,11 @@ impl Vertex {
 impl Shape {
 	
 	pub fn new( cnt_vertex: usize) -> Shape {
-		Shape { tex_id: -1, vertices: Vec::with_capacity(cnt_vertex) }
+		Shape { tex_id: -1, material: None, vertices: Vec::with_capacity(cnt_vertex) }
 	}
 	
 	pub fn new_init(tex_id: i32, vertices: &Vec<Vertex>) -> Shape {
-		Shape { tex_id , vertices: vertices.to_vec() }
+		Shape { tex_id , material: None, vertices: vertices.to_vec() }
 	}
 	
 	pub fn new_point(vec: &Vec3) -> Shape {
@@ -90,6 +103,7 @@ impl Shape {
 		}
 	}
 	
+	//scale, translate and transform bake into the vertex positions, Mesh::model keeps them untouched
 	pub fn scale(&mut self, x: f32, y: f32, z: f32) {
 		for vertex in self.vertices.iter_mut() {
 			vertex.vec.x *= x;
@@ -106,6 +120,19 @@ impl Shape {
 		}
 	}
 	
+	//normal of a triangle by its counter clockwise winding, zero for points, lines and degenerated triangles
+	pub fn face_normal(&self, normal: &mut Vec3) {
+		normal.set(0.0, 0.0, 0.0);
+		if self.vertices.len() != 3 { return; }
+		let mut edge1: Vec3 = self.vertices[1].vec.clone();
+		edge1 -= &self.vertices[0].vec;
+		let mut edge2: Vec3 = self.vertices[2].vec.clone();
+		edge2 -= &self.vertices[0].vec;
+		edge1.cross_to(&edge2, normal);
+		if normal.len() == 0.0 { return; }
+		normal.normalize();
+	}
+	
 	pub fn transform(&mut self, mat: &Mat3) {
 		for vertex in self.vertices.iter_mut() {
 			vertex.vec *= mat;
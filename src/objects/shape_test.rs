@@ -1,7 +1,9 @@
 use objects::shape::*;
+use objects::mesh::*;
 use math::vec::vec3::*;
 use math::mat::mat3::*;
 use color::*;
+use test_util::*;
 
 #[test]
 fn point() {
@@ -37,4 +39,41 @@ fn triangle() {
 	assert_eq!(shape.vertices[1].color == WHITE, true);
 	assert_eq!(shape.vertices[2].vec == vec3, true);
 	assert_eq!(shape.vertices[2].color == BLUE, true);
-}
\ No newline at end of file
+}
+
+#[test]
+fn face_normal_degenerated() {
+	let mut normal: Vec3 = Vec3::new(1., 1., 1.);
+	let flat: Shape = Shape::new_triangle(&Vec3::new(0., 0., 0.), &Vec3::new(1., 1., 1.), &Vec3::new(2., 2., 2.));
+	flat.face_normal(&mut normal);
+	assert_eq!(normal == Vec3::new(0., 0., 0.), true);
+	
+	let shape: Shape = Shape::new_triangle(&Vec3::new(0., 0., 0.), &Vec3::new(2., 0., 0.), &Vec3::new(0., 2., 0.));
+	shape.face_normal(&mut normal);
+	assert_eq!(normal == Vec3::new(0., 0., 1.), true);
+}
+
+#[test]
+fn mesh_transform_and_scale_normals() {
+	//the normal of the plane x + y = 1
+	let mut mesh: Mesh = MeshBuilder::triangle(&Vec3::new(1., 0., 0.), &Vec3::new(0., 1., 0.), &Vec3::new(0., 1., 1.));
+	let s: f32 = 0.5f32.sqrt();
+	for vertex in mesh.shapes[0].vertices.iter_mut() {
+		vertex.normal = Vec3::new(s, s, 0.);
+	}
+	let mut rotz: Mat3 = Mat3::new_empty();
+	rotz.to_rotz(30.);
+	let mut baked: Mesh = mesh.clone();
+	baked.scale_model(2., 1., 1.);
+	baked.rotate_model(&rotz);
+	baked.bake_model();
+	
+	//the plane becomes x / 2 + y = 1, its normal (0.5, 1, 0) normalized
+	mesh.scale(2., 1., 1.);
+	let n: f32 = 1.25f32.sqrt();
+	assert_close(&mesh.shapes[0].vertices[0].normal, &Vec3::new(0.5 / n, 1. / n, 0.), 1e-5);
+	mesh.transform(&rotz);
+	for (a, b) in mesh.shapes[0].vertices.iter().zip(baked.shapes[0].vertices.iter()) {
+		assert_close(&a.normal, &b.normal, 1e-5);
+	}
+}
@@ -0,0 +1,149 @@
+/*
+	Skeletal skinning. A skin names the joint nodes of the scene with their inverse bind matrices, every vertex references
+	up to four joints by their position in the skin and weights them. The joints are ordinary nodes, so node tracks of
+	an animation clip move the skeleton. Skinned vertices end up in world space, the transform of the node drawing the
+	mesh and the model matrix of the mesh are not used, like in glTF.
+*/
+use math::vec::vec3::*;
+use math::mat::mat4::*;
+use objects::node::*;
+use objects::scene::*;
+use objects::shape::*;
+use quaternion::*;
+use error::*;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SkinningMethod {
+	Linear, //blends the joint matrices, strongly twisted joints lose volume
+	DualQuaternion, //blends the rigid part of the joints and keeps the volume, scales of the joints are ignored
+}
+
+#[derive(Clone)]
+pub struct Skin {
+	pub name: String,
+	pub joints: Vec<String>, //node names, the position is the joint index of the vertices
+	pub inverse_bind: Vec<Mat4>, //from mesh space into the joint in the bind pose
+	pub method: SkinningMethod,
+}
+
+//joint matrices of a skin for one pose of the scene
+#[derive(Clone)]
+pub struct SkinPose {
+	pub method: SkinningMethod,
+	pub matrices: Vec<Mat4>, //world matrix of the joint * inverse bind matrix
+	dual: Vec<(Quaternion, Quaternion)>, //real and dual part of the matrices for DualQuaternion
+}
+
+impl Skin {
+	pub fn new(name: &str) -> Skin {
+		Skin { name: name.to_string(), joints: Vec::new(), inverse_bind: Vec::new(), method: SkinningMethod::Linear }
+	}
+	
+	pub fn add_joint(&mut self, node: &str, inverse_bind: Mat4) {
+		self.joints.push(node.to_string());
+		self.inverse_bind.push(inverse_bind);
+	}
+	
+	//joint matrices with the current transforms of the scene nodes
+	pub fn pose(&self, scene: &Scene) -> RenderResult<SkinPose> {
+		if self.joints.len() != self.inverse_bind.len() {
+			return Err(RenderError::InvalidModel(format!("skin {} has {} joints but {} inverse bind matrices", self.name, self.joints.len(), self.inverse_bind.len())));
+		}
+		let mut worlds: Vec<Option<Mat4>> = vec![None; self.joints.len()];
+		scene.visit_nodes(|node, world| {
+			for (i, joint) in self.joints.iter().enumerate() {
+				if worlds[i].is_none() && *joint == node.name {
+					let mut mat: Mat4 = Mat4::new_empty();
+					mat.set_from(world);
+					worlds[i] = Some(mat);
+				}
+			}
+		});
+		let mut matrices: Vec<Mat4> = Vec::with_capacity(self.joints.len());
+		for (i, world) in worlds.into_iter().enumerate() {
+			match world {
+				Some(mut mat) => {
+					mat *= &self.inverse_bind[i];
+					matrices.push(mat);
+				},
+				None => return Err(RenderError::InvalidModel(format!("joint {} of skin {} does not exist", self.joints[i], self.name))),
+			}
+		}
+		Ok(SkinPose::new(self.method, matrices))
+	}
+}
+
+impl SkinPose {
+	pub fn new(method: SkinningMethod, matrices: Vec<Mat4>) -> SkinPose {
+		let mut dual: Vec<(Quaternion, Quaternion)> = Vec::new();
+		if method == SkinningMethod::DualQuaternion {
+			for mat in &matrices {
+				let (mut t, mut real, mut scale) = (Vec3::new_empty(), Quaternion::new_identity(), Vec3::new_empty());
+				decompose(mat, &mut t, &mut real, &mut scale);
+				//the dual part is half the translation times the rotation
+				let d: Quaternion = Quaternion::new(0.0, t.x, t.y, t.z) * &real;
+				dual.push((real, Quaternion::new(0.5 * d.w, 0.5 * d.x, 0.5 * d.y, 0.5 * d.z)));
+			}
+		}
+		SkinPose { method, matrices, dual }
+	}
+	
+	pub fn check_vertex(&self, vertex: &Vertex) -> RenderResult<()> {
+		for i in 0..4 {
+			if vertex.weights[i] != 0.0 && vertex.joints[i] as usize >= self.matrices.len() {
+				return Err(RenderError::InvalidModel(format!("vertex references joint {} of {}", vertex.joints[i], self.matrices.len())));
+			}
+		}
+		Ok(())
+	}
+	
+	//position of the vertex in the pose, None if it has no weights, the weights are normalized to a sum of 1
+	pub fn skin_point(&self, vertex: &Vertex) -> Option<Vec3> {
+		let sum: f32 = vertex.weights.iter().sum();
+		if sum == 0.0 {
+			return None;
+		}
+		let v: &Vec3 = &vertex.vec;
+		match self.method {
+			SkinningMethod::Linear => {
+				let mut out: Vec3 = Vec3::new_empty();
+				for i in 0..4 {
+					let weight: f32 = vertex.weights[i] / sum;
+					if weight == 0.0 { continue; }
+					let mut p: Vec3 = Vec3::new(v.x, v.y, v.z);
+					transform_point(&self.matrices[vertex.joints[i] as usize], &mut p);
+					out.x += weight * p.x;
+					out.y += weight * p.y;
+					out.z += weight * p.z;
+				}
+				Some(out)
+			},
+			SkinningMethod::DualQuaternion => {
+				let (mut real, mut dual) = (Quaternion::new(0.0, 0.0, 0.0, 0.0), Quaternion::new(0.0, 0.0, 0.0, 0.0));
+				let mut first: Option<&Quaternion> = None;
+				for i in 0..4 {
+					if vertex.weights[i] == 0.0 { continue; }
+					let (ref r, ref d) = self.dual[vertex.joints[i] as usize];
+					//q and -q are the same rotation, blend along the shorter way
+					let reference: &Quaternion = *first.get_or_insert(r);
+					let weight: f32 = if reference.dot(r) < 0.0 { -vertex.weights[i] / sum } else { vertex.weights[i] / sum };
+					real = Quaternion::new(real.w + weight * r.w, real.x + weight * r.x, real.y + weight * r.y, real.z + weight * r.z);
+					dual = Quaternion::new(dual.w + weight * d.w, dual.x + weight * d.x, dual.y + weight * d.y, dual.z + weight * d.z);
+				}
+				let len: f32 = real.norm();
+				if len == 0.0 {
+					return None;
+				}
+				real = Quaternion::new(real.w / len, real.x / len, real.y / len, real.z / len);
+				dual = Quaternion::new(dual.w / len, dual.x / len, dual.y / len, dual.z / len);
+				let t: Quaternion = dual * &real.conjugate();
+				let mut out: Vec3 = Vec3::new(v.x, v.y, v.z);
+				real.rotate(&mut out);
+				out.x += 2.0 * t.x;
+				out.y += 2.0 * t.y;
+				out.z += 2.0 * t.z;
+				Some(out)
+			},
+		}
+	}
+}
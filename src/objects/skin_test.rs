@@ -0,0 +1,68 @@
+use objects::skin::*;
+use objects::scene::*;
+use objects::node::*;
+use objects::shape::*;
+use objects::animation::*;
+use quaternion::*;
+use color::*;
+use error::*;
+use math::vec::vec2::*;
+use math::vec::vec3::*;
+use test_util::*;
+
+fn skinned_vertex(x: f32, y: f32, z: f32, joints: [u16; 4], weights: [f32; 4]) -> Vertex {
+	let mut vertex: Vertex = Vertex::new_init(Vec3::new(x, y, z), WHITE.clone(), Vec2::new_empty());
+	vertex.joints = joints;
+	vertex.weights = weights;
+	vertex
+}
+
+#[test]
+fn skin_linear_and_dual_quaternion_blend() {
+	let mut scene: Scene = Scene::new(0);
+	let mut root: Node = Node::new("root");
+	let mut bone: Node = Node::new("bone");
+	bone.rotate_quaternion(&Quaternion::from_axis_angle(&Vec3::new(0.0, 0.0, 1.0), 90.0));
+	root.children.push(bone);
+	scene.nodes.push(root);
+	let mut skin: Skin = Skin::new("arm");
+	skin.add_joint("root", identity());
+	skin.add_joint("bone", identity());
+	
+	//halfway between two joints linear blending shrinks the arm, dual quaternions keep its length
+	let half: Vertex = skinned_vertex(1.0, 0.0, 0.0, [0, 1, 0, 0], [1.0, 1.0, 0.0, 0.0]);
+	let linear: Vec3 = skin.pose(&scene).unwrap().skin_point(&half).unwrap();
+	assert_close(&linear, &Vec3::new(0.5, 0.5, 0.0), 1e-4);
+	skin.method = SkinningMethod::DualQuaternion;
+	let s: f32 = (0.5f32).sqrt();
+	let dual: Vec3 = skin.pose(&scene).unwrap().skin_point(&half).unwrap();
+	assert_close(&dual, &Vec3::new(s, s, 0.0), 1e-4);
+	
+	//a single joint moves rigidly with both methods, the inverse bind matrix is applied first
+	scene.find_node_mut("bone").unwrap().translate(0.0, 2.0, 0.0);
+	skin.inverse_bind[1] = translation(-1.0, 0.0, 0.0);
+	let rigid: Vertex = skinned_vertex(2.0, 0.0, 0.0, [1, 0, 0, 0], [0.5, 0.0, 0.0, 0.0]);
+	let dual: Vec3 = skin.pose(&scene).unwrap().skin_point(&rigid).unwrap();
+	skin.method = SkinningMethod::Linear;
+	let linear: Vec3 = skin.pose(&scene).unwrap().skin_point(&rigid).unwrap();
+	assert_close(&linear, &Vec3::new(0.0, 3.0, 0.0), 1e-4);
+	assert_close(&dual, &linear, 1e-4);
+	
+	//joints are nodes, so node tracks animate the skeleton
+	let mut clip: Clip = Clip::new("wave");
+	clip.add(Target::Node("bone".to_string()), Channel::Position(vec![Keyframe::new(0.0, Vec3::new(0.0, 4.0, 0.0))]), Interpolation::Step);
+	clip.apply(&mut scene, 0.0).unwrap();
+	assert_close(&skin.pose(&scene).unwrap().skin_point(&rigid).unwrap(), &Vec3::new(0.0, 5.0, 0.0), 1e-4);
+	
+	let pose: SkinPose = skin.pose(&scene).unwrap();
+	assert_eq!(pose.skin_point(&skinned_vertex(1.0, 0.0, 0.0, [0; 4], [0.0; 4])).is_none(), true);
+	match pose.check_vertex(&skinned_vertex(1.0, 0.0, 0.0, [0, 2, 0, 0], [0.5, 0.5, 0.0, 0.0])) {
+		Err(RenderError::InvalidModel(_)) => {},
+		_ => panic!("joints beyond the skin must be rejected"),
+	}
+	skin.add_joint("missing", identity());
+	match skin.pose(&scene) {
+		Err(RenderError::InvalidModel(_)) => {},
+		_ => panic!("joints without a node must be rejected"),
+	}
+}
@@ -0,0 +1,165 @@
+use math::vec::vec3::*;
+use math::mat::mat3::*;
+use math::mat::mat4::*;
+use std::ops::*;
+
+//unit quaternions describe rotations, angles are in degrees like Mat3::to_rotx
+#[derive(Clone, PartialEq, Debug)]
+pub struct Quaternion {
+	pub w: f32,
+	pub x: f32,
+	pub y: f32,
+	pub z: f32,
+}
+
+impl Quaternion {
+	pub fn new(w: f32, x: f32, y: f32, z: f32) -> Quaternion {
+		Quaternion { w, x, y, z }
+	}
+	
+	pub fn new_identity() -> Quaternion {
+		Quaternion { w: 1.0, x: 0.0, y: 0.0, z: 0.0 }
+	}
+	
+	pub fn from_axis_angle(axis: &Vec3, deg: f32) -> Quaternion {
+		let mut axis: Vec3 = axis.clone();
+		axis.normalize();
+		let (s, c) = (deg.to_radians() * 0.5).sin_cos();
+		Quaternion { w: c, x: axis.x * s, y: axis.y * s, z: axis.z * s }
+	}
+	
+	//rotates around x first, then y and z, like the product rotz * roty * rotx of Mat3
+	pub fn from_euler(x_deg: f32, y_deg: f32, z_deg: f32) -> Quaternion {
+		let mut q: Quaternion = Quaternion::from_axis_angle(&Vec3::new(0.0, 0.0, 1.0), z_deg);
+		q *= &Quaternion::from_axis_angle(&Vec3::new(0.0, 1.0, 0.0), y_deg);
+		q *= &Quaternion::from_axis_angle(&Vec3::new(1.0, 0.0, 0.0), x_deg);
+		q
+	}
+	
+	//mat has to be a rotation, scales must be removed before
+	pub fn from_mat3(mat: &Mat3) -> Quaternion {
+		let trace: f32 = mat._11 + mat._22 + mat._33;
+		let mut q: Quaternion = if trace > 0.0 {
+			let s: f32 = (trace + 1.0).sqrt() * 2.0;
+			Quaternion::new(0.25 * s, (mat._32 - mat._23) / s, (mat._13 - mat._31) / s, (mat._21 - mat._12) / s)
+		} else if mat._11 > mat._22 && mat._11 > mat._33 {
+			let s: f32 = (1.0 + mat._11 - mat._22 - mat._33).sqrt() * 2.0;
+			Quaternion::new((mat._32 - mat._23) / s, 0.25 * s, (mat._12 + mat._21) / s, (mat._13 + mat._31) / s)
+		} else if mat._22 > mat._33 {
+			let s: f32 = (1.0 + mat._22 - mat._11 - mat._33).sqrt() * 2.0;
+			Quaternion::new((mat._13 - mat._31) / s, (mat._12 + mat._21) / s, 0.25 * s, (mat._23 + mat._32) / s)
+		} else {
+			let s: f32 = (1.0 + mat._33 - mat._11 - mat._22).sqrt() * 2.0;
+			Quaternion::new((mat._21 - mat._12) / s, (mat._13 + mat._31) / s, (mat._23 + mat._32) / s, 0.25 * s)
+		};
+		q.normalize();
+		q
+	}
+	
+	//uses the upper 3x3 part, the translation is ignored
+	pub fn from_mat4(mat: &Mat4) -> Quaternion {
+		Quaternion::from_mat3(&Mat3::new(mat._11, mat._12, mat._13,
+										 mat._21, mat._22, mat._23,
+										 mat._31, mat._32, mat._33))
+	}
+	
+	pub fn to_mat3(&self) -> Mat3 {
+		let (w, x, y, z) = (self.w, self.x, self.y, self.z);
+		Mat3::new(1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - z * w), 2.0 * (x * z + y * w),
+				  2.0 * (x * y + z * w), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - x * w),
+				  2.0 * (x * z - y * w), 2.0 * (y * z + x * w), 1.0 - 2.0 * (x * x + y * y))
+	}
+	
+	pub fn to_mat4(&self) -> Mat4 {
+		let m: Mat3 = self.to_mat3();
+		Mat4::new(m._11, m._12, m._13, 0.0,
+				  m._21, m._22, m._23, 0.0,
+				  m._31, m._32, m._33, 0.0,
+				  0.0, 0.0, 0.0, 1.0)
+	}
+	
+	pub fn set_from(&mut self, q: &Quaternion) {
+		self.w = q.w;
+		self.x = q.x;
+		self.y = q.y;
+		self.z = q.z;
+	}
+	
+	pub fn norm(&self) -> f32 {
+		self.dot(self).sqrt()
+	}
+	
+	pub fn normalize(&mut self) {
+		let len: f32 = self.norm();
+		if len == 0.0 {
+			*self = Quaternion::new_identity();
+			return;
+		}
+		self.w /= len;
+		self.x /= len;
+		self.y /= len;
+		self.z /= len;
+	}
+	
+	pub fn dot(&self, q: &Quaternion) -> f32 {
+		self.w * q.w + self.x * q.x + self.y * q.y + self.z * q.z
+	}
+	
+	//the inverse of a unit quaternion
+	pub fn conjugate(&self) -> Quaternion {
+		Quaternion::new(self.w, -self.x, -self.y, -self.z)
+	}
+	
+	pub fn rotate(&self, vec: &mut Vec3) {
+		//v + 2w(u x v) + 2u x (u x v) with u the vector part
+		let (ux, uy, uz) = (self.x, self.y, self.z);
+		let tx: f32 = 2.0 * (uy * vec.z - uz * vec.y);
+		let ty: f32 = 2.0 * (uz * vec.x - ux * vec.z);
+		let tz: f32 = 2.0 * (ux * vec.y - uy * vec.x);
+		vec.x += self.w * tx + uy * tz - uz * ty;
+		vec.y += self.w * ty + uz * tx - ux * tz;
+		vec.z += self.w * tz + ux * ty - uy * tx;
+	}
+	
+	//spherical interpolation along the shorter arc, t = 0 gives self and t = 1 gives q
+	pub fn slerp(&self, q: &Quaternion, t: f32) -> Quaternion {
+		let mut to: Quaternion = q.clone();
+		let mut cos: f32 = self.dot(q);
+		if cos < 0.0 {
+			to = Quaternion::new(-q.w, -q.x, -q.y, -q.z);
+			cos = -cos;
+		}
+		//nearly equal rotations interpolate linearly to avoid the division by a tiny sine
+		let (s0, s1) = if cos > 0.9995 {
+			(1.0 - t, t)
+		} else {
+			let angle: f32 = cos.min(1.0).acos();
+			let sin: f32 = angle.sin();
+			(((1.0 - t) * angle).sin() / sin, (t * angle).sin() / sin)
+		};
+		let mut result: Quaternion = Quaternion::new(s0 * self.w + s1 * to.w, s0 * self.x + s1 * to.x,
+													 s0 * self.y + s1 * to.y, s0 * self.z + s1 * to.z);
+		result.normalize();
+		result
+	}
+}
+
+//self = self * q, the rotation q is applied before self
+impl<'a> MulAssign<&'a Quaternion> for Quaternion {
+	fn mul_assign(&mut self, q: &'a Quaternion) {
+		let (w, x, y, z) = (self.w, self.x, self.y, self.z);
+		self.w = w * q.w - x * q.x - y * q.y - z * q.z;
+		self.x = w * q.x + x * q.w + y * q.z - z * q.y;
+		self.y = w * q.y - x * q.z + y * q.w + z * q.x;
+		self.z = w * q.z + x * q.y - y * q.x + z * q.w;
+	}
+}
+
+impl<'a> Mul<&'a Quaternion> for Quaternion {
+	type Output = Quaternion;
+	
+	fn mul(mut self, q: &'a Quaternion) -> Quaternion {
+		self *= q;
+		self
+	}
+}
@@ -0,0 +1,91 @@
+use quaternion::*;
+use camera::*;
+use objects::node::*;
+use math::vec::vec3::*;
+use test_util::*;
+use math::mat::mat3::*;
+use math::mat::mat4::*;
+
+#[test]
+fn quaternion_rotation_and_matrices() {
+	let q: Quaternion = Quaternion::from_axis_angle(&Vec3::new(0.0, 0.0, 2.0), 90.0);
+	let mut vec: Vec3 = Vec3::new(1.0, 0.0, 0.0);
+	q.rotate(&mut vec);
+	assert_close(&vec, &Vec3::new(0.0, 1.0, 0.0), 1e-5);
+	
+	//matrix and quaternion rotate the same way and convert back
+	let q: Quaternion = Quaternion::from_euler(30.0, -60.0, 170.0);
+	let m: Mat3 = q.to_mat3();
+	let mut by_q: Vec3 = Vec3::new(0.3, -1.2, 2.0);
+	let mut by_m: Vec3 = by_q.clone();
+	q.rotate(&mut by_q);
+	by_m *= &m;
+	assert_close(&by_q, &by_m, 1e-5);
+	let back: Quaternion = Quaternion::from_mat3(&m);
+	assert_eq!((back.dot(&q).abs() - 1.0).abs() < 1e-5, true);
+	assert_eq!((Quaternion::from_mat4(&q.to_mat4()).dot(&q).abs() - 1.0).abs() < 1e-5, true);
+	
+	//x first turns (0,1,0) to (0,0,1), then y turns it to (1,0,0)
+	let mut vec: Vec3 = Vec3::new(0.0, 1.0, 0.0);
+	Quaternion::from_euler(90.0, 90.0, 0.0).rotate(&mut vec);
+	assert_close(&vec, &Vec3::new(1.0, 0.0, 0.0), 1e-5);
+	
+	let mut vec: Vec3 = Vec3::new(0.5, 0.5, 0.5);
+	let mut inverse: Quaternion = q.conjugate();
+	inverse *= &q;
+	inverse.rotate(&mut vec);
+	assert_close(&vec, &Vec3::new(0.5, 0.5, 0.5), 1e-5);
+}
+
+#[test]
+fn quaternion_slerp() {
+	let a: Quaternion = Quaternion::new_identity();
+	let b: Quaternion = Quaternion::from_axis_angle(&Vec3::new(0.0, 1.0, 0.0), 120.0);
+	assert_eq!((a.slerp(&b, 0.0).dot(&a) - 1.0).abs() < 1e-5, true);
+	assert_eq!((a.slerp(&b, 1.0).dot(&b) - 1.0).abs() < 1e-5, true);
+	
+	let half: Quaternion = a.slerp(&b, 0.25);
+	let expected: Quaternion = Quaternion::from_axis_angle(&Vec3::new(0.0, 1.0, 0.0), 30.0);
+	assert_eq!((half.dot(&expected) - 1.0).abs() < 1e-5, true);
+	
+	//the negated quaternion is the same rotation, slerp takes the short way
+	let negated: Quaternion = Quaternion::new(-b.w, -b.x, -b.y, -b.z);
+	assert_eq!((a.slerp(&negated, 0.25).dot(&expected).abs() - 1.0).abs() < 1e-5, true);
+}
+
+#[test]
+fn quaternion_camera_and_node() {
+	let from: Vec3 = Vec3::new(1.0, 2.0, 3.0);
+	let to: Vec3 = Vec3::new(-1.0, 0.5, -2.0);
+	let mut camera: Camera = Camera::new_init(&from, &to, -1.0, 1.0, 1.0, -1.0, 1.0, 10.0);
+	camera.to_perspective(&from, &to, -1.0, 1.0, 1.0, -1.0, 1.0, 10.0);
+	let (mut view, mut transformation) = (Mat4::new_empty(), Mat4::new_empty());
+	view.set_from(&camera.view);
+	transformation.set_from(&camera.transformation);
+	
+	//orienting the camera with its own orientation reproduces look_at
+	let q: Quaternion = camera.orientation();
+	camera.orient_perspective(&from, &q);
+	assert_close(&camera.to, &to, 1e-5);
+	let m = [(&view, &camera.view), (&transformation, &camera.transformation)];
+	for &(a, b) in m.iter() {
+		assert_eq!((a._11 - b._11).abs() < 1e-4 && (a._23 - b._23).abs() < 1e-4 && (a._34 - b._34).abs() < 1e-4, true);
+	}
+	
+	//looking straight down has no world up to lose, the camera looks along -forward
+	let down: Quaternion = Quaternion::from_axis_angle(&Vec3::new(1.0, 0.0, 0.0), -90.0);
+	camera.orient_ortho(&from, &down);
+	assert_close(&camera.forward, &Vec3::new(0.0, 1.0, 0.0), 1e-5);
+	assert_close(&camera.up, &Vec3::new(0.0, 0.0, -1.0), 1e-5);
+	assert_eq!(camera.view._11.is_nan(), false);
+	let mut below: Vec3 = Vec3::new(1.0, 0.0, 3.0);
+	transform_point(&camera.view, &mut below);
+	assert_close(&below, &Vec3::new(0.0, 0.0, -2.0), 1e-5);
+	
+	let mut node: Node = Node::new("node");
+	node.rotate_quaternion(&Quaternion::from_axis_angle(&Vec3::new(0.0, 0.0, 1.0), 90.0));
+	node.translate(0.0, 0.0, 1.0);
+	let mut vec: Vec3 = Vec3::new(1.0, 0.0, 0.0);
+	transform_point(&node.transform, &mut vec);
+	assert_close(&vec, &Vec3::new(0.0, 1.0, 1.0), 1e-5);
+}
@@ -4,13 +4,43 @@ use math::vec::vec3::*;
 use math::mat::mat4::*;
 use math::utils::*;
 use texture::*;
+use texture::format::netpbm::*;
+use texture::format::exr::*;
 use camera::*;
+use backend::*;
+use error::*;
+use diagnostics::*;
+use stats::*;
+use frame_sink::*;
 use objects::shape::*;
 use objects::mesh::*;
+use objects::indexed_mesh::*;
+use objects::instance::*;
+use objects::skin::*;
+use objects::morph::*;
 use objects::scene::*;
 use std;
 use std::io::Write;                                                                                                                                                                                                                                                                                                                            
 use std::fs::File;   
+use std::path::Path;
+use std::io::BufWriter;
+use std::time::Instant;
+
+//camera projection of a vertex, rejected if it lies behind the camera
+#[derive(Clone)]
+pub struct ProjectedVertex {
+	pub ndc: Vec3,
+	pub raster: Vec3,
+	pub weight: f32,
+	pub rz: f32, //reciprocal raster depth
+	pub rejected: bool,
+}
+
+impl ProjectedVertex {
+	pub fn new() -> ProjectedVertex {
+		ProjectedVertex { ndc: Vec3::new_empty(), raster: Vec3::new_empty(), weight: 1.0, rz: 0.0, rejected: false }
+	}
+}
 
 pub struct Renderer {
 	pub img_width: i32,
@@ -30,39 +60,46 @@ pub struct Renderer {
 	pub bgcolor: Color,
 	pub min_z: f32,
 	pub max_z: f32,
+	pub diagnostics: Option<Box<dyn DiagnosticSink>>,
+	pub overdraw_buffer: Vec<u32>,
+	stats: RenderStats,
+	transformation: Mat4, //camera transformation composed with the model matrix of the current draw
+	tint: Color, //color factors of the current draw, 1.0 keeps the colors
+	skin: Option<SkinPose>, //joint matrices of the current draw if it is skinned
 }
 
 impl Renderer {
-
+	
 	pub fn new(img_width: i32, img_height: i32, bgcolor: &Color, samplestep: u32) -> Renderer {
 		let us: u32 = samplestep * samplestep;
-		let bw: u32 = img_width as u32 * us;
-		let buffersize: usize = (img_width * img_height * us as i32) as usize;
 		
 		let mut renderer = Renderer {
 			camera: Camera::new(),
 			min_z: std::f32::MAX, max_z: 0.0,
 			texture: Vec::new(),
-			img_width, img_height,
-			img_width_half: img_width as f32 * 0.5,
-			img_height_half: img_height as f32 * 0.5,
-			buf_width : bw as i32,
-			buf_height: img_height,
+			img_width: 0, img_height: 0,
+			img_width_half: 0.0,
+			img_height_half: 0.0,
+			buf_width : 0,
+			buf_height: 0,
 			samplestep,
-			frame_buffer: Vec::with_capacity(buffersize),
-			z_buffer: Vec::with_capacity(buffersize),
+			frame_buffer: Vec::new(),
+			z_buffer: Vec::new(),
 			used_samples: us,
 			sample_factor: 1.0 / us as f32,
 			samples: Vec::with_capacity(us as usize),
-			bgcolor: bgcolor.clone()
+			bgcolor: bgcolor.clone(),
+			diagnostics: None,
+			overdraw_buffer: Vec::new(),
+			stats: RenderStats::new(),
+			transformation: Mat4::new_empty(),
+			tint: Color::new(1.0, 1.0, 1.0),
+			skin: None,
 		};
 		
-		for i in 0..buffersize {
-			renderer.z_buffer.push(std::f32::MAX);
-			renderer.frame_buffer.push(BLACK.clone());
-		}
+		renderer._init_buffers(img_width, img_height);
 		
-		for i in 0..us {
+		for _i in 0..us {
 			renderer.samples.push(Vec3::new_empty());
 		}
 		
@@ -78,19 +115,141 @@ impl Renderer {
 		renderer
 	}
 	
+	//cameras with fit_aspect follow the image size, so non square images are not stretched
+	fn _fit_camera(&mut self) {
+		if self.camera.fit_aspect && self.img_height > 0 {
+			self.camera.set_aspect(self.img_width as f32 / self.img_height as f32);
+		}
+	}
+	
+	fn _init_buffers(&mut self, img_width: i32, img_height: i32) {
+		let buffersize: usize = (img_width * img_height * self.used_samples as i32) as usize;
+		
+		self.img_width = img_width;
+		self.img_height = img_height;
+		self.img_width_half = img_width as f32 * 0.5;
+		self.img_height_half = img_height as f32 * 0.5;
+		self.buf_width = (img_width as u32 * self.used_samples) as i32;
+		self.buf_height = img_height;
+		
+		self.z_buffer = Vec::with_capacity(buffersize);
+		self.frame_buffer = Vec::with_capacity(buffersize);
+		self.overdraw_buffer = Vec::with_capacity(buffersize);
+		for _i in 0..buffersize {
+			self.z_buffer.push(std::f32::MAX);
+			self.frame_buffer.push(BLACK.clone());
+			self.overdraw_buffer.push(0);
+		}
+		
+		self.min_z = std::f32::MAX;
+		self.max_z = 0.0;
+		self.stats.reset();
+	}
+	
+	fn _resolve_color(&self, x: i32, y: i32, color: &mut Color) {
+		color.set_from_rgb(0.0, 0.0, 0.0);
+		let samplestart: usize = (y * self.buf_width) as usize + (x as u32 * self.used_samples) as usize;
+		for sample in 0..self.used_samples {
+			let c: &Color = &self.frame_buffer[samplestart + sample as usize];
+			color.r += c.r;
+			color.g += c.g;
+			color.b += c.b;
+		}
+	}
+	
+	fn _resolve_z(&self, x: i32, y: i32) -> f32 {
+		let samplestart: usize = (y * self.buf_width) as usize + (x as u32 * self.used_samples) as usize;
+		let mut z: f32 = 0.0;
+		for sample in 0..self.used_samples {
+			let sz: f32 = self.z_buffer[samplestart + sample as usize];
+			if sz == std::f32::MAX { return std::f32::MAX; }
+			z += sz;
+		}
+		z * self.sample_factor
+	}
+	
+	pub fn set_diagnostics(&mut self, sink: Box<dyn DiagnosticSink>) {
+		self.diagnostics = Some(sink);
+	}
+	
+	fn _diag_enabled(&self, level: DiagnosticLevel) -> bool {
+		match self.diagnostics {
+			Some(ref sink) => sink.enabled(level),
+			None => false,
+		}
+	}
+	
+	fn _diag(&self, level: DiagnosticLevel, message: &str) {
+		if let Some(ref sink) = self.diagnostics {
+			sink.log(level, message);
+		}
+	}
+	
+	//counters of everything rendered since the last clear_frame
+	pub fn frame_report(&self) -> FrameReport {
+		let mut report: FrameReport = FrameReport::new();
+		report.triangles_submitted = self.stats.triangles;
+		report.triangles_clipped = self.stats.rejected_w_triangles;
+		report.triangles_culled = self.stats.rejected_culled + self.stats.rejected_zero_area;
+		report.samples_shaded = self.stats.depth_passes;
+		report.min_z = self.min_z;
+		report.max_z = self.max_z;
+		report
+	}
+	
+	//statistics of everything rendered since the last clear_frame
+	pub fn render_stats(&self) -> RenderStats {
+		let mut stats: RenderStats = self.stats.clone();
+		stats.set_overdraw_from(&self.overdraw_buffer);
+		stats
+	}
+	
+	//per pixel heat map of the highest sample overdraw, see stats::overdraw_color
+	pub fn overdraw_heatmap(&self) -> Texture {
+		let mut heatmap: Texture = Texture::new(self.img_width as u32, self.img_height as u32);
+		let max_cnt: f32 = (OVERDRAW_BUCKETS - 1) as f32;
+		for j in 0..self.img_height {
+			let bi: usize = (j * self.buf_width) as usize;
+			for i in 0..self.img_width {
+				let samplestart: usize = bi + (i as u32 * self.used_samples) as usize;
+				let mut cnt: u32 = 0;
+				for sample in 0..self.used_samples {
+					cnt = cnt.max(self.overdraw_buffer[samplestart + sample as usize]);
+				}
+				overdraw_color(cnt as f32, max_cnt, &mut heatmap.buffer[(j * self.img_width + i) as usize]);
+			}
+		}
+		heatmap
+	}
+	
+	fn _count_depth_pass(&mut self, bi: &u32, z: f32) {
+		self.overdraw_buffer[*bi as usize] += 1;
+		self.stats.depth_passes += 1;
+		
+		//only for z buffer print 
+		self.min_z = self.min_z.min(z);
+		self.max_z = self.max_z.max(z);
+	}
+	
+	pub fn report_frame(&self) {
+		if self._diag_enabled(DiagnosticLevel::Debug) {
+			self._diag(DiagnosticLevel::Debug, &self.frame_report().to_string());
+		}
+	}
+	
 	fn _set_color_to_fb_(&mut self, bi: &u32, new_color: &Color) {
 		let fbc = &mut self.frame_buffer[*bi as usize];
 		let sf: &f32 = &self.sample_factor;
-		fbc.r = new_color.r * sf;
-		fbc.g = new_color.g * sf;
-		fbc.b = new_color.b * sf;
+		fbc.r = new_color.r * sf * self.tint.r;
+		fbc.g = new_color.g * sf * self.tint.g;
+		fbc.b = new_color.b * sf * self.tint.b;
 	}
 	
-
-	fn _compute_px_color( &self, color: &mut Color, bc: &Barycentric, weight1: &f32 , weight2: &f32 , weight3: &f32 ,
-						  img_w: &i32, v1c: &Color, v2c: &Color, v3c: &Color,
-						  v1t: &Vec2, v2t: &Vec2, v3t: &Vec2, tex_id: &i32 ) {
 	
+	fn _compute_px_color( &self, color: &mut Color, bc: &Barycentric, weight1: &f32 , weight2: &f32 , weight3: &f32 ,
+						  v1c: &Color, v2c: &Color, v3c: &Color,
+						  v1t: &Vec2, v2t: &Vec2, v3t: &Vec2, tex_id: &i32 ) -> RenderResult<()> {
+		
 		let z0:f32  = bc.bc0*(*weight1);
 		let z1:f32  = bc.bc1*(*weight2);
 		let z2:f32  = bc.bc2*(*weight3);
@@ -103,15 +262,25 @@ impl Renderer {
 				color.b = (z0*v1c.b + z1*v2c.b + z2*v3c.b ) * z3;
 				}
 			_ => {
-				let texx: usize = (( z0*v1t.x + z1*v2t.x + z2*v3t.x ) * z3 * 512.0) as usize;
-				let texy: usize = (( z0*v1t.y + z1*v2t.y + z2*v3t.y ) * z3 * 512.0) as usize;
+				//an empty texture has no texel to sample
+				let texture: &Texture = match self.texture.get(*tex_id as usize) {
+					Some(texture) if *tex_id >= 0 && texture.width > 0 && texture.height > 0 => texture,
+					_ => return Err(RenderError::InvalidTextureId(*tex_id)),
+				};
+				let tw: f32 = texture.width as f32;
+				let th: f32 = texture.height as f32;
 				
-				let txc: &Color = &self.texture[0].buffer[texy * (*img_w as usize) + texx];
+				//coordinates of 1.0 or slightly outside of the texture by interpolation are clamped to the border
+				let texx: usize = ((( z0*v1t.x + z1*v2t.x + z2*v3t.x ) * z3 * tw).max(0.0).min(tw - 1.0)) as usize;
+				let texy: usize = ((( z0*v1t.y + z1*v2t.y + z2*v3t.y ) * z3 * th).max(0.0).min(th - 1.0)) as usize;
+				
+				let txc: &Color = &texture.buffer[texy * (texture.width as usize) + texx];
 				color.r = txc.r;
 				color.g = txc.g;
 				color.b = txc.b;
 			}						
 		}
+		Ok(())
 	}
 	
 	fn _compute_and_set_z(&mut self, rz1: &f32, rz2: &f32, rz3: &f32, bc: &Barycentric, bi: &u32) -> bool {
@@ -119,15 +288,17 @@ impl Renderer {
 		z += *rz2 * bc.bc1;
 		z += *rz3 * bc.bc2;
 		
-		let old_z: &mut f32 = &mut self.z_buffer[*bi as usize];
-		
-		if z > *old_z  { return true; }
+		self.stats.fragments_tested += 1;
 		
-		*old_z = z;
+		{
+			let old_z: &mut f32 = &mut self.z_buffer[*bi as usize];
+			
+			if z > *old_z  { return true; }
+			
+			*old_z = z;
+		}
 		
-		//only for z buffer print 
-		self.min_z = self.min_z.min(z);
-		self.max_z = self.max_z.max(z);
+		self._count_depth_pass(bi, z);
 		
 		false
 	}
@@ -136,30 +307,34 @@ impl Renderer {
 		let mut z = *rz1 * bc.bc0;
 		z += *rz2 * bc.bc1;
 		
-		let old_z: &mut f32 = &mut self.z_buffer[*bi as usize];
+		self.stats.fragments_tested += 1;
 		
-		if z > *old_z  { return true; }
+		{
+			let old_z: &mut f32 = &mut self.z_buffer[*bi as usize];
+			
+			if z > *old_z  { return true; }
+			
+			*old_z = z;
+		}
 		
-		*old_z = z;
-		
-		//only for z buffer print 
-		self.min_z = self.min_z.min(z);
-		self.max_z = self.max_z.max(z);
+		self._count_depth_pass(bi, z);
 		
 		false
 	}
-
+	
 	fn _compute_and_set_z_point(&mut self, rz1: &f32, bi: &u32) -> bool {
 		
-		let old_z: &mut f32 = &mut self.z_buffer[*bi as usize];
+		self.stats.fragments_tested += 1;
 		
-		if *rz1 > *old_z  { return true; }
-		
-		*old_z = *rz1;
+		{
+			let old_z: &mut f32 = &mut self.z_buffer[*bi as usize];
+			
+			if *rz1 > *old_z  { return true; }
+			
+			*old_z = *rz1;
+		}
 		
-		//only for z buffer print 
-		self.min_z = self.min_z.min(*rz1);
-		self.max_z = self.max_z.max(*rz1);
+		self._count_depth_pass(bi, *rz1);
 		
 		false
 	}
@@ -173,7 +348,7 @@ impl Renderer {
 	
 	fn _compute_sample_bc_and_check(cursample: &Vec3, cur_w: &u32, cur_h: &u32,
 										 bc: &mut Barycentric, p_raster1: &Vec3, p_raster2: &Vec3,p_raster3: &Vec3) -> bool {
-	
+		
 		let mut pixel_sample = Vec3 { x: 0.0, y: 0.0, z: 0.0 };
 		Renderer::update_sample(&mut pixel_sample, cursample, cur_w, cur_h);
 		
@@ -189,7 +364,7 @@ impl Renderer {
 		bc.bc0 = bc.w0_12 * bc.area;
 		bc.bc1 = bc.w1_20 * bc.area;
 		bc.bc2 = bc.w2_01 * bc.area;
-	
+		
 		false
 	}
 	
@@ -214,7 +389,7 @@ impl Renderer {
 	
 	fn _compute_sample_and_check_point(cur_w: &mut u32, cur_h: &mut u32,
 									   img_w: &i32, img_h: &i32, p_raster1: &Vec3) -> bool {
-						
+		
 		if (p_raster1.x >= 0.0) &&
 			 (p_raster1.x <= *img_w as f32) &&
 			 (p_raster1.y >= 0.0) &&
@@ -223,10 +398,10 @@ impl Renderer {
 				*cur_h = p_raster1.y as u32;
 				return false;
 			 }
-			
+		
 		true
 	}
-
+	
 	fn _compute_min_max_w_h(maxx: &mut u32, maxy: &mut u32, minx: &mut u32,miny: &mut u32,
 							cur_w: &mut u32, cur_h: &mut u32, img_w: &i32, img_h: &i32,
 							p_raster1: &Vec3, p_raster2: &Vec3,p_raster3: &Vec3) {
@@ -241,7 +416,7 @@ impl Renderer {
 	fn _compute_min_max_w_h_line(maxx: &mut u32, maxy: &mut u32, minx: &mut u32,miny: &mut u32,
 								 cur_w: &mut u32, cur_h: &mut u32, img_w: &i32, img_h: &i32,
 								 p_raster1: &Vec3, p_raster2: &Vec3) {
-								 
+		
 		*maxx = (*img_w as u32).min((p_raster1.x as u32).max(p_raster2.x as u32));
 		*maxy = (*img_h as u32).min((p_raster1.y as u32).max(p_raster2.y as u32));
 		*minx = (0 as u32).max((p_raster1.x as u32).min(p_raster2.x as u32));
@@ -252,7 +427,7 @@ impl Renderer {
 	
 	fn _world_to_raster(v: &Vec3, ndc: &mut Vec3, raster: &mut Vec3, weight: &mut f32,
 					   img_w_h: &f32, img_h_h: &f32, rz3: &mut f32, ct: &Mat4) -> bool {	
-							 
+		
 		ndc.x += (v.x * ct._11) + (v.y * ct._12) + (v.z * ct._13);// + ct._14;
 		ndc.y += (v.x * ct._21) + (v.y * ct._22) + (v.z * ct._23);// + ct._24;
 		ndc.z += (v.x * ct._31) + (v.y * ct._32) + (v.z * ct._33);// + ct._34;
@@ -268,19 +443,44 @@ impl Renderer {
 		false
 	}
 	
-	fn render_point(&mut self, shape: &Shape ) {
+	//projects a vertex with the current camera, the result can be shared by all primitives using the vertex
+	fn _project(&mut self, vertex: &Vertex, p: &mut ProjectedVertex) {
+		let skinned: Option<Vec3> = match self.skin {
+			Some(ref pose) => pose.skin_point(vertex),
+			None => None,
+		};
+		let v: &Vec3 = skinned.as_ref().unwrap_or(&vertex.vec);
+		p.ndc.set(self.transformation._14, self.transformation._24, self.transformation._34);
+		p.weight = self.transformation._44;
+		p.rejected = Renderer::_world_to_raster(v, &mut p.ndc, &mut p.raster, &mut p.weight, 
+												&self.img_width_half, &self.img_height_half, &mut p.rz, &self.transformation);
+		self.stats.vertices_transformed += 1;
+	}
+	
+	fn render_point(&mut self, v1: &Vertex ) {
+		let start: Instant = Instant::now();
+		let mut p1: ProjectedVertex = ProjectedVertex::new();
+		self._project(v1, &mut p1);
+		self.stats.vertex_time += start.elapsed();
+		self._raster_point(v1, &p1);
+	}
+	
+	fn _raster_point(&mut self, v1: &Vertex, p1: &ProjectedVertex) {
+		let start: Instant = Instant::now();
+		self.stats.points += 1;
 		
-		//let ct: &Mat4 = &self.camera.transformation;
-		let mut p_ndc1 = Vec3{x: self.camera.transformation._14, y: self.camera.transformation._24, z: self.camera.transformation._34};
-		let mut p_raster1: Vec3 = Vec3 { x: 0.0, y: 0.0, z: 0.0 };
-		let mut weight1 = self.camera.transformation._44;
-		let rz1: &mut f32 = &mut 0.0;
-		let v1: &Vertex = &shape.vertices[0]; 
-		let v1v: &Vec3 = &v1.vec;
+		let p_raster1: &Vec3 = &p1.raster;
+		let rz1: &f32 = &p1.rz;
 		let v1c: &Color = &v1.color;
 		
-		if Renderer::_world_to_raster(v1v, &mut p_ndc1, &mut p_raster1, &mut weight1, 
-									  &self.img_width_half, &self.img_height_half, rz1, &self.camera.transformation) { return; }
+		if p1.rejected { 
+			self.stats.rejected_w += 1;
+			self.stats.vertex_time += start.elapsed();
+			return; 
+		}
+		
+		let raster_start: Instant = Instant::now();
+		self.stats.vertex_time += raster_start - start;
 		
 		let maxx: u32 = 1; 
 		let maxy: u32 = 1; 
@@ -293,9 +493,9 @@ impl Renderer {
 		while cur_h < maxy {
 			let mut cur_w: u32 = minx;
 			while cur_w < maxx {				
-	
-				for sample in 0..used_samples {
 				
+				for sample in 0..used_samples {
+					
 					if Renderer::_compute_sample_and_check_point(
 									&mut cur_w, &mut cur_h, &self.img_width, &self.img_height, &p_raster1) { continue; }
 					
@@ -312,48 +512,57 @@ impl Renderer {
 			cur_h += 1;
 		}
 		
+		self.stats.raster_time += raster_start.elapsed();
 	}
-	fn render_line(&mut self, shape: &Shape ) {
 	
-		let vertices: &Vec<Vertex> = &shape.vertices;
-		let v1: &Vertex = &vertices[0]; 
-		let v2: &Vertex = &vertices[1];
+	fn render_line(&mut self, v1: &Vertex, v2: &Vertex ) {
+		let start: Instant = Instant::now();
+		let mut p1: ProjectedVertex = ProjectedVertex::new();
+		let mut p2: ProjectedVertex = ProjectedVertex::new();
+		self._project(v1, &mut p1);
+		self._project(v2, &mut p2);
+		self.stats.vertex_time += start.elapsed();
+		self._raster_line(v1, v2, &p1, &p2);
+	}
+	
+	fn _raster_line(&mut self, v1: &Vertex, v2: &Vertex, p1: &ProjectedVertex, p2: &ProjectedVertex) {
+		let start: Instant = Instant::now();
+		self.stats.lines += 1;
+		
 		let v1v: &Vec3 = &v1.vec;
 		let v2v: &Vec3 = &v2.vec;
 		let v1c: &Color = &v1.color;
 		let mut cursample: Vec3;
-		let mut p_ndc1: Vec3 = Vec3{x: self.camera.transformation._14,y: self.camera.transformation._24,z: self.camera.transformation._34}; 
-		let mut p_ndc2: Vec3 = Vec3{x: self.camera.transformation._14,y: self.camera.transformation._24,z: self.camera.transformation._34}; 
-		let mut p_raster1: Vec3= Vec3 {x: 0.0, y: 0.0, z: 0.0}; 
-		let mut p_raster2: Vec3= Vec3 {x: 0.0, y: 0.0, z: 0.0}; 
+		let (p_ndc1, p_ndc2): (&Vec3, &Vec3) = (&p1.ndc, &p2.ndc);
+		let (p_raster1, p_raster2): (&Vec3, &Vec3) = (&p1.raster, &p2.raster);
 		let mut cur_w: u32 = 0;
 		let mut cur_h: u32 = 0;
 		let mut maxx: u32 = 0; 
 		let mut maxy: u32 = 0;
 		let mut minx: u32 = 0;
 		let mut miny: u32 = 0; 
-		let mut weight1: f32 = self.camera.transformation._44; 
-		let mut weight2: f32 = self.camera.transformation._44; 
-		let mut rz1: f32 = 0.0; 
-		let mut rz2: f32 = 0.0;
+		let mut rz1: f32 = p1.rz; 
+		let mut rz2: f32 = p2.rz;
 		let mut bc: Barycentric = Barycentric::new();
 		
-		if Renderer::_world_to_raster(v1v, &mut p_ndc1, &mut p_raster1, &mut weight1, 
-									  &self.img_width_half, &self.img_height_half, &mut rz1, &self.camera.transformation) { return; }
-		if Renderer::_world_to_raster(v2v, &mut p_ndc2, &mut p_raster2, &mut weight2, 
-									  &self.img_width_half, &self.img_height_half, &mut rz2, &self.camera.transformation) { return; }
+		if p1.rejected || p2.rejected { 
+			self.stats.rejected_w += 1;
+			self.stats.vertex_time += start.elapsed();
+			return; 
+		}
 		
-		//println!("v1: {} {} {}", v1v.x, v1v.y, v1v.z);
-		//println!("n1: {} {} {}", p_ndc1.x, p_ndc1.y, p_ndc1.z);
-		//println!("r1: {} {} {}", p_raster1.x, p_raster1.y, p_raster1.z);
-		//println!("v2: {} {} {}", v2v.x, v2v.y, v2v.z);
-		//println!("n2: {} {} {}", p_ndc2.x, p_ndc2.y, p_ndc2.z);
-		//println!("r2: {} {} {}", p_raster2.x, p_raster2.y, p_raster2.z);
+		if self._diag_enabled(DiagnosticLevel::Trace) {
+			self._diag(DiagnosticLevel::Trace, &format!("line v1: {} {} {} n1: {} {} {} r1: {} {} {}", 
+					   v1v.x, v1v.y, v1v.z, p_ndc1.x, p_ndc1.y, p_ndc1.z, p_raster1.x, p_raster1.y, p_raster1.z));
+			self._diag(DiagnosticLevel::Trace, &format!("line v2: {} {} {} n2: {} {} {} r2: {} {} {}", 
+					   v2v.x, v2v.y, v2v.z, p_ndc2.x, p_ndc2.y, p_ndc2.z, p_raster2.x, p_raster2.y, p_raster2.z));
+		}
 		
 		Renderer::_compute_min_max_w_h_line(&mut maxx, &mut maxy, &mut minx, &mut miny, &mut cur_w, &mut cur_h, 
 											&self.img_width, &self.img_height, &p_raster1, &p_raster2);
 		
-		//println!("minx: {} maxy: {}", minx, maxy);
+		let raster_start: Instant = Instant::now();
+		self.stats.vertex_time += raster_start - start;
 		
 		while cur_h < maxy {
 			let mut cur_w: u32 = minx;
@@ -363,7 +572,7 @@ impl Renderer {
 				for sample in 0..self.used_samples {
 					{
 						let cursample: &Vec3 = &self.samples[sample as usize];
-					
+						
 						if Renderer::_compute_sample_bc_and_check_line(cursample,&cur_w, &cur_h, &mut bc,
 											 &p_raster1, &p_raster2) { continue; }
 					}
@@ -378,13 +587,24 @@ impl Renderer {
 			
 			cur_h += 1;
 		}
+		
+		self.stats.raster_time += raster_start.elapsed();
 	}
 	
-	fn render_triangle(&mut self, shape: &Shape ) {
-		let vertices: &Vec<Vertex> = &shape.vertices;
-		let v1: &Vertex = &vertices[0]; 
-		let v2: &Vertex = &vertices[1];
-		let v3: &Vertex = &vertices[2];
+	fn render_triangle(&mut self, v1: &Vertex, v2: &Vertex, v3: &Vertex, tex_id: &i32 ) -> RenderResult<()> {
+		let start: Instant = Instant::now();
+		let mut p1: ProjectedVertex = ProjectedVertex::new();
+		let mut p2: ProjectedVertex = ProjectedVertex::new();
+		let mut p3: ProjectedVertex = ProjectedVertex::new();
+		self._project(v1, &mut p1);
+		self._project(v2, &mut p2);
+		self._project(v3, &mut p3);
+		self.stats.vertex_time += start.elapsed();
+		self._raster_triangle(v1, v2, v3, &p1, &p2, &p3, tex_id)
+	}
+	
+	fn _raster_triangle(&mut self, v1: &Vertex, v2: &Vertex, v3: &Vertex, 
+						p1: &ProjectedVertex, p2: &ProjectedVertex, p3: &ProjectedVertex, tex_id: &i32 ) -> RenderResult<()> {
 		let v1v: &Vec3 = &v1.vec;
 		let v2v: &Vec3 = &v2.vec;
 		let v3v: &Vec3 = &v3.vec;
@@ -395,49 +615,59 @@ impl Renderer {
 		let v2t: &Vec2 = &v2.tex_coord;
 		let v3t: &Vec2 = &v3.tex_coord;
 		let mut cursample: Vec3;
-		let mut p_ndc1: Vec3 = Vec3{x: self.camera.transformation._14,y: self.camera.transformation._24,z: self.camera.transformation._34}; 
-		let mut p_ndc2: Vec3 = Vec3{x: self.camera.transformation._14,y: self.camera.transformation._24,z: self.camera.transformation._34};
-		let mut p_ndc3: Vec3 = Vec3{x: self.camera.transformation._14,y: self.camera.transformation._24,z: self.camera.transformation._34}; 
-		let mut p_raster1: Vec3= Vec3 {x: 0.0, y: 0.0, z: 0.0}; 
-		let mut p_raster2: Vec3= Vec3 {x: 0.0, y: 0.0, z: 0.0};
-		let mut p_raster3: Vec3= Vec3 {x: 0.0, y: 0.0, z: 0.0}; 
+		let (p_ndc1, p_ndc2, p_ndc3): (&Vec3, &Vec3, &Vec3) = (&p1.ndc, &p2.ndc, &p3.ndc);
+		let (p_raster1, p_raster2, p_raster3): (&Vec3, &Vec3, &Vec3) = (&p1.raster, &p2.raster, &p3.raster);
 		let mut cur_w: u32 = 0;
 		let mut cur_h: u32 = 0;
 		let mut maxx: u32 = 0; 
 		let mut maxy: u32 = 0;
 		let mut minx: u32 = 0;
 		let mut miny: u32 = 0; 
-		let mut weight1: f32 = self.camera.transformation._44; 
-		let mut weight2: f32 = self.camera.transformation._44; 
-		let mut weight3: f32 = self.camera.transformation._44;
-		let mut rz1: f32 = 0.0; 
-		let mut rz2: f32 = 0.0;
-		let mut rz3: f32 = 0.0;
+		let (weight1, weight2, weight3): (f32, f32, f32) = (p1.weight, p2.weight, p3.weight);
+		let mut rz1: f32 = p1.rz; 
+		let mut rz2: f32 = p2.rz;
+		let mut rz3: f32 = p3.rz;
 		let mut bc: Barycentric = Barycentric::new();
 		
-		if Renderer::_world_to_raster(v1v, &mut p_ndc1, &mut p_raster1, &mut weight1, 
-									  &self.img_width_half, &self.img_height_half, &mut rz1, &self.camera.transformation) { return; }
-		if Renderer::_world_to_raster(v2v, &mut p_ndc2, &mut p_raster2, &mut weight2, 
-									  &self.img_width_half, &self.img_height_half, &mut rz2, &self.camera.transformation) { return; }
-		if Renderer::_world_to_raster(v3v, &mut p_ndc3, &mut p_raster3, &mut weight3, 
-									  &self.img_width_half, &self.img_height_half, &mut rz3, &self.camera.transformation) { return; }
-		
-		println!("v1: {} {} {}", v1v.x, v1v.y, v1v.z);
-		println!("n1: {} {} {}", p_ndc1.x, p_ndc1.y, p_ndc1.z);
-		println!("r1: {} {} {}", p_raster1.x, p_raster1.y, p_raster1.z);
-		println!("v2: {} {} {}", v2v.x, v2v.y, v2v.z);
-		println!("n2: {} {} {}", p_ndc2.x, p_ndc2.y, p_ndc2.z);
-		println!("r2: {} {} {}", p_raster2.x, p_raster2.y, p_raster2.z);
-		println!("v3: {} {} {}", v3v.x, v3v.y, v3v.z);
-		println!("n3: {} {} {}", p_ndc3.x, p_ndc3.y, p_ndc3.z);
-		println!("r3: {} {} {}", p_raster3.x, p_raster3.y, p_raster3.z);
-		
-		bc.area = 1.0/((p_raster3.x - p_raster1.x) * (p_raster2.y - p_raster1.y) - (p_raster3.y - p_raster1.y) * (p_raster2.x - p_raster1.x));
+		let start: Instant = Instant::now();
+		self.stats.triangles += 1;
+		
+		if p1.rejected || p2.rejected || p3.rejected { 
+			self.stats.rejected_w += 1;
+			self.stats.rejected_w_triangles += 1;
+			self.stats.vertex_time += start.elapsed();
+			return Ok(()); 
+		}
+		
+		if self._diag_enabled(DiagnosticLevel::Trace) {
+			self._diag(DiagnosticLevel::Trace, &format!("triangle v1: {} {} {} n1: {} {} {} r1: {} {} {}", 
+					   v1v.x, v1v.y, v1v.z, p_ndc1.x, p_ndc1.y, p_ndc1.z, p_raster1.x, p_raster1.y, p_raster1.z));
+			self._diag(DiagnosticLevel::Trace, &format!("triangle v2: {} {} {} n2: {} {} {} r2: {} {} {}", 
+					   v2v.x, v2v.y, v2v.z, p_ndc2.x, p_ndc2.y, p_ndc2.z, p_raster2.x, p_raster2.y, p_raster2.z));
+			self._diag(DiagnosticLevel::Trace, &format!("triangle v3: {} {} {} n3: {} {} {} r3: {} {} {}", 
+					   v3v.x, v3v.y, v3v.z, p_ndc3.x, p_ndc3.y, p_ndc3.z, p_raster3.x, p_raster3.y, p_raster3.z));
+		}
+		
+		let area: f32 = (p_raster3.x - p_raster1.x) * (p_raster2.y - p_raster1.y) - (p_raster3.y - p_raster1.y) * (p_raster2.x - p_raster1.x);
+		
+		//back facing or degenerated triangles can not pass the edge tests
+		if area <= 0.0 {
+			if area == 0.0 {
+				self.stats.rejected_zero_area += 1;
+			} else {
+				self.stats.rejected_culled += 1;
+			}
+			self.stats.vertex_time += start.elapsed();
+			return Ok(());
+		}
+		
+		bc.area = 1.0/area;
 		
 		Renderer::_compute_min_max_w_h(&mut maxx, &mut maxy, &mut minx, &mut miny, &mut cur_w, &mut cur_h, 
 											&self.img_width, &self.img_height, &p_raster1, &p_raster2, &p_raster3);
 		
-		println!("minx: {} maxy: {}", minx, maxy);
+		let raster_start: Instant = Instant::now();
+		self.stats.vertex_time += raster_start - start;
 		
 		let mut cur_color: Color = Color {r: 0.0, g: 0.0, b: 0.0};
 		
@@ -449,7 +679,7 @@ impl Renderer {
 				for sample in 0..self.used_samples {
 					{
 						let cursample: &Vec3 = &self.samples[sample as usize];
-					
+						
 						if Renderer::_compute_sample_bc_and_check(cursample,&cur_w, &cur_h, &mut bc,
 											 &p_raster1, &p_raster2, &p_raster3) { continue; }
 					}
@@ -458,7 +688,7 @@ impl Renderer {
 					if self._compute_and_set_z(&mut rz1, &mut rz2, &mut rz3, &bc, &bi) { continue; }
 					
 					self._compute_px_color(&mut cur_color, &bc, &weight1, &weight2, &weight3,
-										   &self.img_width, v1c, v2c, v3c, v1t, v2t, v3t, &shape.tex_id);
+										   v1c, v2c, v3c, v1t, v2t, v3t, tex_id)?;
 					
 					self._set_color_to_fb_(&bi, &cur_color);
 				}
@@ -468,74 +698,144 @@ impl Renderer {
 			cur_h += 1;
 		}
 		
-		println!("minz: {} maxz: {}", self.min_z, self.max_z);
+		self.stats.raster_time += raster_start.elapsed();
+		
+		Ok(())
+	}
+	
+	//model is given in world space, None draws the vertices as they are
+	fn _begin_draw(&mut self, model: Option<&Mat4>) {
+		self.transformation.set_from(&self.camera.transformation);
+		if let Some(model) = model {
+			self.transformation *= model;
+		}
+		self.tint.set_all(1.0);
+		self.skin = None;
+	}
+	
+	pub fn render_shape(&mut self, shape: &Shape) -> RenderResult<()> {
+		self._begin_draw(None);
+		self._render_shape(shape)
 	}
 	
-	pub fn render_shape(&mut self, shape: &Shape) {
-		match shape.vertices.len() {
-			3 => self.render_triangle(shape),
-			2 => self.render_line(shape),
-			1 => self.render_point(shape),
-			_ => println!("WTF!!! Invalid Shape"),
+	fn _render_shape(&mut self, shape: &Shape) -> RenderResult<()> {
+		let vertices: &Vec<Vertex> = &shape.vertices;
+		match vertices.len() {
+			3 => self.render_triangle(&vertices[0], &vertices[1], &vertices[2], &shape.tex_id)?,
+			2 => self.render_line(&vertices[0], &vertices[1]),
+			1 => self.render_point(&vertices[0]),
+			cnt => return Err(RenderError::InvalidShape(cnt)),
 		}
+		Ok(())
+	}
+	
+	fn _render_mesh(&mut self, mesh: &Mesh) -> RenderResult<()> {
+		self.transformation *= &mesh.model;
+		self._render_shapes(mesh)
 	}
 	
-	pub fn render_mesh(&mut self, mesh: &Mesh) {
+	//morphed meshes are drawn from a blended copy of every shape
+	fn _render_shapes(&mut self, mesh: &Mesh) -> RenderResult<()> {
+		let start: Instant = Instant::now();
+		let morphed: bool = is_morphed(&mesh.morph_targets, &mesh.morph_weights);
+		if morphed {
+			check_morph(&mesh.morph_targets, mesh.vertex_count())?;
+		}
+		let mut result: RenderResult<()> = Ok(());
+		let mut first: usize = 0;
 		for shape in mesh.shapes.iter() {
-			self.render_shape(shape);
+			result = if morphed {
+				let mut blended: Shape = shape.clone();
+				apply_morph(&mut blended.vertices, first, &mesh.morph_targets, &mesh.morph_weights);
+				self._render_shape(&blended)
+			} else {
+				self._render_shape(shape)
+			};
+			if result.is_err() { break; }
+			first += shape.vertices.len();
 		}
+		self.stats.total_time += start.elapsed();
+		result
 	}
 	
-	pub fn render_scene(&mut self, scene: &Scene) {
-		for mesh in scene.meshes.iter() {
-			self.render_mesh(mesh);
+	//projects the vertex on first use in this draw, later primitives reuse the result
+	fn _project_cached(&mut self, vertices: &[Vertex], index: u32, cache: &mut Vec<ProjectedVertex>, cached: &mut Vec<bool>) {
+		let i: usize = index as usize;
+		if cached[i] {
+			self.stats.vertex_cache_hits += 1;
+			return;
 		}
-	}	
+		self._project(&vertices[i], &mut cache[i]);
+		cached[i] = true;
+	}
 	
-	pub fn clear_frame(&mut self) {
-		let buffersize: usize = (self.img_width as u32 * self.img_height as u32 * self.samplestep * self.samplestep) as usize;
-		for i in 0..buffersize {
-			self.z_buffer[i] = std::f32::MAX;
-			self.frame_buffer[i].set_from_color(&BLACK);
+	//vertices are read straight from the shared buffer, no shapes are built, morphing blends a copy of the buffer
+	fn _render_indexed(&mut self, mesh: &IndexedMesh) -> RenderResult<()> {
+		mesh.check_indices()?;
+		let mut blended: Vec<Vertex> = Vec::new();
+		if is_morphed(&mesh.morph_targets, &mesh.morph_weights) {
+			check_morph(&mesh.morph_targets, mesh.vertices.len())?;
+			blended = mesh.vertices.clone();
+			apply_morph(&mut blended, 0, &mesh.morph_targets, &mesh.morph_weights);
 		}
-		self.min_z = std::f32::MAX;
-		self.max_z = 0.0;
+		let vertices: &Vec<Vertex> = if blended.is_empty() { &mesh.vertices } else { &blended };
+		let mut cache: Vec<ProjectedVertex> = vec![ProjectedVertex::new(); vertices.len()];
+		let mut cached: Vec<bool> = vec![false; vertices.len()];
+		let mut prim: [u32; 3] = [0; 3];
+		for i in 0..mesh.primitive_count() {
+			let cnt: usize = mesh.primitive(i, &mut prim);
+			let start: Instant = Instant::now();
+			for index in prim[..cnt].iter() {
+				self._project_cached(vertices, *index, &mut cache, &mut cached);
+			}
+			self.stats.vertex_time += start.elapsed();
+			
+			let (v, p) = (vertices, &cache);
+			let (i1, i2, i3) = (prim[0] as usize, prim[1] as usize, prim[2] as usize);
+			match cnt {
+				3 => self._raster_triangle(&v[i1], &v[i2], &v[i3], &p[i1], &p[i2], &p[i3], &mesh.tex_id)?,
+				2 => self._raster_line(&v[i1], &v[i2], &p[i1], &p[i2]),
+				_ => self._raster_point(&v[i1], &p[i1]),
+			}
+		}
+		Ok(())
 	}
 	
-	pub fn write_fb_ppm(&self, filename: &'static str) {
-		let mut f = File::create(filename).expect("Unable to create file");
-		write!(f, "P6\n{} {}\n255\n", self.img_width, self.img_height).expect("Unable to write header");
+	pub fn write_fb_ppm<P: AsRef<Path>>(&self, filename: P) -> RenderResult<()> {
+		let mut f = File::create(filename)?;
+		write!(f, "P6\n{} {}\n255\n", self.img_width, self.img_height)?;
 		let mut fc: Color = BLACK.clone();
-
+		
 		for j in 0..self.img_height {
-			let bi: usize = (j * self.buf_width) as usize;
 			for i in 0..self.img_width {
-				fc.set_from_rgb(0.0, 0.0, 0.0);
-
-				let samplestart: usize = bi + (i as u32 * self.used_samples) as usize;
-				for sample in 0..self.used_samples{
-					let c: &Color = &self.frame_buffer[samplestart + sample as usize];
-					fc.r += c.r;
-					fc.g += c.g;
-					fc.b += c.b;
-				}
+				self._resolve_color(i, j, &mut fc);
 				
 				let col: &[u8] = &[fc.r as u8, fc.g  as u8, fc.b as u8];
-				f.write_all(col).expect("Unable to write data");				
+				f.write_all(col)?;
 			}
 		}
+		Ok(())
 	}	
 	
-	pub fn write_zb_ppm(&self, filename: &'static str) {
-		let mut f = File::create(filename).expect("Unable to create file");
-		write!(f, "P6\n{} {}\n255\n", self.img_width, self.img_height).expect("Unable to write header");
+	pub fn write_fb_png<P: AsRef<Path>>(&self, filename: P) -> RenderResult<()> {
+		self.read_frame().write_as_png(filename)
+	}
+	
+	//hands the resolved frame to an image sequence or video
+	pub fn write_frame_to(&self, sink: &mut dyn FrameSink) -> RenderResult<()> {
+		sink.write_frame(&self.read_frame())
+	}
+	
+	pub fn write_zb_ppm<P: AsRef<Path>>(&self, filename: P) -> RenderResult<()> {
+		let mut f = File::create(filename)?;
+		write!(f, "P6\n{} {}\n255\n", self.img_width, self.img_height)?;
 		let mut _color: f32= 0.0;
-
+		
 		for j in 0..self.img_height {
 			let bi: usize = (j * self.buf_width) as usize;
 			for i in 0..self.img_width {
 				_color = 0.0;
-
+				
 				let samplestart: usize = bi + (i as u32 * self.used_samples) as usize;
 				for sample in 0..self.used_samples{
 					_color += &self.z_buffer[samplestart + sample as usize];
@@ -550,9 +850,275 @@ impl Renderer {
 				}
 				
 				let col: &[u8] = &[_color as u8, _color as u8, _color as u8];
-				f.write_all(col).expect("Unable to write data");				
+				f.write_all(col)?;
 			}
 		}
+		Ok(())
 	}		
 	
-}
\ No newline at end of file
+	//unclamped resolved colors, one float per channel with 255 mapped to 1.0 like write_pfm
+	fn _frame_channels(&self) -> (Vec<f32>, Vec<f32>, Vec<f32>) {
+		let frame: Texture = self.read_frame();
+		(frame.buffer.iter().map(|c| c.r / 255.0).collect(),
+		 frame.buffer.iter().map(|c| c.g / 255.0).collect(),
+		 frame.buffer.iter().map(|c| c.b / 255.0).collect())
+	}
+	
+	pub fn write_fb_pfm<P: AsRef<Path>>(&self, filename: P) -> RenderResult<()> {
+		let mut f = BufWriter::new(File::create(filename)?);
+		write_pfm(&self.read_frame(), &mut f)
+	}
+	
+	//raw depth values, f32::MAX where nothing was drawn
+	pub fn write_zb_pfm<P: AsRef<Path>>(&self, filename: P) -> RenderResult<()> {
+		let mut f = BufWriter::new(File::create(filename)?);
+		write_pfm_data(&mut f, self.img_width as u32, self.img_height as u32, 1, &self.read_depth())
+	}
+	
+	pub fn write_fb_exr<P: AsRef<Path>>(&self, filename: P) -> RenderResult<()> {
+		let (r, g, b) = self._frame_channels();
+		let mut f = BufWriter::new(File::create(filename)?);
+		write_exr(&mut f, self.img_width as u32, self.img_height as u32, &[("R", &r), ("G", &g), ("B", &b)])
+	}
+	
+	pub fn write_zb_exr<P: AsRef<Path>>(&self, filename: P) -> RenderResult<()> {
+		let mut f = BufWriter::new(File::create(filename)?);
+		write_exr(&mut f, self.img_width as u32, self.img_height as u32, &[("Z", &self.read_depth())])
+	}
+	
+	//the RenderBackend methods are inherent too, so callers do not need the trait in scope
+	pub fn render_mesh(&mut self, mesh: &Mesh) -> RenderResult<()> {
+		self._begin_draw(None);
+		self._render_mesh(mesh)
+	}
+	
+	pub fn render_mesh_transformed(&mut self, mesh: &Mesh, model: &Mat4) -> RenderResult<()> {
+		self._begin_draw(Some(model));
+		self._render_mesh(mesh)
+	}
+	
+	pub fn render_mesh_instances(&mut self, mesh: &Mesh, instances: &[Instance]) -> RenderResult<()> {
+		for instance in instances {
+			self._begin_draw(Some(&instance.transform));
+			self.tint.set_from_color(&instance.tint);
+			self.tint *= 1.0 / 255.0;
+			self.stats.instances += 1;
+			self._render_mesh(mesh)?;
+		}
+		self.tint.set_all(1.0);
+		Ok(())
+	}
+	
+	pub fn render_indexed_mesh(&mut self, mesh: &IndexedMesh) -> RenderResult<()> {
+		self._begin_draw(None);
+		let start: Instant = Instant::now();
+		let result: RenderResult<()> = self._render_indexed(mesh);
+		self.stats.total_time += start.elapsed();
+		result
+	}
+	
+	pub fn render_indexed_mesh_transformed(&mut self, mesh: &IndexedMesh, model: &Mat4) -> RenderResult<()> {
+		self._begin_draw(Some(model));
+		let start: Instant = Instant::now();
+		let result: RenderResult<()> = self._render_indexed(mesh);
+		self.stats.total_time += start.elapsed();
+		result
+	}
+	
+	pub fn render_mesh_skinned(&mut self, mesh: &Mesh, pose: &SkinPose) -> RenderResult<()> {
+		for shape in mesh.shapes.iter() {
+			for vertex in shape.vertices.iter() {
+				pose.check_vertex(vertex)?;
+			}
+		}
+		self._begin_draw(None);
+		self.skin = Some(pose.clone());
+		let result: RenderResult<()> = self._render_shapes(mesh);
+		self.skin = None;
+		result
+	}
+	
+	pub fn render_indexed_mesh_skinned(&mut self, mesh: &IndexedMesh, pose: &SkinPose) -> RenderResult<()> {
+		for vertex in mesh.vertices.iter() {
+			pose.check_vertex(vertex)?;
+		}
+		self._begin_draw(None);
+		self.skin = Some(pose.clone());
+		let start: Instant = Instant::now();
+		let result: RenderResult<()> = self._render_indexed(mesh);
+		self.stats.total_time += start.elapsed();
+		self.skin = None;
+		result
+	}
+	
+	pub fn render_scene(&mut self, scene: &Scene) -> RenderResult<RenderStats> {
+		if scene.nodes.is_empty() {
+			for mesh in scene.meshes.iter() {
+				self.render_mesh(mesh)?;
+			}
+			for mesh in scene.indexed_meshes.iter() {
+				self.render_indexed_mesh(mesh)?;
+			}
+			return Ok(self.render_stats());
+		}
+		
+		//world matrices are composed while walking the hierarchy, the geometry stays untouched
+		let mut result: RenderResult<()> = Ok(());
+		scene.visit_nodes(|node, world| {
+			if result.is_err() { return; }
+			//skinned meshes follow the joints instead of the node
+			let pose: Option<SkinPose> = match node.skin.map(|s| (s, scene.skins.get(s))) {
+				Some((_, Some(skin))) => match skin.pose(scene) {
+					Ok(pose) => Some(pose),
+					Err(err) => { result = Err(err); return; },
+				},
+				Some((s, None)) => { result = Err(RenderError::InvalidModel(format!("node {} references missing skin {}", node.name, s))); return; },
+				None => None,
+			};
+			if let Some(m) = node.mesh {
+				result = match (scene.meshes.get(m), &pose) {
+					(Some(mesh), &Some(ref pose)) => self.render_mesh_skinned(mesh, pose),
+					(Some(mesh), &None) => self.render_mesh_transformed(mesh, world),
+					(None, _) => Err(RenderError::InvalidModel(format!("node {} references missing mesh {}", node.name, m))),
+				};
+			}
+			if let (Some(m), true) = (node.indexed_mesh, result.is_ok()) {
+				result = match (scene.indexed_meshes.get(m), &pose) {
+					(Some(mesh), &Some(ref pose)) => self.render_indexed_mesh_skinned(mesh, pose),
+					(Some(mesh), &None) => self.render_indexed_mesh_transformed(mesh, world),
+					(None, _) => Err(RenderError::InvalidModel(format!("node {} references missing indexed mesh {}", node.name, m))),
+				};
+			}
+		});
+		result?;
+		Ok(self.render_stats())
+	}
+	
+	pub fn clear_frame(&mut self) {
+		for i in 0..self.z_buffer.len() {
+			self.z_buffer[i] = std::f32::MAX;
+			self.frame_buffer[i].set_from_color(&BLACK);
+			self.overdraw_buffer[i] = 0;
+		}
+		self.min_z = std::f32::MAX;
+		self.max_z = 0.0;
+		self.stats.reset();
+	}
+	
+	pub fn resize(&mut self, img_width: i32, img_height: i32) {
+		self._init_buffers(img_width, img_height);
+		self._fit_camera();
+	}
+	
+	pub fn img_width(&self) -> i32 {
+		self.img_width
+	}
+	
+	pub fn img_height(&self) -> i32 {
+		self.img_height
+	}
+	
+	pub fn set_camera(&mut self, camera: Camera) {
+		self.camera = camera;
+		self._fit_camera();
+	}
+	
+	pub fn camera(&self) -> &Camera {
+		&self.camera
+	}
+	
+	pub fn camera_mut(&mut self) -> &mut Camera {
+		&mut self.camera
+	}
+	
+	pub fn read_frame(&self) -> Texture {
+		let mut frame: Texture = Texture::new(self.img_width as u32, self.img_height as u32);
+		for j in 0..self.img_height {
+			for i in 0..self.img_width {
+				self._resolve_color(i, j, &mut frame.buffer[(j * self.img_width + i) as usize]);
+			}
+		}
+		frame
+	}
+	
+	pub fn read_depth(&self) -> Vec<f32> {
+		let mut depth: Vec<f32> = Vec::with_capacity((self.img_width * self.img_height) as usize);
+		for j in 0..self.img_height {
+			for i in 0..self.img_width {
+				depth.push(self._resolve_z(i, j));
+			}
+		}
+		depth
+	}
+}
+
+impl RenderBackend for Renderer {
+	
+	fn render_mesh(&mut self, mesh: &Mesh) -> RenderResult<()> {
+		Renderer::render_mesh(self, mesh)
+	}
+	
+	fn render_mesh_transformed(&mut self, mesh: &Mesh, model: &Mat4) -> RenderResult<()> {
+		Renderer::render_mesh_transformed(self, mesh, model)
+	}
+	
+	fn render_mesh_instances(&mut self, mesh: &Mesh, instances: &[Instance]) -> RenderResult<()> {
+		Renderer::render_mesh_instances(self, mesh, instances)
+	}
+	
+	fn render_indexed_mesh(&mut self, mesh: &IndexedMesh) -> RenderResult<()> {
+		Renderer::render_indexed_mesh(self, mesh)
+	}
+	
+	fn render_indexed_mesh_transformed(&mut self, mesh: &IndexedMesh, model: &Mat4) -> RenderResult<()> {
+		Renderer::render_indexed_mesh_transformed(self, mesh, model)
+	}
+	
+	fn render_mesh_skinned(&mut self, mesh: &Mesh, pose: &SkinPose) -> RenderResult<()> {
+		Renderer::render_mesh_skinned(self, mesh, pose)
+	}
+	
+	fn render_indexed_mesh_skinned(&mut self, mesh: &IndexedMesh, pose: &SkinPose) -> RenderResult<()> {
+		Renderer::render_indexed_mesh_skinned(self, mesh, pose)
+	}
+	
+	fn render_scene(&mut self, scene: &Scene) -> RenderResult<RenderStats> {
+		Renderer::render_scene(self, scene)
+	}
+	
+	fn clear_frame(&mut self) {
+		Renderer::clear_frame(self)
+	}
+	
+	fn resize(&mut self, img_width: i32, img_height: i32) {
+		Renderer::resize(self, img_width, img_height)
+	}
+	
+	fn img_width(&self) -> i32 {
+		Renderer::img_width(self)
+	}
+	
+	fn img_height(&self) -> i32 {
+		Renderer::img_height(self)
+	}
+	
+	fn set_camera(&mut self, camera: Camera) {
+		Renderer::set_camera(self, camera)
+	}
+	
+	fn camera(&self) -> &Camera {
+		Renderer::camera(self)
+	}
+	
+	fn camera_mut(&mut self) -> &mut Camera {
+		Renderer::camera_mut(self)
+	}
+	
+	fn read_frame(&self) -> Texture {
+		Renderer::read_frame(self)
+	}
+	
+	fn read_depth(&self) -> Vec<f32> {
+		Renderer::read_depth(self)
+	}
+}
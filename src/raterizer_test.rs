@@ -1,10 +1,27 @@
 use rasterizer::*;
+use camera::*;
+use error::*;
+use diagnostics::*;
+use stats::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use color::*;
+use math::vec::vec2::*;
 use math::vec::vec3::*;
 use math::mat::mat3::*;
+use math::mat::mat4::*;
 use objects::mesh::*;
+use objects::indexed_mesh::*;
 use objects::scene::*;
 use objects::shape::*;
+use objects::node::*;
+use objects::instance::*;
+use objects::skin::*;
+use objects::morph::*;
+use objects::material::*;
+use objects::animation::*;
+use frame_sink::*;
+use texture::*;
+use test_util::*;
 
 fn create_renderer(	width: i32, height: i32,
 					from: &Vec3, to: &Vec3, 
@@ -14,8 +31,10 @@ fn create_renderer(	width: i32, height: i32,
 					near: f32,far: f32,
 					bgcolor: &Color,
 					samplestep: u32) -> Renderer{
+	let mut camera: Camera = Camera::new();
+	camera.to_ortho(from, to, zoom * left, zoom * right, zoom * top, zoom * bottom, near, far);
 	let mut renderer: Renderer = Renderer::new(width, height, bgcolor, samplestep);
-	renderer.camera.to_ortho(from, to, zoom * left, zoom * right, zoom * top, zoom * bottom, near, far);
+	renderer.set_camera(camera);
 	renderer
 }
 
@@ -40,8 +59,10 @@ fn create_renderer_perspective(	width: i32, height: i32,
 					near: f32,far: f32,
 					bgcolor: &Color,
 					samplestep: u32) -> Renderer{
+	let mut camera: Camera = Camera::new();
+	camera.to_perspective(from, to, zoom * left, zoom * right, zoom * top, zoom * bottom, near, far);
 	let mut renderer: Renderer = Renderer::new(width, height, bgcolor, samplestep);
-	renderer.camera.to_perspective(from, to, zoom * left, zoom * right, zoom * top, zoom * bottom, near, far);
+	renderer.set_camera(camera);
 	renderer
 }
 
@@ -58,6 +79,634 @@ fn create_test_base_renderer_perspective(samplestep: u32) -> Renderer {
 					samplestep)
 }
 
+//flat colored square around x, y on the z = 0 plane
+fn colored_square(x: f32, y: f32, half: f32, color: &Color) -> Mesh {
+	let mut square: Mesh = MeshBuilder::quad(&Vec3::new(x - half, y - half, 0.0), &Vec3::new(x + half, y - half, 0.0),
+											 &Vec3::new(x - half, y + half, 0.0), &Vec3::new(x + half, y + half, 0.0));
+	for shape in square.shapes.iter_mut() {
+		for vertex in shape.vertices.iter_mut() {
+			vertex.color = color.clone();
+		}
+	}
+	square
+}
+
+//pixel showing the point x, y of the z = 0 plane in create_test_base_renderer
+fn pixel_at(frame: &Texture, x: f32, y: f32) -> &Color {
+	let column: u32 = ((x + 1.0) * 256.0) as u32;
+	let row: u32 = ((1.0 - y) * 256.0) as u32;
+	&frame.buffer[(row * frame.width + column) as usize]
+}
+
+fn assert_color(color: &Color, r: f32, g: f32, b: f32) {
+	assert!((color.r - r).abs() < 0.01 && (color.g - g).abs() < 0.01 && (color.b - b).abs() < 0.01,
+		"color is ({}, {}, {}) instead of ({}, {}, {})", color.r, color.g, color.b, r, g, b);
+}
+
+#[test]
+fn backend_resize_and_read_frame() {
+	let mut renderer: Renderer = create_test_base_renderer(2);
+	
+	let frame = renderer.read_frame();
+	assert_eq!(frame.width, 512);
+	assert_eq!(frame.height, 512);
+	
+	renderer.resize(64, 32);
+	assert_eq!(renderer.img_width(), 64);
+	assert_eq!(renderer.img_height(), 32);
+	
+	let frame = renderer.read_frame();
+	assert_eq!(frame.width, 64);
+	assert_eq!(frame.height, 32);
+	assert_eq!(frame.buffer.len(), 64 * 32);
+	
+	let depth = renderer.read_depth();
+	assert_eq!(depth.len(), 64 * 32);
+	assert_eq!(depth.iter().all(|z| *z == ::std::f32::MAX), true);
+}
+
+#[test]
+fn backend_trait_forwards_to_renderer() {
+	//the Renderer methods work without the trait in scope, the trait calls the same code
+	use backend::RenderBackend;
+	let mut renderer: Renderer = create_test_base_renderer(1);
+	{
+		let backend: &mut dyn RenderBackend = &mut renderer;
+		backend.render_mesh(&colored_square(0.0, 0.0, 0.1, &RED)).unwrap();
+		assert_eq!(backend.img_width(), 512);
+	}
+	assert_color(pixel_at(&renderer.read_frame(), 0.0, 0.0), 255.0, 0.0, 0.0);
+}
+
+#[test]
+fn render_invalid_shape() {
+	let mut renderer: Renderer = create_test_base_renderer(1);
+	let shape: Shape = Shape::new(4);
+	
+	match renderer.render_shape(&shape) {
+		Err(RenderError::InvalidShape(0)) => {},
+		_ => panic!("empty shape must be rejected"),
+	}
+}
+
+#[test]
+fn render_invalid_texture_id() {
+	let p1: Vec3 = Vec3{x: -0.75, y: -0.75, z: 0.75 };
+	let p2: Vec3 = Vec3{x: 0.75,  y: -0.55, z: 0.25 };
+	let p3: Vec3 = Vec3{x: 0.05,  y: 0.75,  z: -0.75 };
+	let mut triangle: Mesh = MeshBuilder::triangle(&p1, &p2, &p3);
+	triangle.shapes[0].tex_id = 3;
+	
+	let mut renderer: Renderer = create_test_base_renderer(1);
+	
+	match renderer.render_mesh(&triangle) {
+		Err(RenderError::InvalidTextureId(3)) => {},
+		_ => panic!("missing texture must be rejected"),
+	}
+	
+	renderer.texture = vec![Texture::new(0, 0); 4];
+	match renderer.render_mesh(&triangle) {
+		Err(RenderError::InvalidTextureId(3)) => {},
+		_ => panic!("empty texture must be rejected"),
+	}
+}
+
+#[test]
+fn render_indexed_mesh_like_shapes() {
+	let mut sphere: Mesh = MeshBuilder::sphere(0.5, 20, 20);
+	sphere.compute_bbox();
+	sphere.color_by_bbox();
+	
+	let mut renderer: Renderer = create_test_base_renderer(2);
+	renderer.render_mesh(&sphere).unwrap();
+	let expected = renderer.read_frame();
+	
+	let mut scene: Scene = Scene::new(0);
+	scene.indexed_meshes = IndexedMesh::from_mesh(&sphere).unwrap();
+	renderer.clear_frame();
+	let stats: RenderStats = renderer.render_scene(&scene).unwrap();
+	assert_eq!(stats.triangles as usize, sphere.shapes.len());
+	let frame = renderer.read_frame();
+	assert_same_frame(&frame, &expected);
+	
+	scene.indexed_meshes[0].indices.push(100000);
+	match renderer.render_scene(&scene) {
+		Err(RenderError::InvalidIndex(100000)) => {},
+		_ => panic!("index beyond the vertex buffer must be rejected"),
+	}
+}
+
+#[test]
+fn render_indexed_quad_with_shared_vertices() {
+	let corners: [(f32, f32); 4] = [(-0.3, -0.3), (0.3, -0.3), (-0.3, 0.3), (0.3, 0.3)];
+	let vertices: Vec<Vertex> = corners.iter().map(|c| Vertex::new_init(Vec3::new(c.0, c.1, 0.0), LIME.clone(), Vec2::new_empty())).collect();
+	let quad: IndexedMesh = IndexedMesh::new_init(Topology::Triangles, &vertices, &vec![0, 1, 2, 2, 1, 3]);
+	
+	let mut renderer: Renderer = create_test_base_renderer(1);
+	renderer.render_indexed_mesh(&quad).unwrap();
+	assert_eq!(renderer.render_stats().triangles, 2);
+	let frame = renderer.read_frame();
+	assert_color(pixel_at(&frame, 0.0, 0.0), 0.0, 255.0, 0.0);
+	assert_color(pixel_at(&frame, -0.25, 0.25), 0.0, 255.0, 0.0);
+	assert_color(pixel_at(&frame, 0.25, -0.25), 0.0, 255.0, 0.0);
+	assert_color(pixel_at(&frame, 0.5, 0.0), 0.0, 0.0, 0.0);
+}
+
+#[test]
+fn render_indexed_mesh_projects_vertices_once() {
+	let sphere: Mesh = MeshBuilder::sphere(0.5, 20, 20);
+	let mut indexed: IndexedMesh = IndexedMesh::from_mesh(&sphere).unwrap().remove(0);
+	indexed.optimize_vertex_cache().unwrap();
+	
+	let mut renderer: Renderer = create_test_base_renderer(1);
+	renderer.render_indexed_mesh(&indexed).unwrap();
+	let stats: RenderStats = renderer.render_stats();
+	assert_eq!(stats.vertices_transformed, indexed.vertices.len() as u64);
+	assert_eq!(stats.vertex_cache_hits, (indexed.indices.len() - indexed.vertices.len()) as u64);
+	
+	renderer.clear_frame();
+	renderer.render_mesh(&sphere).unwrap();
+	assert_eq!(renderer.render_stats().vertices_transformed, (sphere.shapes.len() * 3) as u64);
+	assert_eq!(renderer.render_stats().vertex_cache_hits, 0);
+}
+
+#[test]
+fn render_scene_nodes_like_baked_meshes() {
+	let mut cube: Mesh = MeshBuilder::cube(&Vec3::new_empty(), 1.0);
+	cube.compute_bbox();
+	cube.color_by_bbox();
+	let mut rotz: Mat3 = Mat3::new_empty();
+	rotz.to_rotz(30.0);
+	
+	let mut baked: Scene = Scene::new(0);
+	let mut left: Mesh = cube.clone();
+	left.scale(0.5, 0.5, 0.5);
+	left.translate(-0.5, 0.0, 0.0);
+	let mut right: Mesh = cube.clone();
+	right.transform(&rotz);
+	right.scale(0.5, 0.5, 0.5);
+	right.translate(0.5, 0.0, 0.0);
+	baked.meshes.push(left);
+	baked.meshes.push(right);
+	
+	let mut renderer: Renderer = create_test_base_renderer(1);
+	renderer.render_scene(&baked).unwrap();
+	let expected = renderer.read_frame();
+	
+	//both nodes share the untransformed cube below a scaling parent
+	let mut scene: Scene = Scene::new(0);
+	scene.meshes.push(cube);
+	let mut parent: Node = Node::new("parent");
+	parent.scale(0.5, 0.5, 0.5);
+	let mut left: Node = Node::new_mesh("left", 0);
+	left.translate(-1.0, 0.0, 0.0);
+	let mut right: Node = Node::new_mesh("right", 0);
+	right.rotate(&rotz);
+	right.translate(1.0, 0.0, 0.0);
+	parent.children.push(left);
+	parent.children.push(right);
+	scene.nodes.push(parent);
+	
+	renderer.clear_frame();
+	renderer.render_scene(&scene).unwrap();
+	let frame = renderer.read_frame();
+	assert_same_frame(&frame, &expected);
+	
+	scene.find_node_mut("right").unwrap().mesh = Some(1);
+	match renderer.render_scene(&scene) {
+		Err(RenderError::InvalidModel(_)) => {},
+		_ => panic!("nodes referencing missing meshes must be rejected"),
+	}
+}
+
+#[test]
+fn render_scene_child_node_in_parent_space() {
+	let mut scene: Scene = Scene::new(1);
+	scene.meshes.push(colored_square(0.0, 0.0, 0.05, &RED));
+	let mut parent: Node = Node::new("parent");
+	parent.scale(2.0, 2.0, 2.0);
+	parent.translate(0.2, 0.0, 0.0);
+	let mut child: Node = Node::new_mesh("child", 0);
+	child.translate(0.1, 0.2, 0.0);
+	parent.children.push(child);
+	scene.nodes.push(parent);
+	
+	//the child is scaled and moved by its parent: 2 * (p + (0.1, 0.2)) + (0.2, 0)
+	let mut world: Vec<Mat4> = Vec::new();
+	scene.visit_nodes(|node, transform| if node.name == "child" { world.push(transform.clone()); });
+	assert_eq!(world.len(), 1);
+	assert_eq!((world[0]._11, world[0]._22), (2.0, 2.0));
+	assert!((world[0]._14 - 0.4).abs() < 1e-6 && (world[0]._24 - 0.4).abs() < 1e-6, "child is at ({}, {})", world[0]._14, world[0]._24);
+	
+	let mut renderer: Renderer = create_test_base_renderer(1);
+	renderer.render_scene(&scene).unwrap();
+	let frame = renderer.read_frame();
+	assert_color(pixel_at(&frame, 0.4, 0.4), 255.0, 0.0, 0.0);
+	assert_color(pixel_at(&frame, 0.33, 0.47), 255.0, 0.0, 0.0);
+	assert_color(pixel_at(&frame, 0.4, 0.25), 0.0, 0.0, 0.0);
+	assert_color(pixel_at(&frame, 0.0, 0.0), 0.0, 0.0, 0.0);
+}
+
+#[test]
+fn render_skinned_mesh_like_transformed() {
+	let mut cube: Mesh = MeshBuilder::cube(&Vec3::new_empty(), 0.8);
+	cube.compute_bbox();
+	cube.color_by_bbox();
+	let mut rotz: Mat3 = Mat3::new_empty();
+	rotz.to_rotz(30.0);
+	let mut bone: Node = Node::new("bone");
+	bone.rotate(&rotz);
+	bone.translate(0.2, -0.1, 0.0);
+	
+	let mut renderer: Renderer = create_test_base_renderer(1);
+	renderer.render_mesh_transformed(&cube, &bone.transform).unwrap();
+	let expected = renderer.read_frame();
+	
+	//every vertex follows the bone, the scale of the drawing node is ignored
+	let mut skinned: Mesh = cube.clone();
+	for shape in skinned.shapes.iter_mut() {
+		for vertex in shape.vertices.iter_mut() {
+			vertex.weights = [1.0, 0.0, 0.0, 0.0];
+		}
+	}
+	let mut scene: Scene = Scene::new(1);
+	scene.indexed_meshes = IndexedMesh::from_mesh(&skinned).unwrap();
+	scene.meshes.push(skinned);
+	let mut skin: Skin = Skin::new("skeleton");
+	skin.add_joint("bone", identity());
+	scene.skins.push(skin);
+	let mut body: Node = Node::new_mesh("body", 0);
+	body.scale(3.0, 3.0, 3.0);
+	body.skin = Some(0);
+	scene.nodes.push(body);
+	scene.nodes.push(bone);
+	
+	renderer.clear_frame();
+	renderer.render_scene(&scene).unwrap();
+	let frame = renderer.read_frame();
+	assert_same_frame(&frame, &expected);
+	
+	scene.nodes[0].mesh = None;
+	scene.nodes[0].indexed_mesh = Some(0);
+	renderer.clear_frame();
+	renderer.render_scene(&scene).unwrap();
+	let frame = renderer.read_frame();
+	assert_same_frame(&frame, &expected);
+	
+	scene.nodes[0].skin = Some(1);
+	match renderer.render_scene(&scene) {
+		Err(RenderError::InvalidModel(_)) => {},
+		_ => panic!("nodes referencing missing skins must be rejected"),
+	}
+}
+
+#[test]
+fn render_skinned_square_between_joints() {
+	//every vertex is half bound to the resting root and half to the moved bone
+	let mut square: Mesh = colored_square(0.0, 0.0, 0.1, &LIME);
+	for shape in square.shapes.iter_mut() {
+		for vertex in shape.vertices.iter_mut() {
+			vertex.joints = [0, 1, 0, 0];
+			vertex.weights = [0.5, 0.5, 0.0, 0.0];
+		}
+	}
+	let mut scene: Scene = Scene::new(1);
+	scene.meshes.push(square);
+	let mut skin: Skin = Skin::new("skeleton");
+	skin.add_joint("root", identity());
+	skin.add_joint("bone", identity());
+	scene.skins.push(skin);
+	let mut body: Node = Node::new_mesh("body", 0);
+	body.skin = Some(0);
+	let mut bone: Node = Node::new("bone");
+	bone.translate(0.5, 0.5, 0.0);
+	scene.nodes.push(body);
+	scene.nodes.push(Node::new("root"));
+	scene.nodes.push(bone);
+	
+	let pose: SkinPose = scene.skins[0].pose(&scene).unwrap();
+	let corner: Vec3 = pose.skin_point(&scene.meshes[0].shapes[0].vertices[0]).unwrap();
+	assert_close(&corner, &Vec3::new(0.15, 0.15, 0.0), 1e-5);
+	
+	let mut renderer: Renderer = create_test_base_renderer(1);
+	renderer.render_scene(&scene).unwrap();
+	let frame = renderer.read_frame();
+	assert_color(pixel_at(&frame, 0.25, 0.25), 0.0, 255.0, 0.0);
+	assert_color(pixel_at(&frame, 0.0, 0.0), 0.0, 0.0, 0.0);
+	assert_color(pixel_at(&frame, 0.5, 0.5), 0.0, 0.0, 0.0);
+}
+
+#[test]
+fn render_mesh_instances_like_copies() {
+	let mut cube: Mesh = MeshBuilder::cube(&Vec3::new_empty(), 0.4);
+	cube.compute_bbox();
+	cube.color_by_bbox();
+	
+	let mut renderer: Renderer = create_test_base_renderer(2);
+	let positions: [f32; 3] = [-0.6, 0.0, 0.6];
+	for x in positions.iter() {
+		let mut copy: Mesh = cube.clone();
+		copy.translate(*x, 0.0, 0.0);
+		renderer.render_mesh(&copy).unwrap();
+	}
+	let expected = renderer.read_frame();
+	
+	let instances: Vec<Instance> = positions.iter().map(|x| Instance::new_translation(*x, 0.0, 0.0)).collect();
+	renderer.clear_frame();
+	renderer.render_mesh_instances(&cube, &instances).unwrap();
+	assert_eq!(renderer.render_stats().instances, 3);
+	assert_eq!(renderer.render_stats().triangles as usize, cube.shapes.len() * 3);
+	let frame = renderer.read_frame();
+	assert_same_frame(&frame, &expected);
+	
+	//a red tint keeps only the red channel, later draws are not tinted
+	let tinted: Vec<Instance> = vec![Instance::new_tint(translation(0.0, 0.0, 0.0), &RED)];
+	renderer.clear_frame();
+	renderer.render_mesh_instances(&cube, &tinted).unwrap();
+	let frame = renderer.read_frame();
+	assert_eq!(frame.buffer.iter().all(|c| c.g == 0.0 && c.b == 0.0), true);
+	assert_eq!(frame.buffer.iter().any(|c| c.r > 0.0), true);
+	renderer.clear_frame();
+	renderer.render_mesh(&cube).unwrap();
+	assert_eq!(renderer.read_frame().buffer.iter().any(|c| c.g > 0.0), true);
+}
+
+#[test]
+fn render_mesh_instances_with_tint() {
+	let square: Mesh = colored_square(0.0, 0.0, 0.1, &WHITE);
+	let instances: Vec<Instance> = vec![Instance::new_translation(-0.5, 0.0, 0.0),
+										Instance::new_tint(translation(0.5, 0.0, 0.0), &Color::new(255.0, 102.0, 0.0))];
+	
+	let mut renderer: Renderer = create_test_base_renderer(1);
+	renderer.render_mesh_instances(&square, &instances).unwrap();
+	let frame = renderer.read_frame();
+	assert_color(pixel_at(&frame, -0.5, 0.0), 255.0, 255.0, 255.0);
+	assert_color(pixel_at(&frame, 0.5, 0.0), 255.0, 102.0, 0.0);
+	assert_color(pixel_at(&frame, 0.0, 0.0), 0.0, 0.0, 0.0);
+}
+
+#[test]
+fn render_mesh_model_like_baked_vertices() {
+	let mut rotz: Mat3 = Mat3::new_empty();
+	rotz.to_rotz(45.0);
+	let mut cube: Mesh = MeshBuilder::cube(&Vec3::new_empty(), 0.6);
+	cube.compute_bbox();
+	cube.color_by_bbox();
+	let original: Mesh = cube.clone();
+	
+	let mut baked: Mesh = cube.clone();
+	baked.transform(&rotz);
+	baked.translate(0.3, -0.2, 0.0);
+	let mut renderer: Renderer = create_test_base_renderer(2);
+	renderer.render_mesh(&baked).unwrap();
+	let expected = renderer.read_frame();
+	
+	cube.rotate_model(&rotz);
+	cube.translate_model(0.3, -0.2, 0.0);
+	renderer.clear_frame();
+	renderer.render_mesh(&cube).unwrap();
+	let frame = renderer.read_frame();
+	assert_same_frame(&frame, &expected);
+	
+	//the vertices are untouched until the model is baked
+	assert_eq!(cube.shapes.iter().zip(original.shapes.iter()).all(|(a, b)| a.vertices[0].vec == b.vertices[0].vec), true);
+	cube.bake_model();
+	assert_eq!(cube.model._11 == 1.0 && cube.model._14 == 0.0 && cube.model._24 == 0.0, true);
+	for (a, b) in cube.shapes.iter().zip(baked.shapes.iter()) {
+		assert_close(&a.vertices[2].vec, &b.vertices[2].vec, 1e-5);
+	}
+}
+
+#[test]
+fn render_mesh_model_moves_the_drawing() {
+	let mut square: Mesh = colored_square(0.0, 0.0, 0.1, &RED);
+	square.scale_model(2.0, 1.0, 1.0);
+	square.translate_model(0.5, -0.5, 0.0);
+	
+	//x from 0.3 to 0.7, y from -0.6 to -0.4
+	let mut renderer: Renderer = create_test_base_renderer(1);
+	renderer.render_mesh(&square).unwrap();
+	let frame = renderer.read_frame();
+	assert_color(pixel_at(&frame, 0.5, -0.5), 255.0, 0.0, 0.0);
+	assert_color(pixel_at(&frame, 0.65, -0.45), 255.0, 0.0, 0.0);
+	assert_color(pixel_at(&frame, 0.5, -0.65), 0.0, 0.0, 0.0);
+	assert_color(pixel_at(&frame, 0.0, 0.0), 0.0, 0.0, 0.0);
+	assert_eq!((square.shapes[0].vertices[0].vec.x, square.shapes[0].vertices[0].vec.y), (-0.1, -0.1));
+}
+
+#[test]
+fn render_morphed_mesh_like_blended_copy() {
+	let mut cube: Mesh = MeshBuilder::cube(&Vec3::new_empty(), 0.8);
+	cube.compute_bbox();
+	cube.color_by_bbox();
+	let cnt: usize = cube.vertex_count();
+	let mut stretch: MorphTarget = MorphTarget::new("stretch");
+	let mut tint: MorphTarget = MorphTarget::new("tint");
+	for shape in cube.shapes.iter() {
+		for vertex in shape.vertices.iter() {
+			stretch.positions.push(Vec3::new(0.5 * vertex.vec.x, 0.0, 0.0));
+		}
+	}
+	tint.colors = vec![Color::new(-50.0, 20.0, 0.0); cnt];
+	cube.add_morph_target(stretch).unwrap();
+	cube.add_morph_target(tint).unwrap();
+	cube.morph_weights = vec![0.6, 0.5];
+	
+	let mut blended: Mesh = cube.clone();
+	blended.morph_targets.clear();
+	blended.morph_weights.clear();
+	let mut first: usize = 0;
+	for shape in blended.shapes.iter_mut() {
+		apply_morph(&mut shape.vertices, first, &cube.morph_targets, &cube.morph_weights);
+		first += shape.vertices.len();
+	}
+	let mut renderer: Renderer = create_test_base_renderer(1);
+	renderer.render_mesh(&blended).unwrap();
+	let expected = renderer.read_frame();
+	
+	renderer.clear_frame();
+	renderer.render_mesh(&cube).unwrap();
+	let frame = renderer.read_frame();
+	assert_same_frame(&frame, &expected);
+	
+	let indexed: Vec<IndexedMesh> = IndexedMesh::from_mesh(&cube).unwrap();
+	renderer.clear_frame();
+	renderer.render_indexed_mesh(&indexed[0]).unwrap();
+	let frame = renderer.read_frame();
+	assert_same_frame(&frame, &expected);
+	
+	//drawing never changes the mesh
+	let half: f32 = cube.shapes.iter().flat_map(|s| s.vertices.iter()).map(|v| v.vec.x.abs()).fold(0.0, f32::max);
+	assert_eq!(half, 0.4);
+}
+
+#[test]
+fn render_morphed_square_moved_and_recolored() {
+	let mut square: Mesh = colored_square(0.0, 0.0, 0.1, &WHITE);
+	let cnt: usize = square.vertex_count();
+	let mut target: MorphTarget = MorphTarget::new("move");
+	target.positions = vec![Vec3::new(1.0, 0.0, 0.0); cnt];
+	target.colors = vec![Color::new(0.0, -255.0, -255.0); cnt];
+	square.add_morph_target(target).unwrap();
+	square.morph_weights[0] = 0.5;
+	
+	//half the delta, x from 0.4 to 0.6 and half the color change
+	let mut renderer: Renderer = create_test_base_renderer(1);
+	renderer.render_mesh(&square).unwrap();
+	let frame = renderer.read_frame();
+	assert_color(pixel_at(&frame, 0.5, 0.0), 255.0, 127.5, 127.5);
+	assert_color(pixel_at(&frame, 0.0, 0.0), 0.0, 0.0, 0.0);
+	
+	renderer.clear_frame();
+	renderer.render_indexed_mesh(&IndexedMesh::from_mesh(&square).unwrap()[0]).unwrap();
+	let frame = renderer.read_frame();
+	assert_color(pixel_at(&frame, 0.5, 0.0), 255.0, 127.5, 127.5);
+	assert_color(pixel_at(&frame, 0.0, 0.0), 0.0, 0.0, 0.0);
+}
+
+#[test]
+fn render_scene_with_animated_material_color() {
+	let mut quad: Mesh = MeshBuilder::quad(&Vec3::new(-0.5, -0.5, 0.0), &Vec3::new(0.5, -0.5, 0.0), &Vec3::new(-0.5, 0.5, 0.0), &Vec3::new(0.5, 0.5, 0.0));
+	for shape in quad.shapes.iter_mut() {
+		shape.material = Some(0);
+		for vertex in shape.vertices.iter_mut() {
+			vertex.color = RED.clone();
+		}
+	}
+	let mut scene: Scene = Scene::new(1);
+	let mut paint: Material = Material::new("paint");
+	paint.diffuse = RED.clone();
+	scene.materials.push(paint);
+	scene.meshes.push(quad);
+	
+	let mut clip: Clip = Clip::new("fade");
+	clip.add(Target::Material(0), Channel::Color(vec![Keyframe::new(0.0, RED.clone()), Keyframe::new(1.0, BLUE.clone())]), Interpolation::Linear);
+	clip.apply(&mut scene, 1.0).unwrap();
+	
+	let mut renderer: Renderer = create_test_base_renderer(1);
+	renderer.render_scene(&scene).unwrap();
+	let frame = renderer.read_frame();
+	let center: &Color = &frame.buffer[(256 * frame.width + 256) as usize];
+	assert!(*center == BLUE, "center pixel is ({}, {}, {})", center.r, center.g, center.b);
+}
+
+#[test]
+fn write_frame_pfm_roundtrip() {
+	let mut cube: Mesh = MeshBuilder::cube(&Vec3::new_empty(), 1.0);
+	for shape in cube.shapes.iter_mut() {
+		shape.set_color(&WHITE);
+	}
+	let mut renderer: Renderer = create_test_base_renderer(1);
+	renderer.resize(16, 8);
+	renderer.render_mesh(&cube).unwrap();
+	
+	//frame floats use the same scale as write_pfm, so reading them back gives the frame
+	let filename = ::std::env::temp_dir().join("renderer_frame.pfm");
+	renderer.write_fb_pfm(&filename).unwrap();
+	let loaded: Texture = Texture::load(&filename).unwrap();
+	let expected: Texture = renderer.read_frame();
+	assert_eq!(loaded.buffer.iter().zip(expected.buffer.iter()).all(|(a, b)| (a.r - b.r).abs() < 1e-3 && (a.g - b.g).abs() < 1e-3), true);
+	assert_eq!(expected.buffer.iter().any(|c| *c == WHITE), true);
+}
+
+#[test]
+fn write_frames_to_image_sequence() {
+	let sink: ImageSequenceSink = ImageSequenceSink::new("out/shot_##_v###.png");
+	assert_eq!(sink.filename(7), "out/shot_##_v007.png");
+	assert_eq!(ImageSequenceSink::new("frame####").filename(12345), "frame12345");
+	assert_eq!(ImageSequenceSink::new("frames/take.2/frame.ppm").filename(3), "frames/take.2/frame3.ppm");
+	
+	let dir = ::std::env::temp_dir().join("renderer_image_sequence");
+	::std::fs::create_dir_all(&dir).unwrap();
+	let mut sink: ImageSequenceSink = ImageSequenceSink::new(dir.join("frame_####.png").to_str().unwrap());
+	let mut renderer: Renderer = create_test_base_renderer(1);
+	renderer.resize(32, 32);
+	let mut cube: Mesh = MeshBuilder::cube(&Vec3::new_empty(), 1.0);
+	cube.compute_bbox();
+	cube.color_by_bbox();
+	for frame in 0..3 {
+		renderer.clear_frame();
+		cube.translate_model(0.1 * frame as f32, 0.0, 0.0);
+		renderer.render_mesh(&cube).unwrap();
+		renderer.write_frame_to(&mut sink).unwrap();
+	}
+	sink.finish().unwrap();
+	assert_eq!(sink.next, 3);
+	let last: Texture = Texture::load(dir.join("frame_0002.png")).unwrap();
+	let expected: Texture = renderer.read_frame();
+	assert_eq!(last.buffer.iter().zip(expected.buffer.iter()).all(|(a, b)| a.r == (b.r as u8) as f32), true);
+	
+	let mut sink: ImageSequenceSink = ImageSequenceSink::new(dir.join("frame_#.xyz").to_str().unwrap());
+	match renderer.write_frame_to(&mut sink) {
+		Err(RenderError::InvalidImage(_)) => {},
+		_ => panic!("unknown image formats must be rejected"),
+	}
+}
+
+static TRACE_MESSAGES: AtomicUsize = AtomicUsize::new(0);
+
+fn count_trace_messages(level: DiagnosticLevel, _message: &str) {
+	assert_eq!(level, DiagnosticLevel::Trace);
+	TRACE_MESSAGES.fetch_add(1, Ordering::SeqCst);
+}
+
+#[test]
+fn render_diagnostics_and_frame_report() {
+	let p1: Vec3 = Vec3{x: -0.75, y: -0.75, z: 0.75 };
+	let p2: Vec3 = Vec3{x: 0.75,  y: -0.55, z: 0.25 };
+	let p3: Vec3 = Vec3{x: 0.05,  y: 0.75,  z: -0.75 };
+	let triangle: Mesh = MeshBuilder::triangle(&p1, &p2, &p3);
+	
+	let mut renderer: Renderer = create_test_base_renderer(1);
+	renderer.render_mesh(&triangle).unwrap();
+	assert_eq!(TRACE_MESSAGES.load(Ordering::SeqCst), 0);
+	
+	renderer.set_diagnostics(Box::new(CallbackSink::new(DiagnosticLevel::Trace, count_trace_messages)));
+	renderer.render_mesh(&triangle).unwrap();
+	assert_eq!(TRACE_MESSAGES.load(Ordering::SeqCst), 3);
+	
+	let report: FrameReport = renderer.frame_report();
+	assert_eq!(report.triangles_submitted, 2);
+	assert_eq!(report.triangles_clipped + report.triangles_culled, 0);
+	assert_eq!(report.samples_shaded > 0, true);
+	
+	renderer.clear_frame();
+	assert_eq!(renderer.frame_report().triangles_submitted, 0);
+	
+	//points behind the camera are not clipped triangles
+	let mut renderer: Renderer = create_test_base_renderer_perspective(1);
+	renderer.render_shape(&Shape::new_point(&Vec3::new(0.0, 0.0, 5.0))).unwrap();
+	assert_eq!(renderer.render_stats().rejected_w, 1);
+	assert_eq!(renderer.frame_report().triangles_clipped, 0);
+}
+
+#[test]
+fn render_scene_stats_and_overdraw() {
+	let mut scene: Scene = SceneBuilder::triangle();
+	scene.meshes.push(MeshBuilder::cube(&Vec3::new_empty(), 1.0));
+	
+	let mut renderer: Renderer = create_test_base_renderer(2);
+	let stats: RenderStats = renderer.render_scene(&scene).unwrap();
+	
+	assert_eq!(stats.triangles, 13);
+	assert_eq!(stats.shapes(), 13);
+	assert_eq!(stats.rejected_culled + stats.rejected_zero_area > 0, true);
+	assert_eq!(stats.depth_passes > 0, true);
+	assert_eq!(stats.fragments_tested >= stats.depth_passes, true);
+	assert_eq!(stats.overdraw.len(), OVERDRAW_BUCKETS);
+	assert_eq!(stats.overdraw.iter().sum::<u64>(), renderer.z_buffer.len() as u64);
+	assert_eq!(stats.overdraw[0] < renderer.z_buffer.len() as u64, true);
+	
+	let heatmap = renderer.overdraw_heatmap();
+	assert_eq!(heatmap.width, 512);
+	assert_eq!(heatmap.buffer.iter().any(|c| *c != BLACK), true);
+	
+	renderer.clear_frame();
+	let stats: RenderStats = renderer.render_stats();
+	assert_eq!(stats.shapes(), 0);
+	assert_eq!(stats.overdraw[0], renderer.z_buffer.len() as u64);
+}
+
 
 #[test]
 #[cfg(any(feature = "all", all(feature = "point", feature = "orthogonal", feature = "nomsaa")))]
@@ -66,11 +715,11 @@ fn point_ortho() {
 	let points: Mesh = MeshBuilder::point(&p);
 	
 	let mut renderer: Renderer = create_test_base_renderer(1);
-	renderer.render_mesh(&points);
+	renderer.render_mesh(&points).unwrap();
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/point_ortho.ppm");
-		renderer.write_zb_ppm("target/point_ortho_z.ppm");
+		renderer.write_fb_ppm("target/point_ortho.ppm").unwrap();
+		renderer.write_zb_ppm("target/point_ortho_z.ppm").unwrap();
 	}
 }
 
@@ -81,11 +730,11 @@ fn point_perspective() {
 	let points: Mesh = MeshBuilder::point(&p);
 	
 	let mut renderer: Renderer = create_test_base_renderer_perspective(1);
-	renderer.render_mesh(&points);
+	renderer.render_mesh(&points).unwrap();
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/point_perspective.ppm");
-		renderer.write_zb_ppm("target/point_perspective_z.ppm");
+		renderer.write_fb_ppm("target/point_perspective.ppm").unwrap();
+		renderer.write_zb_ppm("target/point_perspective_z.ppm").unwrap();
 	}
 }
 
@@ -96,11 +745,11 @@ fn point_ortho_msaa2x2() {
 	let points: Mesh = MeshBuilder::point(&p);
 	
 	let mut renderer: Renderer = create_test_base_renderer(2);
-	renderer.render_mesh(&points);
+	renderer.render_mesh(&points).unwrap();
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/point_ortho_msaa2x2.ppm");
-		renderer.write_zb_ppm("target/point_ortho_msaa2x2_z.ppm");
+		renderer.write_fb_ppm("target/point_ortho_msaa2x2.ppm").unwrap();
+		renderer.write_zb_ppm("target/point_ortho_msaa2x2_z.ppm").unwrap();
 	}
 }
 
@@ -111,11 +760,11 @@ fn point_perspective_msaa2x2() {
 	let points: Mesh = MeshBuilder::point(&p);
 	
 	let mut renderer: Renderer = create_test_base_renderer_perspective(2);
-	renderer.render_mesh(&points);
+	renderer.render_mesh(&points).unwrap();
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/point_perspective_msaa2x2.ppm");
-		renderer.write_zb_ppm("target/point_perspective_msaa2x2_z.ppm");
+		renderer.write_fb_ppm("target/point_perspective_msaa2x2.ppm").unwrap();
+		renderer.write_zb_ppm("target/point_perspective_msaa2x2_z.ppm").unwrap();
 	}
 }
 
@@ -126,11 +775,11 @@ fn point_ortho_msaa4x4() {
 	let points: Mesh = MeshBuilder::point(&p);
 	
 	let mut renderer: Renderer = create_test_base_renderer(4);
-	renderer.render_mesh(&points);
+	renderer.render_mesh(&points).unwrap();
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/point_ortho_msaa4x4.ppm");
-		renderer.write_zb_ppm("target/point_ortho_msaa4x4_z.ppm");
+		renderer.write_fb_ppm("target/point_ortho_msaa4x4.ppm").unwrap();
+		renderer.write_zb_ppm("target/point_ortho_msaa4x4_z.ppm").unwrap();
 	}
 }
 
@@ -141,11 +790,11 @@ fn point_perspective_msaa4x4() {
 	let points: Mesh = MeshBuilder::point(&p);
 	
 	let mut renderer: Renderer = create_test_base_renderer_perspective(4);
-	renderer.render_mesh(&points);
+	renderer.render_mesh(&points).unwrap();
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/point_perspective_msaa4x4.ppm");
-		renderer.write_zb_ppm("target/point_perspective_msaa4x4_z.ppm");
+		renderer.write_fb_ppm("target/point_perspective_msaa4x4.ppm").unwrap();
+		renderer.write_zb_ppm("target/point_perspective_msaa4x4_z.ppm").unwrap();
 	}
 }
 
@@ -164,13 +813,13 @@ fn test_render_lines_ortho_nomsaa() {
 		line.shapes[0].vertices[1].vec.x += step;
 		line.shapes[0].vertices[1].vec.y -= step;
 		line.shapes[0].vertices[1].vec.z += step;
-		renderer.render_mesh(&line);
+		renderer.render_mesh(&line).unwrap();
 		step += 0.05;
 	}
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/line_orthogonal_nomsaa.ppm");
-		renderer.write_zb_ppm("target/line_orthogonal_nomsaa_z.ppm");
+		renderer.write_fb_ppm("target/line_orthogonal_nomsaa.ppm").unwrap();
+		renderer.write_zb_ppm("target/line_orthogonal_nomsaa_z.ppm").unwrap();
 	}
 	
 }
@@ -190,13 +839,13 @@ fn test_render_lines_perspective_nomsaa() {
 		line.shapes[0].vertices[1].vec.x += step;
 		line.shapes[0].vertices[1].vec.y -= step;
 		line.shapes[0].vertices[1].vec.z += step;
-		renderer.render_mesh(&line);
+		renderer.render_mesh(&line).unwrap();
 		step += 0.05;
 	}
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/line_perspective_nomsaa.ppm");
-		renderer.write_zb_ppm("target/line_perspective_nomsaa_z.ppm");
+		renderer.write_fb_ppm("target/line_perspective_nomsaa.ppm").unwrap();
+		renderer.write_zb_ppm("target/line_perspective_nomsaa_z.ppm").unwrap();
 	}
 	
 }
@@ -216,13 +865,13 @@ fn test_render_lines_ortho_msaa2x2() {
 		line.shapes[0].vertices[1].vec.x += step;
 		line.shapes[0].vertices[1].vec.y -= step;
 		line.shapes[0].vertices[1].vec.z += step;
-		renderer.render_mesh(&line);
+		renderer.render_mesh(&line).unwrap();
 		step += 0.05;
 	}
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/line_orthogonal_nomsaa.ppm");
-		renderer.write_zb_ppm("target/line_orthogonal_nomsaa_z.ppm");
+		renderer.write_fb_ppm("target/line_orthogonal_nomsaa.ppm").unwrap();
+		renderer.write_zb_ppm("target/line_orthogonal_nomsaa_z.ppm").unwrap();
 	}
 	
 }
@@ -242,13 +891,13 @@ fn test_render_lines_perspective_msaa2x2() {
 		line.shapes[0].vertices[1].vec.x += step;
 		line.shapes[0].vertices[1].vec.y -= step;
 		line.shapes[0].vertices[1].vec.z += step;
-		renderer.render_mesh(&line);
+		renderer.render_mesh(&line).unwrap();
 		step += 0.05;
 	}
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/line_perspective_msaa2x2.ppm");
-		renderer.write_zb_ppm("target/line_perspective_msaa2x2_z.ppm");
+		renderer.write_fb_ppm("target/line_perspective_msaa2x2.ppm").unwrap();
+		renderer.write_zb_ppm("target/line_perspective_msaa2x2_z.ppm").unwrap();
 	}
 	
 }
@@ -268,13 +917,13 @@ fn test_render_lines_ortho_msaa4x4() {
 		line.shapes[0].vertices[1].vec.x += step;
 		line.shapes[0].vertices[1].vec.y -= step;
 		line.shapes[0].vertices[1].vec.z += step;
-		renderer.render_mesh(&line);
+		renderer.render_mesh(&line).unwrap();
 		step += 0.05;
 	}
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/line_orthogonal_msaa4x4.ppm");
-		renderer.write_zb_ppm("target/line_orthogonal_msaa4x4_z.ppm");
+		renderer.write_fb_ppm("target/line_orthogonal_msaa4x4.ppm").unwrap();
+		renderer.write_zb_ppm("target/line_orthogonal_msaa4x4_z.ppm").unwrap();
 	}
 	
 }
@@ -294,13 +943,13 @@ fn test_render_lines_perspective_msaa4x4() {
 		line.shapes[0].vertices[1].vec.x += step;
 		line.shapes[0].vertices[1].vec.y -= step;
 		line.shapes[0].vertices[1].vec.z += step;
-		renderer.render_mesh(&line);
+		renderer.render_mesh(&line).unwrap();
 		step += 0.05;
 	}
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/line_perspective_msaa4x4.ppm");
-		renderer.write_zb_ppm("target/line_perspective_msaa4x4_z.ppm");
+		renderer.write_fb_ppm("target/line_perspective_msaa4x4.ppm").unwrap();
+		renderer.write_zb_ppm("target/line_perspective_msaa4x4_z.ppm").unwrap();
 	}
 	
 }
@@ -316,11 +965,11 @@ fn test_render_triangle_orthogonal_nomsaa() {
 	
 	let mut renderer: Renderer = create_test_base_renderer(1);
 
-	renderer.render_mesh(&triangle);
+	renderer.render_mesh(&triangle).unwrap();
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/triangle_orthogonal_nomsaa.ppm");
-		renderer.write_zb_ppm("target/triangle_orthogonal_nomsaa_z.ppm");
+		renderer.write_fb_ppm("target/triangle_orthogonal_nomsaa.ppm").unwrap();
+		renderer.write_zb_ppm("target/triangle_orthogonal_nomsaa_z.ppm").unwrap();
 	}
 	
 }
@@ -336,11 +985,15 @@ fn test_render_triangle_orthogonal_msaa2x2() {
 	
 	let mut renderer: Renderer = create_test_base_renderer(2);
 
-	renderer.render_mesh(&triangle);
+	renderer.render_mesh(&triangle).unwrap();
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/triangle_orthogonal_msaa2x2.ppm");
-		renderer.write_zb_ppm("target/triangle_orthogonal_msaa2x2_z.ppm");
+		renderer.write_fb_ppm("target/triangle_orthogonal_msaa2x2.ppm").unwrap();
+		renderer.write_zb_ppm("target/triangle_orthogonal_msaa2x2_z.ppm").unwrap();
+		renderer.write_fb_pfm("target/triangle_orthogonal_msaa2x2.pfm").unwrap();
+		renderer.write_zb_pfm("target/triangle_orthogonal_msaa2x2_z.pfm").unwrap();
+		renderer.write_fb_exr("target/triangle_orthogonal_msaa2x2.exr").unwrap();
+		renderer.write_zb_exr("target/triangle_orthogonal_msaa2x2_z.exr").unwrap();
 	}
 	
 }
@@ -356,11 +1009,11 @@ fn test_render_triangle_orthogonal_msaa4x4() {
 	
 	let mut renderer: Renderer = create_test_base_renderer(4);
 
-	renderer.render_mesh(&triangle);
+	renderer.render_mesh(&triangle).unwrap();
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/triangle_orthogonal_msaa4x4.ppm");
-		renderer.write_zb_ppm("target/triangle_orthogonal_msaa4x4_z.ppm");
+		renderer.write_fb_ppm("target/triangle_orthogonal_msaa4x4.ppm").unwrap();
+		renderer.write_zb_ppm("target/triangle_orthogonal_msaa4x4_z.ppm").unwrap();
 	}
 	
 }
@@ -376,11 +1029,11 @@ fn test_render_triangle_perspective_nomsaa() {
 	
 	let mut renderer: Renderer = create_test_base_renderer_perspective(1);
 
-	renderer.render_mesh(&triangle);
+	renderer.render_mesh(&triangle).unwrap();
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/triangle_perspective_nomsaa.ppm");
-		renderer.write_zb_ppm("target/triangle_perspective_nomsaa_z.ppm");
+		renderer.write_fb_ppm("target/triangle_perspective_nomsaa.ppm").unwrap();
+		renderer.write_zb_ppm("target/triangle_perspective_nomsaa_z.ppm").unwrap();
 	}
 	
 }
@@ -396,11 +1049,11 @@ fn test_render_triangle_perspective_msaa2x2() {
 	
 	let mut renderer: Renderer = create_test_base_renderer_perspective(2);
 
-	renderer.render_mesh(&triangle);
+	renderer.render_mesh(&triangle).unwrap();
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/triangle_perspective_msaa2x2.ppm");
-		renderer.write_zb_ppm("target/triangle_perspective_msaa2x2_z.ppm");
+		renderer.write_fb_ppm("target/triangle_perspective_msaa2x2.ppm").unwrap();
+		renderer.write_zb_ppm("target/triangle_perspective_msaa2x2_z.ppm").unwrap();
 	}
 	
 }
@@ -416,11 +1069,11 @@ fn test_render_triangle_perspective_msaa4x4() {
 	
 	let mut renderer: Renderer = create_test_base_renderer_perspective(4);
 
-	renderer.render_mesh(&triangle);
+	renderer.render_mesh(&triangle).unwrap();
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/triangle_perspective_msaa4x4.ppm");
-		renderer.write_zb_ppm("target/triangle_perspective_msaa4x4_z.ppm");
+		renderer.write_fb_ppm("target/triangle_perspective_msaa4x4.ppm").unwrap();
+		renderer.write_zb_ppm("target/triangle_perspective_msaa4x4_z.ppm").unwrap();
 	}
 	
 }
@@ -437,11 +1090,11 @@ fn test_render_quad_orthogonal_nomsaa() {
 	
 	let mut renderer: Renderer = create_test_base_renderer(1);
 
-	renderer.render_mesh(&quad);
+	renderer.render_mesh(&quad).unwrap();
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/quad_orthogonal_nomsaa.ppm");
-		renderer.write_zb_ppm("target/quad_orthogonal_nomsaa_z.ppm");
+		renderer.write_fb_ppm("target/quad_orthogonal_nomsaa.ppm").unwrap();
+		renderer.write_zb_ppm("target/quad_orthogonal_nomsaa_z.ppm").unwrap();
 	}
 	
 }
@@ -458,11 +1111,11 @@ fn test_render_quad_orthogonal_msaa2x2() {
 	
 	let mut renderer: Renderer = create_test_base_renderer(2);
 
-	renderer.render_mesh(&quad);
+	renderer.render_mesh(&quad).unwrap();
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/quad_orthogonal_msaa2x2.ppm");
-		renderer.write_zb_ppm("target/quad_orthogonal_msaa2x2_z.ppm");
+		renderer.write_fb_ppm("target/quad_orthogonal_msaa2x2.ppm").unwrap();
+		renderer.write_zb_ppm("target/quad_orthogonal_msaa2x2_z.ppm").unwrap();
 	}
 	
 }
@@ -479,11 +1132,11 @@ fn test_render_quad_orthogonal_msaa4x4() {
 	
 	let mut renderer: Renderer = create_test_base_renderer(4);
 
-	renderer.render_mesh(&quad);
+	renderer.render_mesh(&quad).unwrap();
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/quad_orthogonal_msaa4x4.ppm");
-		renderer.write_zb_ppm("target/quad_orthogonal_msaa4x4_z.ppm");
+		renderer.write_fb_ppm("target/quad_orthogonal_msaa4x4.ppm").unwrap();
+		renderer.write_zb_ppm("target/quad_orthogonal_msaa4x4_z.ppm").unwrap();
 	}
 	
 }
@@ -500,11 +1153,11 @@ fn test_render_quad_perspective_nomsaa() {
 	
 	let mut renderer: Renderer = create_test_base_renderer_perspective(1);
 
-	renderer.render_mesh(&quad);
+	renderer.render_mesh(&quad).unwrap();
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/quad_perspective_nomsaa.ppm");
-		renderer.write_zb_ppm("target/quad_perspective_nomsaa_z.ppm");
+		renderer.write_fb_ppm("target/quad_perspective_nomsaa.ppm").unwrap();
+		renderer.write_zb_ppm("target/quad_perspective_nomsaa_z.ppm").unwrap();
 	}
 	
 }
@@ -521,11 +1174,11 @@ fn test_render_quad_perspective_msaa2x2() {
 	
 	let mut renderer: Renderer = create_test_base_renderer_perspective(2);
 
-	renderer.render_mesh(&quad);
+	renderer.render_mesh(&quad).unwrap();
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/quad_perspective_msaa2x2.ppm");
-		renderer.write_zb_ppm("target/quad_perspective_msaa2x2_z.ppm");
+		renderer.write_fb_ppm("target/quad_perspective_msaa2x2.ppm").unwrap();
+		renderer.write_zb_ppm("target/quad_perspective_msaa2x2_z.ppm").unwrap();
 	}
 	
 }
@@ -542,11 +1195,11 @@ fn test_render_quad_perspective_msaa4x4() {
 	
 	let mut renderer: Renderer = create_test_base_renderer_perspective(4);
 
-	renderer.render_mesh(&quad);
+	renderer.render_mesh(&quad).unwrap();
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/quad_perspective_msaa4x4.ppm");
-		renderer.write_zb_ppm("target/quad_perspective_msaa4x4_z.ppm");
+		renderer.write_fb_ppm("target/quad_perspective_msaa4x4.ppm").unwrap();
+		renderer.write_zb_ppm("target/quad_perspective_msaa4x4_z.ppm").unwrap();
 	}
 	
 }
@@ -560,11 +1213,11 @@ fn test_render_cube_orthogonal_nomsaa() {
 	
 	let mut renderer: Renderer = create_test_base_renderer(1);
 
-	renderer.render_mesh(&cube);
+	renderer.render_mesh(&cube).unwrap();
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/cube_orthogonal_nomsaa.ppm");
-		renderer.write_zb_ppm("target/cube_orthogonal_nomsaa_z.ppm");
+		renderer.write_fb_ppm("target/cube_orthogonal_nomsaa.ppm").unwrap();
+		renderer.write_zb_ppm("target/cube_orthogonal_nomsaa_z.ppm").unwrap();
 	}
 
 	let mut rotmat: Mat3 = Mat3::new_empty();
@@ -577,11 +1230,11 @@ fn test_render_cube_orthogonal_nomsaa() {
 	
 	renderer.clear_frame();
 	
-	renderer.render_mesh(&cube);
+	renderer.render_mesh(&cube).unwrap();
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/cube_rot45_orthogonal_nomsaa.ppm");
-		renderer.write_zb_ppm("target/cube_rot45_orthogonal_nomsaa_z.ppm");
+		renderer.write_fb_ppm("target/cube_rot45_orthogonal_nomsaa.ppm").unwrap();
+		renderer.write_zb_ppm("target/cube_rot45_orthogonal_nomsaa_z.ppm").unwrap();
 	}
 	
 	rotmat.to_rotz(65.); 
@@ -591,11 +1244,11 @@ fn test_render_cube_orthogonal_nomsaa() {
 	cube.transform(&rotmat);
 	
 	renderer.clear_frame();
-	renderer.render_mesh(&cube);
+	renderer.render_mesh(&cube).unwrap();
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/cube_rot65_orthogonal_nomsaa.ppm");
-		renderer.write_zb_ppm("target/cube_rot65_orthogonal_nomsaa_z.ppm");
+		renderer.write_fb_ppm("target/cube_rot65_orthogonal_nomsaa.ppm").unwrap();
+		renderer.write_zb_ppm("target/cube_rot65_orthogonal_nomsaa_z.ppm").unwrap();
 	}
 }
 
@@ -608,11 +1261,11 @@ fn test_render_cube_perspective_nomsaa() {
 	
 	let mut renderer: Renderer = create_test_base_renderer_perspective(1);
 
-	renderer.render_mesh(&cube);
+	renderer.render_mesh(&cube).unwrap();
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/cube_perspective_nomsaa.ppm");
-		renderer.write_zb_ppm("target/cube_perspective_nomsaa_z.ppm");
+		renderer.write_fb_ppm("target/cube_perspective_nomsaa.ppm").unwrap();
+		renderer.write_zb_ppm("target/cube_perspective_nomsaa_z.ppm").unwrap();
 	}
 
 	let mut rotmat: Mat3 = Mat3::new_empty();
@@ -625,11 +1278,11 @@ fn test_render_cube_perspective_nomsaa() {
 	
 	renderer.clear_frame();
 	
-	renderer.render_mesh(&cube);
+	renderer.render_mesh(&cube).unwrap();
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/cube_rot45_perspective_nomsaa.ppm");
-		renderer.write_zb_ppm("target/cube_rot45_perspective_nomsaa_z.ppm");
+		renderer.write_fb_ppm("target/cube_rot45_perspective_nomsaa.ppm").unwrap();
+		renderer.write_zb_ppm("target/cube_rot45_perspective_nomsaa_z.ppm").unwrap();
 	}
 	
 	rotmat.to_rotz(65.); 
@@ -639,10 +1292,71 @@ fn test_render_cube_perspective_nomsaa() {
 	cube.transform(&rotmat);
 	
 	renderer.clear_frame();
-	renderer.render_mesh(&cube);
+	renderer.render_mesh(&cube).unwrap();
 	
 	if cfg!(feature = "output") {
-		renderer.write_fb_ppm("target/cube_rot65_perspective_nomsaa.ppm");
-		renderer.write_zb_ppm("target/cube_rot65_perspective_nomsaa_z.ppm");
+		renderer.write_fb_ppm("target/cube_rot65_perspective_nomsaa.ppm").unwrap();
+		renderer.write_zb_ppm("target/cube_rot65_perspective_nomsaa_z.ppm").unwrap();
 	}
 }
+
+//columns and rows covered by drawn pixels
+fn drawn_extent(frame: &Texture) -> (u32, u32) {
+	let (mut minx, mut maxx, mut miny, mut maxy) = (frame.width, 0, frame.height, 0);
+	for y in 0..frame.height {
+		for x in 0..frame.width {
+			if frame.buffer[(y * frame.width + x) as usize] != BLACK {
+				minx = minx.min(x);
+				maxx = maxx.max(x + 1);
+				miny = miny.min(y);
+				maxy = maxy.max(y + 1);
+			}
+		}
+	}
+	(maxx.saturating_sub(minx), maxy.saturating_sub(miny))
+}
+
+#[test]
+fn camera_fov_and_size_follow_image_aspect() {
+	let cube: Mesh = MeshBuilder::cube(&Vec3::new_empty(), 1.0);
+	let from: Vec3 = Vec3::new(0.0, 0.0, 3.0);
+	let to: Vec3 = Vec3::new_empty();
+	let up: Vec3 = Vec3::new(0.0, 1.0, 0.0);
+	
+	//two units high, the cube covers half of the rows and as many columns whatever the width
+	let mut renderer: Renderer = Renderer::new(200, 100, &BLACK, 1);
+	renderer.set_camera(Camera::orthographic_size(&from, &to, &up, 2.0, 1.0, 1.0, 5.0));
+	assert_eq!((renderer.camera().r - 2.0).abs() < 1e-6, true);
+	renderer.render_mesh(&cube).unwrap();
+	assert_eq!(drawn_extent(&renderer.read_frame()), (50, 50));
+	
+	renderer.resize(100, 200);
+	renderer.render_mesh(&cube).unwrap();
+	assert_eq!(drawn_extent(&renderer.read_frame()), (100, 100));
+	
+	//a 90 degree field of view shows 5 units at the front face of the cube 2.5 units away
+	renderer.clear_frame();
+	renderer.set_camera(Camera::perspective_fov(&from, &to, &up, 90.0, 1.0, 1.0, 5.0));
+	assert_eq!((renderer.camera().t - 1.0).abs() < 1e-6 && (renderer.camera().r - 0.5).abs() < 1e-6, true);
+	renderer.render_mesh(&cube).unwrap();
+	let (w, h) = drawn_extent(&renderer.read_frame());
+	assert_eq!(w, h);
+	assert_eq!((w as i32 - 40).abs() <= 1, true);
+	
+	//up turns the image, cameras without fit_aspect keep their bounds
+	renderer.resize(200, 100);
+	renderer.set_camera(Camera::orthographic_size(&from, &to, &Vec3::new(1.0, 0.0, 0.0), 2.0, 1.0, 1.0, 5.0));
+	assert_eq!((renderer.camera().up.x - 1.0).abs() < 1e-6, true);
+	let mut camera: Camera = Camera::new();
+	camera.to_ortho(&from, &to, -2.0, 2.0, 2.0, -2.0, 1.0, 5.0);
+	renderer.set_camera(camera);
+	assert_eq!(renderer.camera().r, 2.0);
+	
+	//the projection kind is recorded, a fresh camera has none and stays without one
+	assert_eq!(renderer.camera().projection_kind, ProjectionKind::Orthographic);
+	let mut camera: Camera = Camera::new();
+	camera.look_at(&from, &to);
+	camera.set_aspect(2.0);
+	assert_eq!(camera.projection_kind, ProjectionKind::None);
+	assert_eq!(camera.projection._44, 0.0);
+}
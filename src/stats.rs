@@ -0,0 +1,89 @@
+use std::fmt;
+use std::time::Duration;
+use color::*;
+use math::utils::{interpolate_lin};
+
+//last bucket of the overdraw histogram collects all samples written this often or more
+pub const OVERDRAW_BUCKETS: usize = 8;
+
+#[derive(Clone)]
+pub struct RenderStats {
+	pub points: u32,
+	pub lines: u32,
+	pub triangles: u32,
+	pub instances: u32,
+	pub rejected_w: u32,
+	pub rejected_w_triangles: u32, //triangles among rejected_w
+	pub rejected_culled: u32,
+	pub rejected_zero_area: u32,
+	pub vertices_transformed: u64,
+	pub vertex_cache_hits: u64, //projections reused by indexed meshes
+	pub fragments_tested: u64,
+	pub depth_passes: u64,
+	pub overdraw: Vec<u64>, //index is the count of depth passes of a sample
+	pub vertex_time: Duration,
+	pub raster_time: Duration,
+	pub total_time: Duration,
+}
+
+impl RenderStats {
+	pub fn new() -> RenderStats {
+		RenderStats {
+			points: 0, lines: 0, triangles: 0, instances: 0,
+			rejected_w: 0, rejected_w_triangles: 0, rejected_culled: 0, rejected_zero_area: 0,
+			vertices_transformed: 0, vertex_cache_hits: 0,
+			fragments_tested: 0, depth_passes: 0,
+			overdraw: vec![0; OVERDRAW_BUCKETS],
+			vertex_time: Duration::new(0, 0),
+			raster_time: Duration::new(0, 0),
+			total_time: Duration::new(0, 0),
+		}
+	}
+	
+	pub fn reset(&mut self) {
+		*self = RenderStats::new();
+	}
+	
+	pub fn shapes(&self) -> u32 {
+		self.points + self.lines + self.triangles
+	}
+	
+	pub fn set_overdraw_from(&mut self, overdraw_buffer: &Vec<u32>) {
+		for bucket in self.overdraw.iter_mut() {
+			*bucket = 0;
+		}
+		for cnt in overdraw_buffer.iter() {
+			self.overdraw[(*cnt as usize).min(OVERDRAW_BUCKETS - 1)] += 1;
+		}
+	}
+}
+
+impl fmt::Display for RenderStats {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "shapes points:{} lines:{} triangles:{} instances:{} rejected w:{} culled:{} zero area:{} vertices transformed:{} cache hits:{} fragments:{} depth passes:{} overdraw:{:?} time vertex:{:?} raster:{:?} total:{:?}",
+				self.points, self.lines, self.triangles, self.instances,
+				self.rejected_w, self.rejected_culled, self.rejected_zero_area,
+				self.vertices_transformed, self.vertex_cache_hits,
+				self.fragments_tested, self.depth_passes, self.overdraw,
+				self.vertex_time, self.raster_time, self.total_time)
+	}
+}
+
+/*
+	heat map color ramp: black (never written) over blue, green and yellow up to red
+	for samples written max_cnt times or more
+*/
+pub fn overdraw_color(cnt: f32, max_cnt: f32, color: &mut Color) {
+	if cnt <= 0.0 {
+		color.set_from_color(&BLACK);
+		return;
+	}
+	let t: f32 = interpolate_lin(cnt.min(max_cnt), 0.0, 0.0, max_cnt, 3.0);
+	if t < 1.0 {
+		color.set_from_rgb(0.0, 255.0 * t, 255.0 * (1.0 - t));
+	} else if t < 2.0 {
+		color.set_from_rgb(255.0 * (t - 1.0), 255.0, 0.0);
+	} else {
+		color.set_from_rgb(255.0, 255.0 * (3.0 - t), 0.0);
+	}
+}
@@ -0,0 +1,15 @@
+use texture::*;
+use math::vec::vec3::*;
+
+//compares pixel by pixel and reports the first difference
+pub fn assert_same_frame(frame: &Texture, expected: &Texture) {
+	assert_eq!((frame.width, frame.height), (expected.width, expected.height));
+	for (i, (a, b)) in frame.buffer.iter().zip(expected.buffer.iter()).enumerate() {
+		assert!(a == b, "pixel {} is ({}, {}, {}) instead of ({}, {}, {})", i, a.r, a.g, a.b, b.r, b.g, b.b);
+	}
+}
+
+pub fn assert_close(a: &Vec3, b: &Vec3, eps: f32) {
+	assert!((a.x - b.x).abs() < eps && (a.y - b.y).abs() < eps && (a.z - b.z).abs() < eps,
+		"({}, {}, {}) is not within {} of ({}, {}, {})", a.x, a.y, a.z, eps, b.x, b.y, b.z);
+}
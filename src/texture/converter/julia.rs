@@ -1,4 +1,5 @@
 use texture::*;
+use error::*;
 use color::*;
 use math::algorithm::fractals::julia::*;
 use math::utils::{interpolate_lin};
@@ -33,10 +34,13 @@ pub fn julia_color_line_int_8_bit(julia: &Julia, jbt: &JuliaPoint, col: &mut Col
 }
 
 pub fn julia_to_texture(julia: &Julia, target: &mut Texture, 
-					    jul_color_func: fn(&Julia, &JuliaPoint, &mut Color) ) {
+					    jul_color_func: fn(&Julia, &JuliaPoint, &mut Color) ) -> RenderResult<()> {
 	if target.width == julia.width && target.height == julia.height {
 		for (i, color) in target.buffer.iter_mut().enumerate() {
 			jul_color_func(julia, &julia.map[i], color);
 		}
+		Ok(())
+	} else {
+		Err(RenderError::dimension_mismatch(julia.width, julia.height, target.width, target.height))
 	}
 }
@@ -1,4 +1,5 @@
 use texture::*;
+use error::*;
 use color::*;
 use math::algorithm::fractals::mandelbrot::*;
 use math::utils::{interpolate_lin};
@@ -51,11 +52,14 @@ pub fn mandelbrot_color_line_int_rgb(mb: &Mandelbrot, mbt: &MandelbrotPoint, col
 }
 
 pub fn mandelbrot_to_texture(mb: &Mandelbrot, target: &mut Texture, 
-							 mb_color_func: fn(&Mandelbrot, &MandelbrotPoint, &mut Color) ) {
+							 mb_color_func: fn(&Mandelbrot, &MandelbrotPoint, &mut Color) ) -> RenderResult<()> {
 	if target.width == mb.width && target.height == mb.height {
 		for (i, color) in target.buffer.iter_mut().enumerate() {
 			mb_color_func(mb, &mb.map[i], color);
 		}
+		Ok(())
+	} else {
+		Err(RenderError::dimension_mismatch(mb.width, mb.height, target.width, target.height))
 	}
 }
 
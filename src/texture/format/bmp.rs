@@ -0,0 +1,201 @@
+/*
+	BMP reader and writer. Writes 24 bit images or 32 bit images with an alpha mask (if the
+	texture has alpha), reads uncompressed 8, 24 and 32 bit images and bitfield encoded 16 and 32 bit images.
+*/
+use std::io::{Read, Write};
+use texture::*;
+use error::*;
+
+fn write_u16(out: &mut Vec<u8>, value: u16) {
+	out.extend(&[value as u8, (value >> 8) as u8]);
+}
+
+fn write_u32(out: &mut Vec<u8>, value: u32) {
+	out.extend(&[value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]);
+}
+
+fn read_u16(data: &[u8], pos: usize) -> u16 {
+	(data[pos] as u16) | ((data[pos + 1] as u16) << 8)
+}
+
+fn read_u32(data: &[u8], pos: usize) -> u32 {
+	(data[pos] as u32) | ((data[pos + 1] as u32) << 8) | ((data[pos + 2] as u32) << 16) | ((data[pos + 3] as u32) << 24)
+}
+
+fn invalid(message: &str) -> RenderError {
+	RenderError::InvalidImage(format!("bmp: {}", message))
+}
+
+pub fn write_bmp<W: Write>(texture: &Texture, w: &mut W) -> RenderResult<()> {
+	let with_alpha: bool = texture.alpha.is_some();
+	let bytes_per_pixel: usize = if with_alpha { 4 } else { 3 };
+	let row_size: usize = (texture.width as usize * bytes_per_pixel + 3) & !3;
+	//BITMAPV4HEADER for the alpha mask, BITMAPINFOHEADER otherwise
+	let info_size: u32 = if with_alpha { 108 } else { 40 };
+	let data_offset: u32 = 14 + info_size;
+	let image_size: u32 = (row_size * texture.height as usize) as u32;
+	
+	let mut out: Vec<u8> = Vec::with_capacity(data_offset as usize + image_size as usize);
+	out.extend(b"BM");
+	write_u32(&mut out, data_offset + image_size);
+	write_u32(&mut out, 0);
+	write_u32(&mut out, data_offset);
+	
+	write_u32(&mut out, info_size);
+	write_u32(&mut out, texture.width);
+	write_u32(&mut out, texture.height);
+	write_u16(&mut out, 1);
+	write_u16(&mut out, (bytes_per_pixel * 8) as u16);
+	write_u32(&mut out, if with_alpha { 3 } else { 0 });
+	write_u32(&mut out, image_size);
+	write_u32(&mut out, 2835); //72 dpi
+	write_u32(&mut out, 2835);
+	write_u32(&mut out, 0);
+	write_u32(&mut out, 0);
+	if with_alpha {
+		for mask in &[0x00ff0000, 0x0000ff00, 0x000000ff, 0xff000000] {
+			write_u32(&mut out, *mask);
+		}
+		write_u32(&mut out, 0x73524742); //sRGB
+		out.extend(&[0; 48]); //endpoints and gamma
+	}
+	
+	//rows are stored from bottom to top
+	for y in (0..texture.height as usize).rev() {
+		let start: usize = out.len();
+		for x in 0..texture.width as usize {
+			let i: usize = y * texture.width as usize + x;
+			let color: &Color = &texture.buffer[i];
+			out.extend(&[color.b as u8, color.g as u8, color.r as u8]);
+			if let Some(ref alpha) = texture.alpha {
+				out.push(alpha[i] as u8);
+			}
+		}
+		while out.len() - start < row_size {
+			out.push(0);
+		}
+	}
+	
+	w.write_all(&out)?;
+	Ok(())
+}
+
+//extracts a channel described by a bitfield mask and scales it to 0 - 255
+fn mask_value(pixel: u32, mask: u32) -> f32 {
+	if mask == 0 {
+		return 0.0;
+	}
+	let shift: u32 = mask.trailing_zeros();
+	let max: u32 = mask >> shift;
+	((pixel & mask) >> shift) as f32 * 255.0 / max as f32
+}
+
+pub fn read_bmp<R: Read>(r: &mut R) -> RenderResult<Texture> {
+	let mut data: Vec<u8> = Vec::new();
+	r.read_to_end(&mut data)?;
+	if data.len() < 26 || &data[0..2] != b"BM" {
+		return Err(invalid("missing signature"));
+	}
+	
+	let data_offset: usize = read_u32(&data, 10) as usize;
+	let info_size: usize = read_u32(&data, 14) as usize;
+	if info_size < 40 || data.len() < 14 + info_size {
+		return Err(invalid("unsupported info header"));
+	}
+	
+	let width: i32 = read_u32(&data, 18) as i32;
+	let height: i32 = read_u32(&data, 22) as i32;
+	let bit_count: u16 = read_u16(&data, 28);
+	let compression: u32 = read_u32(&data, 30);
+	if width <= 0 || height == 0 {
+		return Err(invalid("invalid image size"));
+	}
+	//negative height marks top to bottom rows
+	let top_down: bool = height < 0;
+	let height: u32 = match height.checked_abs() {
+		Some(height) => height as u32,
+		None => return Err(invalid("invalid image size")),
+	};
+	let width: u32 = width as u32;
+	
+	let mut masks: [u32; 4] = [0x00ff0000, 0x0000ff00, 0x000000ff, 0];
+	if bit_count == 16 {
+		masks = [0x7c00, 0x03e0, 0x001f, 0];
+	}
+	match (compression, bit_count) {
+		(0, 8) | (0, 16) | (0, 24) | (0, 32) => {},
+		(3, 16) | (3, 32) => {
+			//the masks follow a BITMAPINFOHEADER or are part of newer headers
+			if data.len() < 14 + 40 + 12 {
+				return Err(invalid("missing bitfields"));
+			}
+			for i in 0..3 {
+				masks[i] = read_u32(&data, 54 + i * 4);
+			}
+			if info_size >= 56 {
+				masks[3] = read_u32(&data, 66);
+			}
+		},
+		_ => return Err(invalid(&format!("compression {} with {} bit not supported", compression, bit_count))),
+	}
+	
+	let mut palette: Vec<Color> = Vec::new();
+	if bit_count == 8 {
+		let mut colors: usize = read_u32(&data, 46) as usize;
+		if colors == 0 { colors = 256; }
+		let start: usize = 14 + info_size;
+		if data.len() < start + colors * 4 {
+			return Err(invalid("truncated palette"));
+		}
+		for i in 0..colors {
+			let p: usize = start + i * 4;
+			palette.push(Color::new(data[p + 2] as f32, data[p + 1] as f32, data[p] as f32));
+		}
+	}
+	
+	let bytes_per_pixel: usize = bit_count as usize / 8;
+	let row_size: usize = (width as usize * bytes_per_pixel + 3) & !3;
+	let needed: Option<usize> = row_size.checked_mul(height as usize).and_then(|size| size.checked_add(data_offset));
+	if needed.map_or(true, |needed| data.len() < needed) {
+		return Err(invalid("truncated pixel data"));
+	}
+	
+	let mut texture: Texture = Texture::new(width, height);
+	let mut alpha: Vec<f32> = vec![255.0; texture.buffer.len()];
+	//32 bit images without alpha mask often leave the fourth byte zero
+	let mut alpha_used: bool = masks[3] != 0;
+	let guess_alpha: bool = compression == 0 && bit_count == 32;
+	
+	for row in 0..height as usize {
+		let y: usize = if top_down { row } else { height as usize - 1 - row };
+		let line: &[u8] = &data[data_offset + row * row_size..];
+		for x in 0..width as usize {
+			let i: usize = y * width as usize + x;
+			let p: &[u8] = &line[x * bytes_per_pixel..];
+			match bit_count {
+				8 => {
+					match palette.get(p[0] as usize) {
+						Some(color) => texture.buffer[i].set_from_color(color),
+						None => return Err(invalid("palette index out of range")),
+					}
+				},
+				24 => texture.buffer[i].set_from_rgb(p[2] as f32, p[1] as f32, p[0] as f32),
+				_ => {
+					let pixel: u32 = if bit_count == 16 { read_u16(p, 0) as u32 } else { read_u32(p, 0) };
+					texture.buffer[i].set_from_rgb(mask_value(pixel, masks[0]), mask_value(pixel, masks[1]), mask_value(pixel, masks[2]));
+					if guess_alpha {
+						alpha[i] = p[3] as f32;
+						alpha_used |= p[3] != 0;
+					} else if masks[3] != 0 {
+						alpha[i] = mask_value(pixel, masks[3]);
+					}
+				},
+			}
+		}
+	}
+	
+	if alpha_used {
+		texture.alpha = Some(alpha);
+	}
+	Ok(texture)
+}
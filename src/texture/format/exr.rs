@@ -0,0 +1,85 @@
+/*
+	Minimal OpenEXR writer, single part scanline image with uncompressed 32 bit float channels.
+*/
+use std::io::Write;
+use error::*;
+
+fn push_u32(out: &mut Vec<u8>, value: u32) {
+	out.extend(&[value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]);
+}
+
+fn push_attribute(out: &mut Vec<u8>, name: &str, type_name: &str, value: &[u8]) {
+	out.extend(name.as_bytes());
+	out.push(0);
+	out.extend(type_name.as_bytes());
+	out.push(0);
+	push_u32(out, value.len() as u32);
+	out.extend(value);
+}
+
+//channels are (name, data) pairs, every data holds width * height values with the top row first
+pub fn write_exr<W: Write>(w: &mut W, width: u32, height: u32, channels: &[(&str, &[f32])]) -> RenderResult<()> {
+	if width == 0 || height == 0 || channels.is_empty() {
+		return Err(RenderError::InvalidImage("exr image without pixels".to_string()));
+	}
+	for &(_, data) in channels {
+		if data.len() != width as usize * height as usize {
+			return Err(RenderError::InvalidImage(format!("expected {} values, got {}", width as usize * height as usize, data.len())));
+		}
+	}
+	
+	//readers expect the channel list sorted by name
+	let mut sorted: Vec<&(&str, &[f32])> = channels.iter().collect();
+	sorted.sort_by(|a, b| a.0.cmp(b.0));
+	
+	let mut header: Vec<u8> = vec![0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0];
+	
+	let mut chlist: Vec<u8> = Vec::new();
+	for channel in &sorted {
+		chlist.extend(channel.0.as_bytes());
+		chlist.push(0);
+		push_u32(&mut chlist, 2); //FLOAT
+		chlist.extend(&[0, 0, 0, 0]); //pLinear and reserved
+		push_u32(&mut chlist, 1); //x sampling
+		push_u32(&mut chlist, 1); //y sampling
+	}
+	chlist.push(0);
+	push_attribute(&mut header, "channels", "chlist", &chlist);
+	push_attribute(&mut header, "compression", "compression", &[0]);
+	
+	let mut window: Vec<u8> = Vec::new();
+	for value in &[0, 0, width - 1, height - 1] {
+		push_u32(&mut window, *value);
+	}
+	push_attribute(&mut header, "dataWindow", "box2i", &window);
+	push_attribute(&mut header, "displayWindow", "box2i", &window);
+	push_attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+	push_attribute(&mut header, "pixelAspectRatio", "float", &[0, 0, 0x80, 0x3f]);
+	push_attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+	push_attribute(&mut header, "screenWindowWidth", "float", &[0, 0, 0x80, 0x3f]);
+	header.push(0);
+	
+	//offset table with one entry per scanline
+	let line_size: u64 = 8 + 4 * width as u64 * sorted.len() as u64;
+	let data_start: u64 = header.len() as u64 + 8 * height as u64;
+	for y in 0..height as u64 {
+		let offset: u64 = data_start + y * line_size;
+		push_u32(&mut header, offset as u32);
+		push_u32(&mut header, (offset >> 32) as u32);
+	}
+	w.write_all(&header)?;
+	
+	let mut line: Vec<u8> = Vec::with_capacity(line_size as usize);
+	for y in 0..height as usize {
+		line.clear();
+		push_u32(&mut line, y as u32);
+		push_u32(&mut line, (line_size - 8) as u32);
+		for channel in &sorted {
+			for value in &channel.1[y * width as usize..(y + 1) * width as usize] {
+				push_u32(&mut line, value.to_bits());
+			}
+		}
+		w.write_all(&line)?;
+	}
+	Ok(())
+}
@@ -0,0 +1,310 @@
+/*
+	Animated GIF89a writer. Every frame gets its own palette of up to 256 colors by median cut,
+	frames with few colors keep them exactly. Floyd-Steinberg dithering spreads the quantization error.
+	The delay is given in 1/100 s, the NETSCAPE2.0 extension makes the animation loop.
+*/
+use std::io::Write;
+use std::collections::{HashMap, HashSet};
+use texture::*;
+use color::*;
+use error::*;
+
+pub struct GifWriter<W: Write> {
+	out: W,
+	width: u32,
+	height: u32,
+	pub delay: u16, //1/100 s per frame
+	pub dither: bool,
+	pub frames: u32, //frames written so far
+	finished: bool,
+}
+
+fn write_u16(out: &mut Vec<u8>, value: u16) {
+	out.extend(&[value as u8, (value >> 8) as u8]);
+}
+
+fn channel(value: f32) -> u8 {
+	value.max(0.0).min(255.0).round() as u8
+}
+
+fn key(r: u8, g: u8, b: u8) -> u32 {
+	((r as u32) << 16) | ((g as u32) << 8) | b as u32
+}
+
+//colors of a box of the median cut with their pixel counts
+struct ColorBox {
+	colors: Vec<([u8; 3], u32)>,
+}
+
+impl ColorBox {
+	fn range(&self, c: usize) -> u8 {
+		let min: u8 = self.colors.iter().map(|e| e.0[c]).min().unwrap_or(0);
+		let max: u8 = self.colors.iter().map(|e| e.0[c]).max().unwrap_or(0);
+		max - min
+	}
+	
+	fn widest(&self) -> (usize, u8) {
+		(0..3).map(|c| (c, self.range(c))).max_by_key(|&(_, range)| range).unwrap_or((0, 0))
+	}
+	
+	fn average(&self) -> Color {
+		let (mut r, mut g, mut b, mut cnt): (f64, f64, f64, f64) = (0.0, 0.0, 0.0, 0.0);
+		for &(c, n) in &self.colors {
+			r += c[0] as f64 * n as f64;
+			g += c[1] as f64 * n as f64;
+			b += c[2] as f64 * n as f64;
+			cnt += n as f64;
+		}
+		Color::new((r / cnt).round() as f32, (g / cnt).round() as f32, (b / cnt).round() as f32)
+	}
+}
+
+//palette of at most max_colors colors, the distinct colors of the texture if there are not more
+pub fn median_cut(texture: &Texture, max_colors: usize) -> Vec<Color> {
+	let mut counts: HashMap<u32, u32> = HashMap::new();
+	for color in &texture.buffer {
+		*counts.entry(key(channel(color.r), channel(color.g), channel(color.b))).or_insert(0) += 1;
+	}
+	let mut colors: Vec<([u8; 3], u32)> = counts.iter().map(|(k, n)| ([(k >> 16) as u8, (k >> 8) as u8, *k as u8], *n)).collect();
+	//the hash map order is random, sorting keeps palettes reproducible
+	colors.sort();
+	if colors.len() <= max_colors {
+		return colors.iter().map(|&(c, _)| Color::new(c[0] as f32, c[1] as f32, c[2] as f32)).collect();
+	}
+	
+	let mut boxes: Vec<ColorBox> = vec![ColorBox { colors }];
+	while boxes.len() < max_colors {
+		//split the box with the widest channel at the median pixel
+		let (index, channel, range) = boxes.iter().enumerate().map(|(i, b)| { let (c, r) = b.widest(); (i, c, r) })
+										   .max_by_key(|&(_, _, range)| range).unwrap();
+		if range == 0 {
+			break;
+		}
+		let mut colors: Vec<([u8; 3], u32)> = boxes.swap_remove(index).colors;
+		colors.sort_by_key(|e| e.0[channel]);
+		let total: u32 = colors.iter().map(|e| e.1).sum();
+		let mut cnt: u32 = 0;
+		let mut split: usize = 1;
+		for (i, e) in colors.iter().enumerate() {
+			cnt += e.1;
+			if cnt * 2 >= total {
+				split = (i + 1).max(1).min(colors.len() - 1);
+				break;
+			}
+		}
+		let upper: Vec<([u8; 3], u32)> = colors.split_off(split);
+		boxes.push(ColorBox { colors });
+		boxes.push(ColorBox { colors: upper });
+	}
+	boxes.iter().map(|b| b.average()).collect()
+}
+
+fn nearest(palette: &[Color], r: f32, g: f32, b: f32) -> usize {
+	let mut best: usize = 0;
+	let mut best_distance: f32 = ::std::f32::MAX;
+	for (i, p) in palette.iter().enumerate() {
+		let distance: f32 = (p.r - r) * (p.r - r) + (p.g - g) * (p.g - g) + (p.b - b) * (p.b - b);
+		if distance < best_distance {
+			best = i;
+			best_distance = distance;
+		}
+	}
+	best
+}
+
+//palette index of every pixel, dithering diffuses the error 7/16 right, 3/16, 5/16 and 1/16 to the next line
+pub fn map_to_palette(texture: &Texture, palette: &[Color], dither: bool) -> Vec<u8> {
+	let width: usize = texture.width as usize;
+	let mut indices: Vec<u8> = Vec::with_capacity(texture.buffer.len());
+	if !dither {
+		let mut cache: HashMap<u32, u8> = HashMap::new();
+		for color in &texture.buffer {
+			let (r, g, b) = (channel(color.r), channel(color.g), channel(color.b));
+			let index: u8 = *cache.entry(key(r, g, b)).or_insert_with(|| nearest(palette, r as f32, g as f32, b as f32) as u8);
+			indices.push(index);
+		}
+		return indices;
+	}
+	let mut error: Vec<[f32; 3]> = vec![[0.0; 3]; width + 2];
+	let mut next_error: Vec<[f32; 3]> = vec![[0.0; 3]; width + 2];
+	for j in 0..texture.height as usize {
+		for i in 0..width {
+			let color: &Color = &texture.buffer[j * width + i];
+			let e: [f32; 3] = error[i + 1];
+			let wanted: [f32; 3] = [(color.r.max(0.0).min(255.0) + e[0]).max(0.0).min(255.0),
+									(color.g.max(0.0).min(255.0) + e[1]).max(0.0).min(255.0),
+									(color.b.max(0.0).min(255.0) + e[2]).max(0.0).min(255.0)];
+			let index: usize = nearest(palette, wanted[0], wanted[1], wanted[2]);
+			let p: &Color = &palette[index];
+			let diff: [f32; 3] = [wanted[0] - p.r, wanted[1] - p.g, wanted[2] - p.b];
+			for c in 0..3 {
+				error[i + 2][c] += diff[c] * 7.0 / 16.0;
+				next_error[i][c] += diff[c] * 3.0 / 16.0;
+				next_error[i + 1][c] += diff[c] * 5.0 / 16.0;
+				next_error[i + 2][c] += diff[c] / 16.0;
+			}
+			indices.push(index as u8);
+		}
+		::std::mem::swap(&mut error, &mut next_error);
+		for e in next_error.iter_mut() {
+			*e = [0.0; 3];
+		}
+	}
+	indices
+}
+
+//least significant bit first, as GIF wants it
+struct BitWriter {
+	out: Vec<u8>,
+	buffer: u32,
+	bits: u32,
+}
+
+impl BitWriter {
+	fn write(&mut self, code: u16, size: u32) {
+		self.buffer |= (code as u32) << self.bits;
+		self.bits += size;
+		while self.bits >= 8 {
+			self.out.push(self.buffer as u8);
+			self.buffer >>= 8;
+			self.bits -= 8;
+		}
+	}
+	
+	fn finish(mut self) -> Vec<u8> {
+		if self.bits > 0 {
+			self.out.push(self.buffer as u8);
+		}
+		self.out
+	}
+}
+
+//variable length LZW of GIF, the code size grows up to 12 bits and a clear code restarts the table when it is full
+pub fn lzw_encode(indices: &[u8], min_code_size: u32) -> Vec<u8> {
+	let clear: u16 = 1 << min_code_size;
+	let end: u16 = clear + 1;
+	let mut writer: BitWriter = BitWriter { out: Vec::new(), buffer: 0, bits: 0 };
+	let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+	let mut next: u16 = end + 1;
+	let mut size: u32 = min_code_size + 1;
+	writer.write(clear, size);
+	
+	let mut prefix: u16 = match indices.first() {
+		Some(index) => *index as u16,
+		None => {
+			writer.write(end, size);
+			return writer.finish();
+		},
+	};
+	for index in &indices[1..] {
+		if let Some(code) = table.get(&(prefix, *index)) {
+			prefix = *code;
+			continue;
+		}
+		writer.write(prefix, size);
+		//the decoder adds its entry one code later, so it grows the code size at the same point
+		if next as u32 >= (1 << size) && size < 12 {
+			size += 1;
+		}
+		if next >= 4095 {
+			writer.write(clear, size);
+			table.clear();
+			next = end + 1;
+			size = min_code_size + 1;
+		} else {
+			table.insert((prefix, *index), next);
+			next += 1;
+		}
+		prefix = *index as u16;
+	}
+	writer.write(prefix, size);
+	if next as u32 >= (1 << size) && size < 12 {
+		size += 1;
+	}
+	writer.write(end, size);
+	writer.finish()
+}
+
+impl<W: Write> GifWriter<W> {
+	//repeat None plays the animation once, Some(0) loops forever
+	pub fn new(mut out: W, width: u32, height: u32, repeat: Option<u16>) -> RenderResult<GifWriter<W>> {
+		if width == 0 || height == 0 || width > 0xffff || height > 0xffff {
+			return Err(RenderError::InvalidImage(format!("gif: invalid size {}x{}", width, height)));
+		}
+		let mut header: Vec<u8> = b"GIF89a".to_vec();
+		write_u16(&mut header, width as u16);
+		write_u16(&mut header, height as u16);
+		//no global color table, 8 bit color resolution
+		header.extend(&[0x70, 0, 0]);
+		if let Some(count) = repeat {
+			header.extend(&[0x21, 0xff, 11]);
+			header.extend(b"NETSCAPE2.0");
+			header.extend(&[3, 1]);
+			write_u16(&mut header, count);
+			header.push(0);
+		}
+		out.write_all(&header)?;
+		Ok(GifWriter { out, width, height, delay: 4, dither: false, frames: 0, finished: false })
+	}
+	
+	pub fn write_frame(&mut self, texture: &Texture) -> RenderResult<()> {
+		if texture.width != self.width || texture.height != self.height {
+			return Err(RenderError::dimension_mismatch(self.width, self.height, texture.width, texture.height));
+		}
+		if self.finished {
+			return Err(RenderError::InvalidImage("gif: frame after the trailer".to_string()));
+		}
+		let palette: Vec<Color> = median_cut(texture, 256);
+		//exact palettes need no dithering
+		let keys: HashSet<u32> = palette.iter().map(|c| key(c.r as u8, c.g as u8, c.b as u8)).collect();
+		let exact: bool = texture.buffer.iter().all(|c| keys.contains(&key(channel(c.r), channel(c.g), channel(c.b))));
+		let indices: Vec<u8> = map_to_palette(texture, &palette, self.dither && !exact);
+		let mut bits: u32 = 1;
+		while (1 << bits) < palette.len() {
+			bits += 1;
+		}
+		
+		let mut block: Vec<u8> = Vec::new();
+		//graphic control extension with the delay, no transparency
+		block.extend(&[0x21, 0xf9, 4, 0]);
+		write_u16(&mut block, self.delay);
+		block.extend(&[0, 0]);
+		//image descriptor with a local color table
+		block.push(0x2c);
+		write_u16(&mut block, 0);
+		write_u16(&mut block, 0);
+		write_u16(&mut block, self.width as u16);
+		write_u16(&mut block, self.height as u16);
+		block.push(0x80 | (bits - 1) as u8);
+		for i in 0..(1 << bits) {
+			match palette.get(i) {
+				Some(c) => block.extend(&[c.r as u8, c.g as u8, c.b as u8]),
+				None => block.extend(&[0, 0, 0]),
+			}
+		}
+		let min_code_size: u32 = bits.max(2);
+		block.push(min_code_size as u8);
+		for chunk in lzw_encode(&indices, min_code_size).chunks(255) {
+			block.push(chunk.len() as u8);
+			block.extend(chunk);
+		}
+		block.push(0);
+		self.out.write_all(&block)?;
+		self.frames += 1;
+		Ok(())
+	}
+	
+	//writes the trailer, later frames are rejected
+	pub fn finish(&mut self) -> RenderResult<()> {
+		if !self.finished {
+			self.out.write_all(&[0x3b])?;
+			self.finished = true;
+		}
+		self.out.flush()?;
+		Ok(())
+	}
+	
+	pub fn into_inner(self) -> W {
+		self.out
+	}
+}
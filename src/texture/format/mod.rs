@@ -0,0 +1,8 @@
+pub mod zlib;
+pub mod png;
+pub mod netpbm;
+pub mod exr;
+pub mod bmp;
+pub mod tga;
+pub mod y4m;
+pub mod gif;
@@ -0,0 +1,361 @@
+/*
+	Netpbm formats P1 - P6 and the float formats PF (color) and Pf (gray).
+	Gray and bitmap images are written from the luma of the colors, 0.0 - 255.0 texture
+	values are stored as 0.0 - 1.0 in float maps.
+*/
+use std::io::{Read, Write};
+use texture::*;
+use error::*;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum NetpbmFormat {
+	BitmapAscii,  //P1
+	GraymapAscii, //P2
+	PixmapAscii,  //P3
+	Bitmap,       //P4
+	Graymap,      //P5
+	Pixmap,       //P6
+}
+
+impl NetpbmFormat {
+	pub fn magic(&self) -> &'static str {
+		match *self {
+			NetpbmFormat::BitmapAscii => "P1",
+			NetpbmFormat::GraymapAscii => "P2",
+			NetpbmFormat::PixmapAscii => "P3",
+			NetpbmFormat::Bitmap => "P4",
+			NetpbmFormat::Graymap => "P5",
+			NetpbmFormat::Pixmap => "P6",
+		}
+	}
+}
+
+fn luma(color: &Color) -> f32 {
+	0.299 * color.r + 0.587 * color.g + 0.114 * color.b
+}
+
+//ascii output with the line length limit of 70 characters
+struct AsciiLines {
+	out: Vec<u8>,
+	line_len: usize,
+}
+
+impl AsciiLines {
+	fn push(&mut self, token: &str) {
+		if self.line_len > 0 && self.line_len + token.len() + 1 > 70 {
+			self.out.push(b'\n');
+			self.line_len = 0;
+		}
+		if self.line_len > 0 {
+			self.out.push(b' ');
+			self.line_len += 1;
+		}
+		self.out.extend(token.as_bytes());
+		self.line_len += token.len();
+	}
+	
+	fn end_line(&mut self) {
+		if self.line_len > 0 {
+			self.out.push(b'\n');
+			self.line_len = 0;
+		}
+	}
+}
+
+pub fn write_pnm<W: Write>(texture: &Texture, w: &mut W, format: NetpbmFormat) -> RenderResult<()> {
+	match format {
+		NetpbmFormat::BitmapAscii | NetpbmFormat::Bitmap => 
+			writeln!(w, "{}\n{} {}", format.magic(), texture.width, texture.height)?,
+		_ => writeln!(w, "{}\n{} {}\n255", format.magic(), texture.width, texture.height)?,
+	}
+	
+	let width: usize = texture.width as usize;
+	let mut data: Vec<u8> = Vec::with_capacity(texture.buffer.len() * 3);
+	let mut lines: AsciiLines = AsciiLines { out: Vec::new(), line_len: 0 };
+	
+	for row in texture.buffer.chunks(width.max(1)) {
+		match format {
+			NetpbmFormat::BitmapAscii => {
+				for color in row {
+					lines.push(if luma(color) < 128.0 { "1" } else { "0" });
+				}
+				lines.end_line();
+			},
+			NetpbmFormat::GraymapAscii => {
+				for color in row {
+					lines.push(&(luma(color) as u8).to_string());
+				}
+				lines.end_line();
+			},
+			NetpbmFormat::PixmapAscii => {
+				for color in row {
+					lines.push(&(color.r as u8).to_string());
+					lines.push(&(color.g as u8).to_string());
+					lines.push(&(color.b as u8).to_string());
+				}
+				lines.end_line();
+			},
+			NetpbmFormat::Bitmap => {
+				for bits in row.chunks(8) {
+					let mut byte: u8 = 0;
+					for (i, color) in bits.iter().enumerate() {
+						if luma(color) < 128.0 { byte |= 0x80 >> i; }
+					}
+					data.push(byte);
+				}
+			},
+			NetpbmFormat::Graymap => {
+				for color in row {
+					data.push(luma(color) as u8);
+				}
+			},
+			NetpbmFormat::Pixmap => {
+				for color in row {
+					data.extend(&[color.r as u8, color.g as u8, color.b as u8]);
+				}
+			},
+		}
+	}
+	
+	w.write_all(&data)?;
+	w.write_all(&lines.out)?;
+	Ok(())
+}
+
+//raw float map, data holds channels (1 or 3) floats per pixel with the top row first
+pub fn write_pfm_data<W: Write>(w: &mut W, width: u32, height: u32, channels: usize, data: &[f32]) -> RenderResult<()> {
+	if channels != 1 && channels != 3 {
+		return Err(RenderError::InvalidImage(format!("pfm with {} channels not supported", channels)));
+	}
+	if data.len() != width as usize * height as usize * channels {
+		return Err(RenderError::InvalidImage(format!("expected {} values, got {}", width as usize * height as usize * channels, data.len())));
+	}
+	
+	//negative scale marks little endian data
+	writeln!(w, "{}\n{} {}\n-1.0", if channels == 3 { "PF" } else { "Pf" }, width, height)?;
+	
+	let stride: usize = width as usize * channels;
+	let mut bytes: Vec<u8> = Vec::with_capacity(data.len() * 4);
+	for y in (0..height as usize).rev() {
+		for value in &data[y * stride..(y + 1) * stride] {
+			let bits: u32 = value.to_bits();
+			bytes.extend(&[bits as u8, (bits >> 8) as u8, (bits >> 16) as u8, (bits >> 24) as u8]);
+		}
+	}
+	w.write_all(&bytes)?;
+	Ok(())
+}
+
+pub fn write_pfm<W: Write>(texture: &Texture, w: &mut W) -> RenderResult<()> {
+	let mut data: Vec<f32> = Vec::with_capacity(texture.buffer.len() * 3);
+	for color in &texture.buffer {
+		data.push(color.r / 255.0);
+		data.push(color.g / 255.0);
+		data.push(color.b / 255.0);
+	}
+	write_pfm_data(w, texture.width, texture.height, 3, &data)
+}
+
+struct HeaderReader<'a> {
+	data: &'a [u8],
+	pos: usize,
+}
+
+impl<'a> HeaderReader<'a> {
+	fn skip_whitespace_and_comments(&mut self) {
+		while self.pos < self.data.len() {
+			match self.data[self.pos] {
+				b'#' => {
+					while self.pos < self.data.len() && self.data[self.pos] != b'\n' { self.pos += 1; }
+				},
+				b' ' | b'\t' | b'\n' | b'\r' => self.pos += 1,
+				_ => break,
+			}
+		}
+	}
+	
+	fn token(&mut self) -> RenderResult<&'a str> {
+		self.skip_whitespace_and_comments();
+		let start: usize = self.pos;
+		while self.pos < self.data.len() && !(self.data[self.pos] as char).is_whitespace() && self.data[self.pos] != b'#' {
+			self.pos += 1;
+		}
+		if start == self.pos {
+			return Err(RenderError::InvalidImage("unexpected end of netpbm header".to_string()));
+		}
+		match ::std::str::from_utf8(&self.data[start..self.pos]) {
+			Ok(token) => Ok(token),
+			Err(_) => Err(RenderError::InvalidImage("invalid netpbm header".to_string())),
+		}
+	}
+	
+	fn number<T: ::std::str::FromStr>(&mut self) -> RenderResult<T> {
+		let token: &str = self.token()?;
+		match token.parse::<T>() {
+			Ok(value) => Ok(value),
+			Err(_) => Err(RenderError::InvalidImage(format!("invalid number {} in netpbm file", token))),
+		}
+	}
+	
+	//a single whitespace separates the header from binary data
+	fn binary_data(&self) -> &'a [u8] {
+		&self.data[(self.pos + 1).min(self.data.len())..]
+	}
+}
+
+fn unexpected_end() -> RenderError {
+	RenderError::InvalidImage("unexpected end of netpbm data".to_string())
+}
+
+//checks the header size against the bytes left before the texture is allocated, bytes_per_pixel is the minimum a pixel needs
+fn check_size(width: u32, height: u32, bytes_per_pixel: usize, available: usize) -> RenderResult<()> {
+	let needed: Option<usize> = width.checked_mul(height).and_then(|pixels| (pixels as usize).checked_mul(bytes_per_pixel));
+	match needed {
+		Some(needed) if needed <= available => Ok(()),
+		Some(_) => Err(unexpected_end()),
+		None => Err(RenderError::InvalidImage(format!("netpbm size {}x{} is too large", width, height))),
+	}
+}
+
+//reads every netpbm format P1 - P6 as well as PF and Pf
+pub fn read_pnm<R: Read>(r: &mut R) -> RenderResult<Texture> {
+	let mut data: Vec<u8> = Vec::new();
+	r.read_to_end(&mut data)?;
+	let mut header: HeaderReader = HeaderReader { data: &data, pos: 0 };
+	
+	let magic: &str = header.token()?;
+	if magic == "PF" || magic == "Pf" {
+		return read_pfm_body(&mut header, magic == "PF");
+	}
+	
+	let width: u32 = header.number()?;
+	let height: u32 = header.number()?;
+	let maxval: u32 = if magic == "P1" || magic == "P4" { 1 } else { header.number()? };
+	if maxval == 0 || maxval > 65535 {
+		return Err(RenderError::InvalidImage(format!("invalid netpbm maxval {}", maxval)));
+	}
+	
+	//ascii samples take at least one digit, binary ones their full size
+	let sample_bytes: usize = if maxval > 255 { 2 } else { 1 };
+	match magic {
+		"P1" | "P2" => check_size(width, height, 1, data.len() - header.pos)?,
+		"P3" => check_size(width, height, 3, data.len() - header.pos)?,
+		"P4" => {
+			check_size(width, height, 0, 0)?;
+			check_size(((width as u64 + 7) / 8) as u32, height, 1, header.binary_data().len())?;
+		},
+		"P5" => check_size(width, height, sample_bytes, header.binary_data().len())?,
+		"P6" => check_size(width, height, 3 * sample_bytes, header.binary_data().len())?,
+		_ => return Err(RenderError::InvalidImage(format!("unknown netpbm format {}", magic))),
+	}
+	
+	let mut texture: Texture = Texture::new(width, height);
+	let scale: f32 = 255.0 / maxval as f32;
+	let pixels: usize = texture.buffer.len();
+	
+	match magic {
+		"P1" => {
+			//bitmap digits do not need to be separated
+			let mut cnt: usize = 0;
+			header.skip_whitespace_and_comments();
+			while cnt < pixels {
+				header.skip_whitespace_and_comments();
+				match header.data.get(header.pos) {
+					Some(&b'0') => texture.buffer[cnt].set_all(255.0),
+					Some(&b'1') => texture.buffer[cnt].set_all(0.0),
+					_ => return Err(unexpected_end()),
+				}
+				header.pos += 1;
+				cnt += 1;
+			}
+		},
+		"P2" | "P3" => {
+			for color in texture.buffer.iter_mut() {
+				if magic == "P2" {
+					color.set_all(header.number::<u32>()? as f32 * scale);
+				} else {
+					let r: f32 = header.number::<u32>()? as f32;
+					let g: f32 = header.number::<u32>()? as f32;
+					let b: f32 = header.number::<u32>()? as f32;
+					color.set_from_rgb(r * scale, g * scale, b * scale);
+				}
+			}
+		},
+		"P4" => {
+			let body: &[u8] = header.binary_data();
+			let row_bytes: usize = (width as usize + 7) / 8;
+			if body.len() < row_bytes * height as usize {
+				return Err(unexpected_end());
+			}
+			for y in 0..height as usize {
+				for x in 0..width as usize {
+					let bit: u8 = body[y * row_bytes + x / 8] & (0x80 >> (x % 8));
+					texture.buffer[y * width as usize + x].set_all(if bit != 0 { 0.0 } else { 255.0 });
+				}
+			}
+		},
+		"P5" | "P6" => {
+			let body: &[u8] = header.binary_data();
+			let channels: usize = if magic == "P5" { 1 } else { 3 };
+			if body.len() < pixels * channels * sample_bytes {
+				return Err(unexpected_end());
+			}
+			let sample = |i: usize| -> f32 {
+				if sample_bytes == 2 {
+					(((body[i * 2] as u32) << 8) | body[i * 2 + 1] as u32) as f32 * scale
+				} else {
+					body[i] as f32 * scale
+				}
+			};
+			for (i, color) in texture.buffer.iter_mut().enumerate() {
+				if channels == 1 {
+					color.set_all(sample(i));
+				} else {
+					color.set_from_rgb(sample(i * 3), sample(i * 3 + 1), sample(i * 3 + 2));
+				}
+			}
+		},
+		_ => return Err(RenderError::InvalidImage(format!("unknown netpbm format {}", magic))),
+	}
+	
+	Ok(texture)
+}
+
+fn read_pfm_body(header: &mut HeaderReader, color: bool) -> RenderResult<Texture> {
+	let width: u32 = header.number()?;
+	let height: u32 = header.number()?;
+	let scale: f32 = header.number()?;
+	let little_endian: bool = scale < 0.0;
+	let channels: usize = if color { 3 } else { 1 };
+	
+	let body: &[u8] = header.binary_data();
+	check_size(width, height, channels * 4, body.len())?;
+	let stride: usize = width as usize * channels;
+	
+	let mut texture: Texture = Texture::new(width, height);
+	let value = |i: usize| -> f32 {
+		let b: &[u8] = &body[i * 4..i * 4 + 4];
+		let bits: u32 = if little_endian {
+			(b[0] as u32) | ((b[1] as u32) << 8) | ((b[2] as u32) << 16) | ((b[3] as u32) << 24)
+		} else {
+			((b[0] as u32) << 24) | ((b[1] as u32) << 16) | ((b[2] as u32) << 8) | (b[3] as u32)
+		};
+		f32::from_bits(bits) * 255.0
+	};
+	
+	//rows are stored from bottom to top
+	for y in 0..height as usize {
+		let row: usize = (height as usize - 1 - y) * stride;
+		for x in 0..width as usize {
+			let i: usize = row + x * channels;
+			let px = &mut texture.buffer[y * width as usize + x];
+			if color {
+				px.set_from_rgb(value(i), value(i + 1), value(i + 2));
+			} else {
+				px.set_all(value(i));
+			}
+		}
+	}
+	
+	Ok(texture)
+}
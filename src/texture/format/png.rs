@@ -0,0 +1,273 @@
+/*
+	PNG reader and writer. Writes 8 bit RGB or RGBA (if the texture has alpha), reads
+	non interlaced gray, gray alpha, RGB, RGBA and palette images with 8 or 16 bit samples.
+*/
+use std::io::{Read, Write};
+use texture::*;
+use texture::format::zlib::*;
+use error::*;
+
+static SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+
+//16384 x 16384, larger images are rejected before anything is allocated
+const MAX_PIXELS: usize = 1 << 28;
+
+pub fn crc32(data: &[u8]) -> u32 {
+	crc32_update(0xffffffff, data) ^ 0xffffffff
+}
+
+fn crc32_update(mut crc: u32, data: &[u8]) -> u32 {
+	for byte in data {
+		crc ^= *byte as u32;
+		for _i in 0..8 {
+			crc = if crc & 1 != 0 { 0xedb88320 ^ (crc >> 1) } else { crc >> 1 };
+		}
+	}
+	crc
+}
+
+fn write_u32(out: &mut Vec<u8>, value: u32) {
+	out.extend(&[(value >> 24) as u8, (value >> 16) as u8, (value >> 8) as u8, value as u8]);
+}
+
+fn read_u32(data: &[u8]) -> u32 {
+	((data[0] as u32) << 24) | ((data[1] as u32) << 16) | ((data[2] as u32) << 8) | data[3] as u32
+}
+
+fn write_chunk<W: Write>(w: &mut W, chunk_type: &[u8; 4], data: &[u8]) -> RenderResult<()> {
+	let mut header: Vec<u8> = Vec::with_capacity(8);
+	write_u32(&mut header, data.len() as u32);
+	header.extend(chunk_type);
+	let crc: u32 = crc32_update(crc32_update(0xffffffff, chunk_type), data) ^ 0xffffffff;
+	let mut crc_bytes: Vec<u8> = Vec::with_capacity(4);
+	write_u32(&mut crc_bytes, crc);
+	
+	w.write_all(&header)?;
+	w.write_all(data)?;
+	w.write_all(&crc_bytes)?;
+	Ok(())
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+	let p: i16 = a as i16 + b as i16 - c as i16;
+	let pa: i16 = (p - a as i16).abs();
+	let pb: i16 = (p - b as i16).abs();
+	let pc: i16 = (p - c as i16).abs();
+	if pa <= pb && pa <= pc { a } else if pb <= pc { b } else { c }
+}
+
+fn filter_row(filter: u8, row: &[u8], prior: &[u8], bpp: usize, out: &mut Vec<u8>) {
+	for i in 0..row.len() {
+		let a: u8 = if i >= bpp { row[i - bpp] } else { 0 };
+		let b: u8 = prior[i];
+		let c: u8 = if i >= bpp { prior[i - bpp] } else { 0 };
+		out.push(match filter {
+			0 => row[i],
+			1 => row[i].wrapping_sub(a),
+			2 => row[i].wrapping_sub(b),
+			3 => row[i].wrapping_sub(((a as u16 + b as u16) >> 1) as u8),
+			_ => row[i].wrapping_sub(paeth(a, b, c)),
+		});
+	}
+}
+
+fn unfilter_row(filter: u8, row: &mut [u8], prior: &[u8], bpp: usize) -> RenderResult<()> {
+	for i in 0..row.len() {
+		let a: u8 = if i >= bpp { row[i - bpp] } else { 0 };
+		let b: u8 = prior[i];
+		let c: u8 = if i >= bpp { prior[i - bpp] } else { 0 };
+		row[i] = match filter {
+			0 => row[i],
+			1 => row[i].wrapping_add(a),
+			2 => row[i].wrapping_add(b),
+			3 => row[i].wrapping_add(((a as u16 + b as u16) >> 1) as u8),
+			4 => row[i].wrapping_add(paeth(a, b, c)),
+			_ => return Err(RenderError::InvalidImage(format!("invalid png filter type {}", filter))),
+		};
+	}
+	Ok(())
+}
+
+pub fn write_png<W: Write>(texture: &Texture, w: &mut W) -> RenderResult<()> {
+	let channels: usize = if texture.alpha.is_some() { 4 } else { 3 };
+	let width: usize = texture.width as usize;
+	
+	let mut ihdr: Vec<u8> = Vec::with_capacity(13);
+	write_u32(&mut ihdr, texture.width);
+	write_u32(&mut ihdr, texture.height);
+	ihdr.extend(&[8, if channels == 4 { 6 } else { 2 }, 0, 0, 0]);
+	
+	let mut raw: Vec<u8> = Vec::with_capacity((width * channels + 1) * texture.height as usize);
+	let mut prior: Vec<u8> = vec![0; width * channels];
+	let mut row: Vec<u8> = Vec::with_capacity(width * channels);
+	let mut filtered: Vec<u8> = Vec::with_capacity(width * channels);
+	let mut best: Vec<u8> = Vec::with_capacity(width * channels);
+	
+	for y in 0..texture.height as usize {
+		row.clear();
+		for x in 0..width {
+			let i: usize = y * width + x;
+			let color = &texture.buffer[i];
+			row.extend(&[color.r as u8, color.g as u8, color.b as u8]);
+			if let Some(ref alpha) = texture.alpha {
+				row.push(alpha[i] as u8);
+			}
+		}
+		
+		//common heuristic: use the filter with the smallest sum of absolute differences
+		let mut best_filter: u8 = 0;
+		let mut best_sum: u64 = u64::max_value();
+		for filter in 0..5 {
+			filtered.clear();
+			filter_row(filter, &row, &prior, channels, &mut filtered);
+			let sum: u64 = filtered.iter().map(|v| (*v as i8 as i16).abs() as u64).sum();
+			if sum < best_sum {
+				best_sum = sum;
+				best_filter = filter;
+				best.clear();
+				best.extend(&filtered);
+			}
+		}
+		
+		raw.push(best_filter);
+		raw.extend(&best);
+		prior.clear();
+		prior.extend(&row);
+	}
+	
+	w.write_all(&SIGNATURE)?;
+	write_chunk(w, b"IHDR", &ihdr)?;
+	write_chunk(w, b"IDAT", &zlib_compress(&raw))?;
+	write_chunk(w, b"IEND", &[])?;
+	Ok(())
+}
+
+pub fn read_png<R: Read>(r: &mut R) -> RenderResult<Texture> {
+	let mut data: Vec<u8> = Vec::new();
+	r.read_to_end(&mut data)?;
+	
+	if data.len() < 8 || data[..8] != SIGNATURE {
+		return Err(RenderError::InvalidImage("missing png signature".to_string()));
+	}
+	
+	let mut pos: usize = 8;
+	let mut header: Option<(u32, u32, u8, u8)> = None;
+	let mut palette: Vec<u8> = Vec::new();
+	let mut transparency: Vec<u8> = Vec::new();
+	let mut idat: Vec<u8> = Vec::new();
+	
+	loop {
+		if pos + 12 > data.len() {
+			return Err(RenderError::InvalidImage("unexpected end of png".to_string()));
+		}
+		let len: usize = read_u32(&data[pos..]) as usize;
+		if pos + 12 + len > data.len() {
+			return Err(RenderError::InvalidImage("unexpected end of png".to_string()));
+		}
+		let chunk_type: &[u8] = &data[pos + 4..pos + 8];
+		let chunk: &[u8] = &data[pos + 8..pos + 8 + len];
+		if crc32(&data[pos + 4..pos + 8 + len]) != read_u32(&data[pos + 8 + len..]) {
+			return Err(RenderError::InvalidImage("png chunk checksum mismatch".to_string()));
+		}
+		pos += 12 + len;
+		
+		match chunk_type {
+			b"IHDR" => {
+				if len != 13 {
+					return Err(RenderError::InvalidImage("invalid png header".to_string()));
+				}
+				if chunk[10] != 0 || chunk[11] != 0 {
+					return Err(RenderError::InvalidImage("unknown png compression or filter method".to_string()));
+				}
+				if chunk[12] != 0 {
+					return Err(RenderError::InvalidImage("interlaced png not supported".to_string()));
+				}
+				header = Some((read_u32(chunk), read_u32(&chunk[4..]), chunk[8], chunk[9]));
+			},
+			b"PLTE" => palette.extend_from_slice(chunk),
+			b"tRNS" => transparency.extend_from_slice(chunk),
+			b"IDAT" => idat.extend_from_slice(chunk),
+			b"IEND" => break,
+			_ => {},
+		}
+	}
+	
+	let (width, height, bit_depth, color_type) = match header {
+		Some(header) => header,
+		None => return Err(RenderError::InvalidImage("missing png header".to_string())),
+	};
+	
+	let channels: usize = match color_type {
+		0 => 1,
+		2 => 3,
+		3 => 1,
+		4 => 2,
+		6 => 4,
+		_ => return Err(RenderError::InvalidImage(format!("invalid png color type {}", color_type))),
+	};
+	if !(bit_depth == 8 || (bit_depth == 16 && color_type != 3)) {
+		return Err(RenderError::InvalidImage(format!("png bit depth {} not supported", bit_depth)));
+	}
+	
+	let sample_bytes: usize = bit_depth as usize / 8;
+	let bpp: usize = channels * sample_bytes;
+	let pixels: Option<usize> = (width as usize).checked_mul(height as usize).filter(|pixels| *pixels <= MAX_PIXELS);
+	let (stride, expected): (usize, usize) = match pixels {
+		Some(_) => {
+			let stride: usize = width as usize * bpp;
+			(stride, (stride + 1) * height as usize)
+		},
+		None => return Err(RenderError::InvalidImage(format!("png size {}x{} is too large", width, height))),
+	};
+	let mut raw: Vec<u8> = zlib_decompress(&idat, expected)?;
+	if raw.len() < expected {
+		return Err(RenderError::InvalidImage("png image data too short".to_string()));
+	}
+	
+	let mut texture: Texture = Texture::new(width, height);
+	let has_alpha: bool = color_type == 4 || color_type == 6 || (color_type == 3 && !transparency.is_empty());
+	let mut alpha: Vec<f32> = Vec::with_capacity(if has_alpha { texture.buffer.len() } else { 0 });
+	
+	let mut prior: Vec<u8> = vec![0; stride];
+	for y in 0..height as usize {
+		let start: usize = y * (stride + 1);
+		let filter: u8 = raw[start];
+		{
+			let row: &mut [u8] = &mut raw[start + 1..start + 1 + stride];
+			unfilter_row(filter, row, &prior, bpp)?;
+			prior.clear();
+			prior.extend_from_slice(row);
+		}
+		
+		for x in 0..width as usize {
+			//for 16 bit samples only the most significant byte is used
+			let sample = |c: usize| prior[x * bpp + c * sample_bytes];
+			let color = &mut texture.buffer[y * width as usize + x];
+			match color_type {
+				0 | 4 => {
+					color.set_all(sample(0) as f32);
+					if color_type == 4 { alpha.push(sample(1) as f32); }
+				},
+				3 => {
+					let index: usize = sample(0) as usize;
+					if index * 3 + 2 >= palette.len() {
+						return Err(RenderError::InvalidImage("png palette index out of range".to_string()));
+					}
+					color.set_from_rgb(palette[index * 3] as f32, palette[index * 3 + 1] as f32, palette[index * 3 + 2] as f32);
+					if has_alpha {
+						alpha.push(*transparency.get(index).unwrap_or(&255) as f32);
+					}
+				},
+				_ => {
+					color.set_from_rgb(sample(0) as f32, sample(1) as f32, sample(2) as f32);
+					if color_type == 6 { alpha.push(sample(3) as f32); }
+				},
+			}
+		}
+	}
+	
+	if has_alpha {
+		texture.alpha = Some(alpha);
+	}
+	Ok(texture)
+}
@@ -0,0 +1,225 @@
+/*
+	TGA reader and writer. Writes 24 bit or 32 bit (if the texture has alpha) true color images,
+	optionally run length encoded. Reads uncompressed and run length encoded true color (15, 16, 24, 32 bit),
+	gray (8 bit, 16 bit with alpha) and color mapped images.
+*/
+use std::io::{Read, Write};
+use texture::*;
+use error::*;
+
+fn read_u16(data: &[u8], pos: usize) -> u16 {
+	(data[pos] as u16) | ((data[pos + 1] as u16) << 8)
+}
+
+fn invalid(message: &str) -> RenderError {
+	RenderError::InvalidImage(format!("tga: {}", message))
+}
+
+fn pixel_bytes(texture: &Texture, i: usize, out: &mut Vec<u8>) {
+	let color: &Color = &texture.buffer[i];
+	out.extend(&[color.b as u8, color.g as u8, color.r as u8]);
+	if let Some(ref alpha) = texture.alpha {
+		out.push(alpha[i] as u8);
+	}
+}
+
+pub fn write_tga<W: Write>(texture: &Texture, w: &mut W, rle: bool) -> RenderResult<()> {
+	if texture.width > 0xffff || texture.height > 0xffff {
+		return Err(invalid("image too large"));
+	}
+	let with_alpha: bool = texture.alpha.is_some();
+	let bpp: usize = if with_alpha { 4 } else { 3 };
+	
+	let mut out: Vec<u8> = vec![0, 0, if rle { 10 } else { 2 }, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+	out.extend(&[texture.width as u8, (texture.width >> 8) as u8, texture.height as u8, (texture.height >> 8) as u8]);
+	out.push((bpp * 8) as u8);
+	//top left origin and the count of alpha bits
+	out.push(0x20 | if with_alpha { 8 } else { 0 });
+	
+	let width: usize = texture.width as usize;
+	let mut pixel: Vec<u8> = Vec::with_capacity(bpp);
+	let mut next: Vec<u8> = Vec::with_capacity(bpp);
+	for y in 0..texture.height as usize {
+		let row: usize = y * width;
+		if !rle {
+			for x in 0..width {
+				pixel_bytes(texture, row + x, &mut out);
+			}
+			continue;
+		}
+		
+		//packets do not cross scanlines
+		let mut x: usize = 0;
+		while x < width {
+			pixel.clear();
+			pixel_bytes(texture, row + x, &mut pixel);
+			let mut run: usize = 1;
+			while x + run < width && run < 128 {
+				next.clear();
+				pixel_bytes(texture, row + x + run, &mut next);
+				if next != pixel { break; }
+				run += 1;
+			}
+			
+			if run > 1 {
+				out.push(0x80 | (run - 1) as u8);
+				out.extend(&pixel);
+				x += run;
+				continue;
+			}
+			
+			//raw packet until the next run of at least two equal pixels
+			let start: usize = x;
+			let mut count: usize = 0;
+			while x < width && count < 128 {
+				pixel.clear();
+				pixel_bytes(texture, row + x, &mut pixel);
+				if x + 1 < width {
+					next.clear();
+					pixel_bytes(texture, row + x + 1, &mut next);
+					if next == pixel && count > 0 { break; }
+				}
+				x += 1;
+				count += 1;
+			}
+			out.push((count - 1) as u8);
+			for i in start..start + count {
+				pixel_bytes(texture, row + i, &mut out);
+			}
+		}
+	}
+	
+	//TGA 2.0 footer without extension and developer area
+	out.extend(&[0; 8]);
+	out.extend(b"TRUEVISION-XFILE.\0");
+	
+	w.write_all(&out)?;
+	Ok(())
+}
+
+//decodes one pixel of the given depth to rgba, 5 bit channels are scaled to 0 - 255
+fn decode_pixel(p: &[u8], depth: u8, gray: bool, rgba: &mut [f32; 4]) {
+	match (gray, depth) {
+		(true, 8) => *rgba = [p[0] as f32, p[0] as f32, p[0] as f32, 255.0],
+		(true, _) => *rgba = [p[0] as f32, p[0] as f32, p[0] as f32, p[1] as f32],
+		(false, 15) | (false, 16) => {
+			let v: u16 = read_u16(p, 0);
+			let scale = |c: u16| -> f32 { (c & 0x1f) as f32 * 255.0 / 31.0 };
+			*rgba = [scale(v >> 10), scale(v >> 5), scale(v), if depth == 16 && v & 0x8000 == 0 { 0.0 } else { 255.0 }];
+		},
+		(false, 24) => *rgba = [p[2] as f32, p[1] as f32, p[0] as f32, 255.0],
+		_ => *rgba = [p[2] as f32, p[1] as f32, p[0] as f32, p[3] as f32],
+	}
+}
+
+pub fn read_tga<R: Read>(r: &mut R) -> RenderResult<Texture> {
+	let mut data: Vec<u8> = Vec::new();
+	r.read_to_end(&mut data)?;
+	if data.len() < 18 {
+		return Err(invalid("truncated header"));
+	}
+	
+	let id_length: usize = data[0] as usize;
+	let colormap_type: u8 = data[1];
+	let image_type: u8 = data[2];
+	let colormap_first: usize = read_u16(&data, 3) as usize;
+	let colormap_length: usize = read_u16(&data, 5) as usize;
+	let colormap_depth: u8 = data[7];
+	let width: u32 = read_u16(&data, 12) as u32;
+	let height: u32 = read_u16(&data, 14) as u32;
+	let depth: u8 = data[16];
+	let descriptor: u8 = data[17];
+	let alpha_bits: u8 = descriptor & 0x0f;
+	
+	let rle: bool = image_type & 8 != 0;
+	let mapped: bool = image_type & 7 == 1;
+	let gray: bool = image_type & 7 == 3;
+	match (image_type & 7, depth) {
+		(1, 8) | (2, 15) | (2, 16) | (2, 24) | (2, 32) | (3, 8) | (3, 16) => {},
+		_ => return Err(invalid(&format!("image type {} with {} bit not supported", image_type, depth))),
+	}
+	if mapped && (colormap_type != 1 || ![15, 16, 24, 32].contains(&colormap_depth)) {
+		return Err(invalid("invalid color map"));
+	}
+	
+	let mut pos: usize = 18 + id_length;
+	let mut colormap: Vec<[f32; 4]> = Vec::new();
+	if colormap_type == 1 {
+		let entry_bytes: usize = (colormap_depth as usize + 7) / 8;
+		if data.len() < pos + colormap_length * entry_bytes {
+			return Err(invalid("truncated color map"));
+		}
+		for i in 0..colormap_length {
+			let mut rgba: [f32; 4] = [0.0; 4];
+			decode_pixel(&data[pos + i * entry_bytes..], colormap_depth, false, &mut rgba);
+			colormap.push(rgba);
+		}
+		pos += colormap_length * entry_bytes;
+	}
+	
+	//unpack rle packets to a plain pixel stream
+	let bytes_per_pixel: usize = (depth as usize + 7) / 8;
+	let pixels: usize = width as usize * height as usize;
+	let raw: Vec<u8> = if rle {
+		let mut raw: Vec<u8> = Vec::with_capacity(pixels * bytes_per_pixel);
+		while raw.len() < pixels * bytes_per_pixel {
+			if pos >= data.len() {
+				return Err(invalid("truncated rle data"));
+			}
+			let header: u8 = data[pos];
+			let count: usize = (header & 0x7f) as usize + 1;
+			pos += 1;
+			let size: usize = if header & 0x80 != 0 { bytes_per_pixel } else { count * bytes_per_pixel };
+			if data.len() < pos + size {
+				return Err(invalid("truncated rle data"));
+			}
+			if header & 0x80 != 0 {
+				for _i in 0..count {
+					raw.extend(&data[pos..pos + size]);
+				}
+			} else {
+				raw.extend(&data[pos..pos + size]);
+			}
+			pos += size;
+		}
+		raw.truncate(pixels * bytes_per_pixel);
+		raw
+	} else {
+		if data.len() < pos + pixels * bytes_per_pixel {
+			return Err(invalid("truncated pixel data"));
+		}
+		data[pos..pos + pixels * bytes_per_pixel].to_vec()
+	};
+	
+	let mut texture: Texture = Texture::new(width, height);
+	let mut alpha: Vec<f32> = vec![255.0; pixels];
+	let top_down: bool = descriptor & 0x20 != 0;
+	let right_to_left: bool = descriptor & 0x10 != 0;
+	let mut rgba: [f32; 4] = [0.0; 4];
+	
+	for row in 0..height as usize {
+		let y: usize = if top_down { row } else { height as usize - 1 - row };
+		for col in 0..width as usize {
+			let x: usize = if right_to_left { width as usize - 1 - col } else { col };
+			let p: &[u8] = &raw[(row * width as usize + col) * bytes_per_pixel..];
+			if mapped {
+				match colormap.get((p[0] as usize).wrapping_sub(colormap_first)) {
+					Some(entry) => rgba = *entry,
+					None => return Err(invalid("color map index out of range")),
+				}
+			} else {
+				decode_pixel(p, depth, gray, &mut rgba);
+			}
+			let i: usize = y * width as usize + x;
+			texture.buffer[i].set_from_rgb(rgba[0], rgba[1], rgba[2]);
+			alpha[i] = rgba[3];
+		}
+	}
+	
+	//only trust alpha the descriptor announces, except for color maps with alpha entries
+	let has_alpha: bool = if mapped { colormap_depth == 32 || (colormap_depth == 16 && alpha_bits > 0) } else { alpha_bits > 0 };
+	if has_alpha {
+		texture.alpha = Some(alpha);
+	}
+	Ok(texture)
+}
@@ -0,0 +1,91 @@
+/*
+	YUV4MPEG2 writer, uncompressed 8 bit 4:2:0 video that common players open directly.
+	Colors are converted with BT.601 to the limited range (Y 16 - 235), chroma is the average of 2x2 pixels
+	with the centered siting of C420jpeg. Odd sizes repeat the last column and row for the chroma planes.
+*/
+use std::io::Write;
+use texture::*;
+use color::*;
+use error::*;
+
+pub struct Y4mWriter<W: Write> {
+	out: W,
+	width: u32,
+	height: u32,
+	pub frames: u32, //frames written so far
+}
+
+fn clamp(value: f32) -> f32 {
+	value.max(0.0).min(255.0)
+}
+
+//y, cb and cr of a color with 0 - 255 channels
+pub fn rgb_to_yuv(r: f32, g: f32, b: f32) -> (u8, u8, u8) {
+	let (r, g, b) = (clamp(r), clamp(g), clamp(b));
+	let y: f32 = 16.0 + (65.481 * r + 128.553 * g + 24.966 * b) / 255.0;
+	let cb: f32 = 128.0 + (-37.797 * r - 74.203 * g + 112.0 * b) / 255.0;
+	let cr: f32 = 128.0 + (112.0 * r - 93.786 * g - 18.214 * b) / 255.0;
+	(y.round() as u8, cb.round() as u8, cr.round() as u8)
+}
+
+//the planes y, u and v of a frame, u and v have half the size rounded up
+pub fn texture_to_yuv420(texture: &Texture) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+	let (width, height) = (texture.width as usize, texture.height as usize);
+	let (chroma_width, chroma_height) = ((width + 1) / 2, (height + 1) / 2);
+	let mut y: Vec<u8> = Vec::with_capacity(width * height);
+	let mut u: Vec<u8> = Vec::with_capacity(chroma_width * chroma_height);
+	let mut v: Vec<u8> = Vec::with_capacity(chroma_width * chroma_height);
+	for color in &texture.buffer {
+		y.push(rgb_to_yuv(color.r, color.g, color.b).0);
+	}
+	for j in 0..chroma_height {
+		for i in 0..chroma_width {
+			let (mut r, mut g, mut b): (f32, f32, f32) = (0.0, 0.0, 0.0);
+			for &(dx, dy) in &[(0, 0), (1, 0), (0, 1), (1, 1)] {
+				let x: usize = (2 * i + dx).min(width - 1);
+				let y: usize = (2 * j + dy).min(height - 1);
+				let color: &Color = &texture.buffer[y * width + x];
+				r += clamp(color.r);
+				g += clamp(color.g);
+				b += clamp(color.b);
+			}
+			let (_, cb, cr) = rgb_to_yuv(r * 0.25, g * 0.25, b * 0.25);
+			u.push(cb);
+			v.push(cr);
+		}
+	}
+	(y, u, v)
+}
+
+impl<W: Write> Y4mWriter<W> {
+	//writes the stream header, the frame rate is fps_num / fps_den frames per second
+	pub fn new(mut out: W, width: u32, height: u32, fps_num: u32, fps_den: u32) -> RenderResult<Y4mWriter<W>> {
+		if width == 0 || height == 0 || fps_num == 0 || fps_den == 0 {
+			return Err(RenderError::InvalidImage(format!("y4m: invalid size {}x{} or frame rate {}:{}", width, height, fps_num, fps_den)));
+		}
+		writeln!(out, "YUV4MPEG2 W{} H{} F{}:{} Ip A1:1 C420jpeg", width, height, fps_num, fps_den)?;
+		Ok(Y4mWriter { out, width, height, frames: 0 })
+	}
+	
+	pub fn write_frame(&mut self, texture: &Texture) -> RenderResult<()> {
+		if texture.width != self.width || texture.height != self.height {
+			return Err(RenderError::dimension_mismatch(self.width, self.height, texture.width, texture.height));
+		}
+		let (y, u, v) = texture_to_yuv420(texture);
+		self.out.write_all(b"FRAME\n")?;
+		self.out.write_all(&y)?;
+		self.out.write_all(&u)?;
+		self.out.write_all(&v)?;
+		self.frames += 1;
+		Ok(())
+	}
+	
+	pub fn flush(&mut self) -> RenderResult<()> {
+		self.out.flush()?;
+		Ok(())
+	}
+	
+	pub fn into_inner(self) -> W {
+		self.out
+	}
+}
@@ -0,0 +1,400 @@
+/*
+	zlib container (RFC 1950) around deflate (RFC 1951). Compression uses LZ77 with hash
+	chains and the fixed huffman codes, decompression understands all block types.
+*/
+use error::*;
+
+const WINDOW_SIZE: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const MAX_CHAIN: usize = 64;
+const HASH_SIZE: usize = 1 << 15;
+
+static LEN_BASE: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31,
+							   35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+static LEN_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2,
+							   3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+static DIST_BASE: [u16; 30] = [1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193,
+								257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577];
+static DIST_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6,
+								7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+static CL_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+pub fn adler32(data: &[u8]) -> u32 {
+	let mut a: u32 = 1;
+	let mut b: u32 = 0;
+	for chunk in data.chunks(5552) {
+		for byte in chunk {
+			a += *byte as u32;
+			b += a;
+		}
+		a %= 65521;
+		b %= 65521;
+	}
+	(b << 16) | a
+}
+
+pub fn zlib_compress(data: &[u8]) -> Vec<u8> {
+	let mut out: Vec<u8> = vec![0x78, 0x9c];
+	out.extend(deflate(data));
+	let adler: u32 = adler32(data);
+	out.extend(&[(adler >> 24) as u8, (adler >> 16) as u8, (adler >> 8) as u8, adler as u8]);
+	out
+}
+
+//fails instead of producing more than max_len bytes
+pub fn zlib_decompress(data: &[u8], max_len: usize) -> RenderResult<Vec<u8>> {
+	if data.len() < 6 {
+		return Err(RenderError::InvalidImage("zlib stream too short".to_string()));
+	}
+	let (cmf, flg) = (data[0] as u32, data[1] as u32);
+	if cmf & 0x0f != 8 || ((cmf << 8) | flg) % 31 != 0 {
+		return Err(RenderError::InvalidImage("invalid zlib header".to_string()));
+	}
+	if flg & 0x20 != 0 {
+		return Err(RenderError::InvalidImage("zlib preset dictionary not supported".to_string()));
+	}
+	let out: Vec<u8> = inflate(&data[2..], max_len)?;
+	let end: usize = data.len();
+	let adler: u32 = ((data[end - 4] as u32) << 24) | ((data[end - 3] as u32) << 16) |
+					 ((data[end - 2] as u32) << 8) | data[end - 1] as u32;
+	if adler != adler32(&out) {
+		return Err(RenderError::InvalidImage("zlib checksum mismatch".to_string()));
+	}
+	Ok(out)
+}
+
+struct BitWriter {
+	out: Vec<u8>,
+	bitbuf: u32,
+	bitcnt: u32,
+}
+
+impl BitWriter {
+	fn new() -> BitWriter {
+		BitWriter { out: Vec::new(), bitbuf: 0, bitcnt: 0 }
+	}
+	
+	fn write_bits(&mut self, value: u32, cnt: u32) {
+		self.bitbuf |= value << self.bitcnt;
+		self.bitcnt += cnt;
+		while self.bitcnt >= 8 {
+			self.out.push(self.bitbuf as u8);
+			self.bitbuf >>= 8;
+			self.bitcnt -= 8;
+		}
+	}
+	
+	//huffman codes are stored starting with the most significant bit
+	fn write_code(&mut self, code: u32, len: u32) {
+		let mut reversed: u32 = 0;
+		for i in 0..len {
+			reversed |= ((code >> i) & 1) << (len - 1 - i);
+		}
+		self.write_bits(reversed, len);
+	}
+	
+	fn finish(mut self) -> Vec<u8> {
+		if self.bitcnt > 0 {
+			self.out.push(self.bitbuf as u8);
+		}
+		self.out
+	}
+}
+
+fn write_fixed_literal(bw: &mut BitWriter, symbol: u32) {
+	match symbol {
+		0..=143 => bw.write_code(0x30 + symbol, 8),
+		144..=255 => bw.write_code(0x190 + symbol - 144, 9),
+		256..=279 => bw.write_code(symbol - 256, 7),
+		_ => bw.write_code(0xc0 + symbol - 280, 8),
+	}
+}
+
+fn write_match(bw: &mut BitWriter, len: usize, dist: usize) {
+	let mut lc: usize = 0;
+	while lc < 28 && LEN_BASE[lc + 1] as usize <= len { lc += 1; }
+	write_fixed_literal(bw, 257 + lc as u32);
+	bw.write_bits((len - LEN_BASE[lc] as usize) as u32, LEN_EXTRA[lc] as u32);
+	
+	let mut dc: usize = 0;
+	while dc < 29 && DIST_BASE[dc + 1] as usize <= dist { dc += 1; }
+	bw.write_code(dc as u32, 5);
+	bw.write_bits((dist - DIST_BASE[dc] as usize) as u32, DIST_EXTRA[dc] as u32);
+}
+
+fn hash3(data: &[u8], pos: usize) -> usize {
+	(((data[pos] as usize) << 10) ^ ((data[pos + 1] as usize) << 5) ^ data[pos + 2] as usize) & (HASH_SIZE - 1)
+}
+
+//raw deflate stream as one final block with fixed huffman codes
+pub fn deflate(data: &[u8]) -> Vec<u8> {
+	let mut bw: BitWriter = BitWriter::new();
+	bw.write_bits(1, 1); //BFINAL
+	bw.write_bits(1, 2); //BTYPE fixed huffman
+	
+	let mut head: Vec<i64> = vec![-1; HASH_SIZE];
+	let mut prev: Vec<i64> = vec![-1; WINDOW_SIZE];
+	let mut pos: usize = 0;
+	
+	while pos < data.len() {
+		let mut best_len: usize = 0;
+		let mut best_dist: usize = 0;
+		
+		if pos + MIN_MATCH <= data.len() {
+			let h: usize = hash3(data, pos);
+			let mut candidate: i64 = head[h];
+			let mut chain: usize = 0;
+			let max_len: usize = MAX_MATCH.min(data.len() - pos);
+			
+			while candidate >= 0 && chain < MAX_CHAIN {
+				let cpos: usize = candidate as usize;
+				if pos - cpos > WINDOW_SIZE { break; }
+				let mut len: usize = 0;
+				while len < max_len && data[cpos + len] == data[pos + len] { len += 1; }
+				if len > best_len {
+					best_len = len;
+					best_dist = pos - cpos;
+					if len == max_len { break; }
+				}
+				candidate = prev[cpos % WINDOW_SIZE];
+				chain += 1;
+			}
+		}
+		
+		let advance: usize = if best_len >= MIN_MATCH {
+			write_match(&mut bw, best_len, best_dist);
+			best_len
+		} else {
+			write_fixed_literal(&mut bw, data[pos] as u32);
+			1
+		};
+		
+		for p in pos..pos + advance {
+			if p + MIN_MATCH <= data.len() {
+				let h: usize = hash3(data, p);
+				prev[p % WINDOW_SIZE] = head[h];
+				head[h] = p as i64;
+			}
+		}
+		pos += advance;
+	}
+	
+	write_fixed_literal(&mut bw, 256);
+	bw.finish()
+}
+
+struct BitReader<'a> {
+	data: &'a [u8],
+	pos: usize,
+	bitbuf: u32,
+	bitcnt: u32,
+}
+
+impl<'a> BitReader<'a> {
+	fn new(data: &'a [u8]) -> BitReader<'a> {
+		BitReader { data, pos: 0, bitbuf: 0, bitcnt: 0 }
+	}
+	
+	fn bits(&mut self, cnt: u32) -> RenderResult<u32> {
+		while self.bitcnt < cnt {
+			if self.pos >= self.data.len() {
+				return Err(RenderError::InvalidImage("unexpected end of deflate stream".to_string()));
+			}
+			self.bitbuf |= (self.data[self.pos] as u32) << self.bitcnt;
+			self.pos += 1;
+			self.bitcnt += 8;
+		}
+		let value: u32 = self.bitbuf & ((1u32 << cnt) - 1);
+		self.bitbuf >>= cnt;
+		self.bitcnt -= cnt;
+		Ok(value)
+	}
+	
+	fn align(&mut self) {
+		self.bitbuf = 0;
+		self.bitcnt = 0;
+	}
+}
+
+//canonical huffman code given by the count of codes per length and the sorted symbols
+struct Huffman {
+	counts: [u16; 16],
+	symbols: Vec<u16>,
+}
+
+impl Huffman {
+	fn new(lengths: &[u8]) -> Huffman {
+		let mut counts: [u16; 16] = [0; 16];
+		for len in lengths {
+			counts[*len as usize] += 1;
+		}
+		counts[0] = 0;
+		
+		let mut offsets: [u16; 16] = [0; 16];
+		for len in 1..15 {
+			offsets[len + 1] = offsets[len] + counts[len];
+		}
+		
+		let mut symbols: Vec<u16> = vec![0; lengths.len()];
+		for (symbol, len) in lengths.iter().enumerate() {
+			if *len != 0 {
+				symbols[offsets[*len as usize] as usize] = symbol as u16;
+				offsets[*len as usize] += 1;
+			}
+		}
+		Huffman { counts, symbols }
+	}
+	
+	fn decode(&self, br: &mut BitReader) -> RenderResult<u16> {
+		let mut code: i32 = 0;
+		let mut first: i32 = 0;
+		let mut index: i32 = 0;
+		for len in 1..16 {
+			code |= br.bits(1)? as i32;
+			let count: i32 = self.counts[len] as i32;
+			if code - count < first {
+				return Ok(self.symbols[(index + (code - first)) as usize]);
+			}
+			index += count;
+			first += count;
+			first <<= 1;
+			code <<= 1;
+		}
+		Err(RenderError::InvalidImage("invalid huffman code".to_string()))
+	}
+}
+
+fn too_long() -> RenderError {
+	RenderError::InvalidImage("deflate stream longer than expected".to_string())
+}
+
+fn inflate_codes(br: &mut BitReader, out: &mut Vec<u8>, max_len: usize, lit: &Huffman, dist: &Huffman) -> RenderResult<()> {
+	loop {
+		let symbol: usize = lit.decode(br)? as usize;
+		if symbol < 256 {
+			if out.len() >= max_len {
+				return Err(too_long());
+			}
+			out.push(symbol as u8);
+		} else if symbol == 256 {
+			return Ok(());
+		} else {
+			let lc: usize = symbol - 257;
+			if lc >= 29 {
+				return Err(RenderError::InvalidImage("invalid deflate length code".to_string()));
+			}
+			let len: usize = LEN_BASE[lc] as usize + br.bits(LEN_EXTRA[lc] as u32)? as usize;
+			let dc: usize = dist.decode(br)? as usize;
+			if dc >= 30 {
+				return Err(RenderError::InvalidImage("invalid deflate distance code".to_string()));
+			}
+			let d: usize = DIST_BASE[dc] as usize + br.bits(DIST_EXTRA[dc] as u32)? as usize;
+			if d > out.len() {
+				return Err(RenderError::InvalidImage("deflate distance too far back".to_string()));
+			}
+			if len > max_len - out.len() {
+				return Err(too_long());
+			}
+			let start: usize = out.len() - d;
+			for i in 0..len {
+				let byte: u8 = out[start + i];
+				out.push(byte);
+			}
+		}
+	}
+}
+
+fn fixed_tables() -> (Huffman, Huffman) {
+	let mut lengths: Vec<u8> = vec![0; 288];
+	for (symbol, len) in lengths.iter_mut().enumerate() {
+		*len = match symbol {
+			0..=143 => 8,
+			144..=255 => 9,
+			256..=279 => 7,
+			_ => 8,
+		};
+	}
+	(Huffman::new(&lengths), Huffman::new(&[5; 30]))
+}
+
+fn dynamic_tables(br: &mut BitReader) -> RenderResult<(Huffman, Huffman)> {
+	let nlen: usize = br.bits(5)? as usize + 257;
+	let ndist: usize = br.bits(5)? as usize + 1;
+	let ncode: usize = br.bits(4)? as usize + 4;
+	
+	let mut cl_lengths: [u8; 19] = [0; 19];
+	for i in 0..ncode {
+		cl_lengths[CL_ORDER[i]] = br.bits(3)? as u8;
+	}
+	let cl: Huffman = Huffman::new(&cl_lengths);
+	
+	let mut lengths: Vec<u8> = Vec::with_capacity(nlen + ndist);
+	while lengths.len() < nlen + ndist {
+		let symbol: u16 = cl.decode(br)?;
+		let (value, repeat): (u8, u32) = match symbol {
+			0..=15 => (symbol as u8, 1),
+			16 => {
+				let last: u8 = match lengths.last() {
+					Some(last) => *last,
+					None => return Err(RenderError::InvalidImage("deflate repeat without length".to_string())),
+				};
+				(last, 3 + br.bits(2)?)
+			},
+			17 => (0, 3 + br.bits(3)?),
+			_ => (0, 11 + br.bits(7)?),
+		};
+		for _i in 0..repeat {
+			lengths.push(value);
+		}
+	}
+	if lengths.len() > nlen + ndist {
+		return Err(RenderError::InvalidImage("too many deflate code lengths".to_string()));
+	}
+	
+	Ok((Huffman::new(&lengths[..nlen]), Huffman::new(&lengths[nlen..])))
+}
+
+//raw deflate stream, the data behind the last block is ignored
+pub fn inflate(data: &[u8], max_len: usize) -> RenderResult<Vec<u8>> {
+	let mut br: BitReader = BitReader::new(data);
+	let mut out: Vec<u8> = Vec::new();
+	
+	loop {
+		let last: u32 = br.bits(1)?;
+		match br.bits(2)? {
+			0 => {
+				br.align();
+				if br.pos + 4 > data.len() {
+					return Err(RenderError::InvalidImage("unexpected end of deflate stream".to_string()));
+				}
+				let len: usize = data[br.pos] as usize | ((data[br.pos + 1] as usize) << 8);
+				let nlen: usize = data[br.pos + 2] as usize | ((data[br.pos + 3] as usize) << 8);
+				if len != (!nlen & 0xffff) {
+					return Err(RenderError::InvalidImage("invalid stored deflate block".to_string()));
+				}
+				br.pos += 4;
+				if br.pos + len > data.len() {
+					return Err(RenderError::InvalidImage("unexpected end of deflate stream".to_string()));
+				}
+				if len > max_len - out.len() {
+					return Err(too_long());
+				}
+				out.extend_from_slice(&data[br.pos..br.pos + len]);
+				br.pos += len;
+			},
+			1 => {
+				let (lit, dist) = fixed_tables();
+				inflate_codes(&mut br, &mut out, max_len, &lit, &dist)?;
+			},
+			2 => {
+				let (lit, dist) = dynamic_tables(&mut br)?;
+				inflate_codes(&mut br, &mut out, max_len, &lit, &dist)?;
+			},
+			_ => return Err(RenderError::InvalidImage("invalid deflate block type".to_string())),
+		}
+		if last == 1 { break; }
+	}
+	
+	Ok(out)
+}
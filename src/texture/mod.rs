@@ -1,5 +1,6 @@
 pub mod converter;
 pub mod filter;
+pub mod format;
 
 use math::algorithm::fractals::julia::*;
 use math::algorithm::fractals::mandelbrot::*;
@@ -10,17 +11,25 @@ use texture::converter::julia::*;
 use texture::converter::mandelbrot::*;
 
 use texture::filter::{TextureFilter};
+use texture::format::png::*;
+use texture::format::netpbm::*;
+use texture::format::bmp::*;
+use texture::format::tga::*;
+use texture::format::gif::*;
 
-use std::io::Write;                                                                                                                                                                                                                                                                                                                            
 use std::fs::File;    
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
 
 use color::*;
+use error::*;
 
 #[derive(Clone)]
 pub struct Texture {
 	pub width: u32,
 	pub height: u32,
 	pub buffer: Vec<Color>,
+	pub alpha: Option<Vec<f32>>, //same range as the colors, None for opaque textures
 }
 
 impl Texture {
@@ -30,6 +39,7 @@ impl Texture {
 			width: width,
 			height: height,
 			buffer: Vec::with_capacity((width*height) as usize),
+			alpha: None,
 		};
 		
 		for _i in 0..texture.buffer.capacity() {
@@ -38,31 +48,110 @@ impl Texture {
 		texture
 	}
 	
-	pub fn from_noise(&mut self, noise: &Noise) {
-		noise_to_texture(noise, self);
+	pub fn from_noise(&mut self, noise: &Noise) -> RenderResult<()> {
+		noise_to_texture(noise, self)
 	}
 	
-	pub fn from_mandelbrot(&mut self, mandelbrot: &Mandelbrot, mb_color_func: fn(&Mandelbrot, &MandelbrotPoint, &mut Color)) {
-		mandelbrot_to_texture(mandelbrot, self, mb_color_func);
+	pub fn from_mandelbrot(&mut self, mandelbrot: &Mandelbrot, mb_color_func: fn(&Mandelbrot, &MandelbrotPoint, &mut Color)) -> RenderResult<()> {
+		mandelbrot_to_texture(mandelbrot, self, mb_color_func)
 	}
 	
-	pub fn from_julia(&mut self, julia: &Julia, jul_color_func: fn(&Julia, &JuliaPoint, &mut Color) ) {
-		julia_to_texture(julia, self, jul_color_func);
+	pub fn from_julia(&mut self, julia: &Julia, jul_color_func: fn(&Julia, &JuliaPoint, &mut Color) ) -> RenderResult<()> {
+		julia_to_texture(julia, self, jul_color_func)
 	}
 	
 	pub fn filter(&mut self, filter: &TextureFilter) {
 		filter.filter(self);
 	}
-
-	pub fn write_as_ppm(&self, filename: &'static str) {
-		let mut f = File::create(filename).expect("Unable to create file");
-		write!(f, "P6\n{} {}\n255\n", self.width, self.height).expect("Unable to write header");
-		for color in &self.buffer {
-			let col: &[u8] = &[color.r as u8, color.g as u8, color.b as u8];
-			f.write_all(col).expect("Unable to write data");
+	
+	pub fn write_as_ppm<P: AsRef<Path>>(&self, filename: P) -> RenderResult<()> {
+		self.write_as_pnm(filename, NetpbmFormat::Pixmap)
+	}
+	
+	pub fn write_as_pnm<P: AsRef<Path>>(&self, filename: P, format: NetpbmFormat) -> RenderResult<()> {
+		let mut f = BufWriter::new(File::create(filename)?);
+		write_pnm(self, &mut f, format)
+	}
+	
+	pub fn write_as_pfm<P: AsRef<Path>>(&self, filename: P) -> RenderResult<()> {
+		let mut f = BufWriter::new(File::create(filename)?);
+		write_pfm(self, &mut f)
+	}
+	
+	pub fn write_as_png<P: AsRef<Path>>(&self, filename: P) -> RenderResult<()> {
+		let mut f = BufWriter::new(File::create(filename)?);
+		write_png(self, &mut f)
+	}
+	
+	pub fn write_as_bmp<P: AsRef<Path>>(&self, filename: P) -> RenderResult<()> {
+		let mut f = BufWriter::new(File::create(filename)?);
+		write_bmp(self, &mut f)
+	}
+	
+	pub fn write_as_tga<P: AsRef<Path>>(&self, filename: P, rle: bool) -> RenderResult<()> {
+		let mut f = BufWriter::new(File::create(filename)?);
+		write_tga(self, &mut f, rle)
+	}
+	
+	//single frame gif, the colors are reduced to a palette of 256
+	pub fn write_as_gif<P: AsRef<Path>>(&self, filename: P) -> RenderResult<()> {
+		let mut writer = GifWriter::new(BufWriter::new(File::create(filename)?), self.width, self.height, None)?;
+		writer.write_frame(self)?;
+		writer.finish()
+	}
+	
+	//picks the writer by the file extension, tga files are run length encoded
+	pub fn write<P: AsRef<Path>>(&self, filename: P) -> RenderResult<()> {
+		let extension: String = match filename.as_ref().extension() {
+			Some(ext) => ext.to_string_lossy().to_lowercase(),
+			None => String::new(),
+		};
+		match extension.as_str() {
+			"png" => self.write_as_png(filename),
+			"bmp" => self.write_as_bmp(filename),
+			"tga" => self.write_as_tga(filename, true),
+			"gif" => self.write_as_gif(filename),
+			"ppm" | "pnm" => self.write_as_ppm(filename),
+			"pfm" => self.write_as_pfm(filename),
+			_ => Err(RenderError::InvalidImage(format!("unknown image format {}", filename.as_ref().display()))),
 		}
 	}
 	
+	//picks the reader by the file extension
+	pub fn load<P: AsRef<Path>>(filename: P) -> RenderResult<Texture> {
+		let extension: String = match filename.as_ref().extension() {
+			Some(ext) => ext.to_string_lossy().to_lowercase(),
+			None => String::new(),
+		};
+		match extension.as_str() {
+			"png" => Texture::load_png(filename),
+			"bmp" => Texture::load_bmp(filename),
+			"tga" => Texture::load_tga(filename),
+			"pbm" | "pgm" | "ppm" | "pnm" | "pfm" => Texture::load_pnm(filename),
+			_ => Err(RenderError::InvalidImage(format!("unknown image format {}", filename.as_ref().display()))),
+		}
+	}
+	
+	pub fn load_bmp<P: AsRef<Path>>(filename: P) -> RenderResult<Texture> {
+		let mut f = BufReader::new(File::open(filename)?);
+		read_bmp(&mut f)
+	}
+	
+	pub fn load_tga<P: AsRef<Path>>(filename: P) -> RenderResult<Texture> {
+		let mut f = BufReader::new(File::open(filename)?);
+		read_tga(&mut f)
+	}
+	
+	pub fn load_pnm<P: AsRef<Path>>(filename: P) -> RenderResult<Texture> {
+		let mut f = BufReader::new(File::open(filename)?);
+		read_pnm(&mut f)
+	}
+	
+	pub fn load_png<P: AsRef<Path>>(filename: P) -> RenderResult<Texture> {
+		let mut f = BufReader::new(File::open(filename)?);
+		read_png(&mut f)
+	}
+	
 }
 
 
@@ -1,5 +1,15 @@
 use texture::*;
+use error::*;
 use texture::filter::*;
+use texture::format::zlib::*;
+use texture::format::png::*;
+use texture::format::netpbm::*;
+use texture::format::exr::*;
+use texture::format::bmp::*;
+use texture::format::tga::*;
+use texture::format::y4m::*;
+use texture::format::gif::*;
+use color::*;
 use texture::converter::julia::*;
 use texture::converter::mandelbrot::*;
 
@@ -8,6 +18,503 @@ use math::algorithm::noise::ds::*;
 use math::algorithm::fractals::mandelbrot::*;
 use math::algorithm::fractals::julia::*;
 
+#[test]
+fn noise_texture_dimension_mismatch() {
+	let ds: DiamondSquare = DiamondSquare::new(33, 33);
+	let mut texture: Texture = Texture::new(32, 33);
+	
+	match texture.from_noise(&ds.noise) {
+		Err(RenderError::DimensionMismatch { expected_width: 33, width: 32, .. }) => {},
+		_ => panic!("noise and texture size differ"),
+	}
+}
+
+fn gradient_texture(width: u32, height: u32) -> Texture {
+	let mut texture: Texture = Texture::new(width, height);
+	for y in 0..height {
+		for x in 0..width {
+			texture.buffer[(y * width + x) as usize].set_from_rgb((x * 7 % 256) as f32, (y * 3 % 256) as f32, ((x ^ y) % 256) as f32);
+		}
+	}
+	texture
+}
+
+#[test]
+fn zlib_roundtrip() {
+	let mut data: Vec<u8> = Vec::new();
+	for i in 0..70000u32 {
+		data.push(if i % 1000 < 500 { (i % 7) as u8 } else { (i.wrapping_mul(2654435761) >> 24) as u8 });
+	}
+	
+	let compressed: Vec<u8> = zlib_compress(&data);
+	assert_eq!(compressed.len() < data.len(), true);
+	assert_eq!(zlib_decompress(&compressed, ::std::usize::MAX).unwrap(), data);
+	assert_eq!(zlib_decompress(&compressed, data.len() - 1).is_err(), true);
+	assert_eq!(zlib_decompress(&zlib_compress(&[]), 0).unwrap().len(), 0);
+}
+
+#[test]
+fn zlib_decompress_stored_and_dynamic() {
+	//stored block with "abc"
+	let stored: [u8; 14] = [0x78, 0x01, 0x01, 0x03, 0x00, 0xfc, 0xff, 0x61, 0x62, 0x63, 0x02, 0x4d, 0x01, 0x27];
+	assert_eq!(zlib_decompress(&stored, 3).unwrap(), b"abc".to_vec());
+	match zlib_decompress(&stored, 2) {
+		Err(RenderError::InvalidImage(_)) => {},
+		_ => panic!("output beyond the limit must be rejected"),
+	}
+	
+	//dynamic huffman block as written by zlib
+	let dynamic: [u8; 37] = [0x78, 0xda, 0x1d, 0x89, 0x89, 0x0d, 0x00, 0x00, 0x0c, 0x01, 0x67, 0x3d, 0xba, 
+							 0xff, 0x0c, 0x45, 0x22, 0xce, 0x83, 0x04, 0x27, 0x02, 0x71, 0x26, 0x1e, 0xc9, 
+							 0xc5, 0x9a, 0x7b, 0x2e, 0x77, 0x33, 0x7e, 0xe7, 0x4b, 0x13, 0x1c];
+	assert_eq!(zlib_decompress(&dynamic, 50).unwrap(), b"abbaadbabbabadcaabaababcbaabcaabacdbababcaacbaacac".to_vec());
+}
+
+#[test]
+fn png_roundtrip_rgb_and_rgba() {
+	let mut texture: Texture = gradient_texture(37, 19);
+	
+	let mut data: Vec<u8> = Vec::new();
+	write_png(&texture, &mut data).unwrap();
+	let loaded: Texture = read_png(&mut &data[..]).unwrap();
+	assert_eq!(loaded.width, 37);
+	assert_eq!(loaded.height, 19);
+	assert_eq!(loaded.buffer == texture.buffer, true);
+	assert_eq!(loaded.alpha.is_none(), true);
+	
+	let alpha: Vec<f32> = (0..texture.buffer.len()).map(|i| (i % 256) as f32).collect();
+	texture.alpha = Some(alpha.clone());
+	data.clear();
+	write_png(&texture, &mut data).unwrap();
+	let loaded: Texture = read_png(&mut &data[..]).unwrap();
+	assert_eq!(loaded.buffer == texture.buffer, true);
+	assert_eq!(loaded.alpha, Some(alpha));
+	
+	data[20] ^= 0xff;
+	match read_png(&mut &data[..]) {
+		Err(RenderError::InvalidImage(_)) => {},
+		_ => panic!("corrupted header must be rejected"),
+	}
+}
+
+//8 bit gray png with the given filtered rows
+fn gray_png(width: u32, height: u32, raw: &[u8]) -> Vec<u8> {
+	let mut texture_data: Vec<u8> = Vec::new();
+	texture_data.extend(&[137, 80, 78, 71, 13, 10, 26, 10]);
+	let mut ihdr: Vec<u8> = b"IHDR".to_vec();
+	ihdr.extend(&[(width >> 24) as u8, (width >> 16) as u8, (width >> 8) as u8, width as u8]);
+	ihdr.extend(&[(height >> 24) as u8, (height >> 16) as u8, (height >> 8) as u8, height as u8]);
+	ihdr.extend(&[8, 0, 0, 0, 0]);
+	let idat_data: Vec<u8> = zlib_compress(raw);
+	let mut idat: Vec<u8> = b"IDAT".to_vec();
+	idat.extend(&idat_data);
+	for chunk in [ihdr, idat, b"IEND".to_vec()].iter() {
+		let len: u32 = chunk.len() as u32 - 4;
+		let crc: u32 = crc32(chunk);
+		texture_data.extend(&[(len >> 24) as u8, (len >> 16) as u8, (len >> 8) as u8, len as u8]);
+		texture_data.extend(chunk);
+		texture_data.extend(&[(crc >> 24) as u8, (crc >> 16) as u8, (crc >> 8) as u8, crc as u8]);
+	}
+	texture_data
+}
+
+#[test]
+fn png_read_gray() {
+	//1x2 8 bit gray image with pixel values 0 and 255
+	let texture_data: Vec<u8> = gray_png(1, 2, &[0, 0, 0, 255]);
+	let loaded: Texture = read_png(&mut &texture_data[..]).unwrap();
+	assert_eq!(loaded.buffer[0] == BLACK, true);
+	assert_eq!(loaded.buffer[1] == WHITE, true);
+}
+
+#[test]
+fn png_rejects_oversized_images() {
+	//sizes are checked before the image data is inflated or the texture allocated
+	for &(width, height) in [(65536, 65536), (0xffffffff, 0xffffffff), (0x10000001, 1)].iter() {
+		match read_png(&mut &gray_png(width, height, &[0, 0])[..]) {
+			Err(RenderError::InvalidImage(_)) => {},
+			_ => panic!("{}x{} png must be rejected", width, height),
+		}
+	}
+	//image data inflating to far more than the 1x2 pixels need
+	match read_png(&mut &gray_png(1, 2, &vec![0; 1 << 20])[..]) {
+		Err(RenderError::InvalidImage(_)) => {},
+		_ => panic!("png data longer than the image must be rejected"),
+	}
+}
+
+#[test]
+fn netpbm_roundtrip() {
+	let texture: Texture = gradient_texture(29, 11);
+	
+	for format in [NetpbmFormat::PixmapAscii, NetpbmFormat::Pixmap].iter() {
+		let mut data: Vec<u8> = Vec::new();
+		write_pnm(&texture, &mut data, *format).unwrap();
+		let loaded: Texture = read_pnm(&mut &data[..]).unwrap();
+		assert_eq!(loaded.width, 29);
+		assert_eq!(loaded.height, 11);
+		assert_eq!(loaded.buffer == texture.buffer, true);
+	}
+	
+	let mut gray: Texture = Texture::new(13, 3);
+	for (i, color) in gray.buffer.iter_mut().enumerate() {
+		color.set_all(if i % 3 == 0 { 0.0 } else { 255.0 });
+	}
+	for format in [NetpbmFormat::BitmapAscii, NetpbmFormat::GraymapAscii, NetpbmFormat::Bitmap, NetpbmFormat::Graymap].iter() {
+		let mut data: Vec<u8> = Vec::new();
+		write_pnm(&gray, &mut data, *format).unwrap();
+		let loaded: Texture = read_pnm(&mut &data[..]).unwrap();
+		assert_eq!(loaded.buffer == gray.buffer, true);
+	}
+	
+	let mut data: Vec<u8> = Vec::new();
+	write_pfm(&texture, &mut data).unwrap();
+	let loaded: Texture = read_pnm(&mut &data[..]).unwrap();
+	for (a, b) in loaded.buffer.iter().zip(texture.buffer.iter()) {
+		assert_eq!((a.r - b.r).abs() < 0.001 && (a.g - b.g).abs() < 0.001 && (a.b - b.b).abs() < 0.001, true);
+	}
+}
+
+#[test]
+fn netpbm_read_ascii_and_16_bit() {
+	//bitmap digits without separators and comments in the header
+	let bitmap: &[u8] = b"P1\n# comment\n3 2\n010\n1 0 1\n";
+	let loaded: Texture = read_pnm(&mut &bitmap[..]).unwrap();
+	assert_eq!(loaded.buffer[0] == WHITE && loaded.buffer[1] == BLACK && loaded.buffer[3] == BLACK, true);
+	
+	let mut graymap: Vec<u8> = b"P5 2 1 65535\n".to_vec();
+	graymap.extend(&[0xff, 0xff, 0x00, 0x00]);
+	let loaded: Texture = read_pnm(&mut &graymap[..]).unwrap();
+	assert_eq!(loaded.buffer[0] == WHITE && loaded.buffer[1] == BLACK, true);
+	
+	match read_pnm(&mut &b"P6\n2 2\n255\n\x00\x00"[..]) {
+		Err(RenderError::InvalidImage(_)) => {},
+		_ => panic!("truncated data must be rejected"),
+	}
+	
+	//sizes from the header are checked before anything is allocated
+	let huge: [&[u8]; 4] = [b"P6\n4294967295 4294967295\n255\n\x00", b"P2\n100000 100000\n255\n0", b"P4\n4294967295 2\n\x00", b"PF\n65536 65536\n-1.0\n\x00"];
+	for data in huge.iter() {
+		match read_pnm(&mut &data[..]) {
+			Err(RenderError::InvalidImage(_)) => {},
+			_ => panic!("sizes beyond the data must be rejected"),
+		}
+	}
+}
+
+fn le_u32(data: &[u8], pos: usize) -> u32 {
+	(data[pos] as u32) | ((data[pos + 1] as u32) << 8) | ((data[pos + 2] as u32) << 16) | ((data[pos + 3] as u32) << 24)
+}
+
+#[test]
+fn pfm_raw_float_data() {
+	let depth: Vec<f32> = vec![0.25, ::std::f32::MAX, 1e-7, 3.0];
+	let mut data: Vec<u8> = Vec::new();
+	write_pfm_data(&mut data, 2, 2, 1, &depth).unwrap();
+	
+	let header: &[u8] = b"Pf\n2 2\n-1.0\n";
+	assert_eq!(&data[..header.len()], header);
+	//bottom row comes first
+	assert_eq!(le_u32(&data, header.len()), 1e-7f32.to_bits());
+	assert_eq!(le_u32(&data, header.len() + 12), ::std::f32::MAX.to_bits());
+	
+	match write_pfm_data(&mut data, 3, 2, 1, &depth) {
+		Err(RenderError::InvalidImage(_)) => {},
+		_ => panic!("data and size differ"),
+	}
+}
+
+#[test]
+fn exr_scanline_layout() {
+	let r: Vec<f32> = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+	let g: Vec<f32> = vec![0.5; 6];
+	let b: Vec<f32> = vec![-1.0, 1e9, 0.0, 0.0, 0.0, 0.0];
+	let mut data: Vec<u8> = Vec::new();
+	write_exr(&mut data, 3, 2, &[("R", &r), ("G", &g), ("B", &b)]).unwrap();
+	
+	assert_eq!(&data[..8], &[0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0]);
+	let chlist: &[u8] = b"channels\0chlist\0";
+	assert_eq!(&data[8..8 + chlist.len()], chlist);
+	//channels sorted by name
+	assert_eq!(&data[8 + chlist.len() + 4..8 + chlist.len() + 6], b"B\0");
+	
+	let line_size: usize = 8 + 3 * 3 * 4;
+	let second: usize = le_u32(&data, data.len() - line_size * 2 - 8) as usize;
+	assert_eq!(second, data.len() - line_size);
+	assert_eq!(le_u32(&data, second), 1);
+	assert_eq!(le_u32(&data, second + 4) as usize, line_size - 8);
+	//B G R of the second line
+	assert_eq!(le_u32(&data, second + 8), 0.0f32.to_bits());
+	assert_eq!(le_u32(&data, second + 8 + 12), 0.5f32.to_bits());
+	assert_eq!(le_u32(&data, second + 8 + 24), 4.0f32.to_bits());
+	
+	let first: usize = le_u32(&data, data.len() - line_size * 2 - 16) as usize;
+	assert_eq!(le_u32(&data, first + 12), 1e9f32.to_bits());
+	
+	match write_exr(&mut data, 2, 2, &[("R", &r)]) {
+		Err(RenderError::InvalidImage(_)) => {},
+		_ => panic!("data and size differ"),
+	}
+}
+
+#[test]
+fn bmp_roundtrip_rgb_and_rgba() {
+	//odd width to test the row padding
+	let mut texture: Texture = gradient_texture(13, 7);
+	
+	let mut data: Vec<u8> = Vec::new();
+	write_bmp(&texture, &mut data).unwrap();
+	assert_eq!(data.len(), 54 + 40 * 7);
+	let loaded: Texture = read_bmp(&mut &data[..]).unwrap();
+	assert_eq!(loaded.width, 13);
+	assert_eq!(loaded.height, 7);
+	assert_eq!(loaded.buffer == texture.buffer, true);
+	assert_eq!(loaded.alpha.is_none(), true);
+	
+	let alpha: Vec<f32> = (0..texture.buffer.len()).map(|i| (i * 3 % 256) as f32).collect();
+	texture.alpha = Some(alpha.clone());
+	data.clear();
+	write_bmp(&texture, &mut data).unwrap();
+	let loaded: Texture = read_bmp(&mut &data[..]).unwrap();
+	assert_eq!(loaded.buffer == texture.buffer, true);
+	assert_eq!(loaded.alpha, Some(alpha));
+}
+
+#[test]
+fn bmp_read_palette_top_down() {
+	//2x2 8 bit image with negative height and a two color palette
+	let mut data: Vec<u8> = b"BM".to_vec();
+	data.extend(&[70, 0, 0, 0, 0, 0, 0, 0, 62, 0, 0, 0]);
+	data.extend(&[40, 0, 0, 0, 2, 0, 0, 0, 0xfe, 0xff, 0xff, 0xff, 1, 0, 8, 0]);
+	data.extend(&[0; 16]);
+	data.extend(&[2, 0, 0, 0, 0, 0, 0, 0]);
+	data.extend(&[255, 0, 0, 0, 0, 0, 255, 0]);
+	data.extend(&[0, 1, 0, 0, 1, 1, 0, 0]);
+	
+	let loaded: Texture = read_bmp(&mut &data[..]).unwrap();
+	assert_eq!(loaded.buffer[0] == Color::new(0.0, 0.0, 255.0), true);
+	assert_eq!(loaded.buffer[1] == Color::new(255.0, 0.0, 0.0), true);
+	assert_eq!(loaded.buffer[3] == Color::new(255.0, 0.0, 0.0), true);
+	
+	match read_bmp(&mut &data[..60]) {
+		Err(RenderError::InvalidImage(_)) => {},
+		_ => panic!("truncated data must be rejected"),
+	}
+	
+	data[22..26].copy_from_slice(&[0, 0, 0, 0x80]);
+	match read_bmp(&mut &data[..]) {
+		Err(RenderError::InvalidImage(_)) => {},
+		_ => panic!("a height of i32::MIN must be rejected"),
+	}
+}
+
+#[test]
+fn tga_roundtrip_raw_and_rle() {
+	let mut texture: Texture = gradient_texture(150, 5);
+	for x in 20..90 {
+		texture.buffer[x].set_from_rgb(1.0, 2.0, 3.0);
+	}
+	
+	for rle in [false, true].iter() {
+		for with_alpha in [false, true].iter() {
+			texture.alpha = if *with_alpha { Some((0..texture.buffer.len()).map(|i| (i % 2 * 255) as f32).collect()) } else { None };
+			let mut data: Vec<u8> = Vec::new();
+			write_tga(&texture, &mut data, *rle).unwrap();
+			let loaded: Texture = read_tga(&mut &data[..]).unwrap();
+			assert_eq!(loaded.width, 150);
+			assert_eq!(loaded.buffer == texture.buffer, true);
+			assert_eq!(loaded.alpha == texture.alpha, true);
+		}
+	}
+	
+	let flat: Texture = Texture::new(300, 300);
+	let mut data: Vec<u8> = Vec::new();
+	write_tga(&flat, &mut data, true).unwrap();
+	assert_eq!(data.len(), 18 + 300 * 3 * 4 + 26);
+}
+
+#[test]
+fn tga_read_bottom_up_rle_across_lines() {
+	//3x2 24 bit rle image with bottom left origin, the first run covers both lines partially
+	let mut data: Vec<u8> = vec![0, 0, 10, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 2, 0, 24, 0];
+	data.extend(&[0x83, 0, 0, 255]);
+	data.extend(&[0x01, 0, 255, 0, 255, 0, 0]);
+	
+	let loaded: Texture = read_tga(&mut &data[..]).unwrap();
+	let red: Color = Color::new(255.0, 0.0, 0.0);
+	let green: Color = Color::new(0.0, 255.0, 0.0);
+	assert_eq!(loaded.buffer[3] == red && loaded.buffer[5] == red && loaded.buffer[0] == red, true);
+	assert_eq!(loaded.buffer[1] == green, true);
+	assert_eq!(loaded.buffer[2] == Color::new(0.0, 0.0, 255.0), true);
+	assert_eq!(loaded.alpha.is_none(), true);
+}
+
+//reference decoder for the lzw streams of gif
+fn lzw_decode(data: &[u8], min_code_size: u32) -> Vec<u8> {
+	let clear: usize = 1 << min_code_size;
+	let initial: Vec<Vec<u8>> = (0..clear + 2).map(|i| vec![i as u8]).collect();
+	let mut table: Vec<Vec<u8>> = initial.clone();
+	let mut size: u32 = min_code_size + 1;
+	let mut prev: Option<Vec<u8>> = None;
+	let mut out: Vec<u8> = Vec::new();
+	let mut bit: usize = 0;
+	while bit + size as usize <= data.len() * 8 {
+		let mut code: usize = 0;
+		for i in 0..size as usize {
+			code |= (((data[(bit + i) / 8] >> ((bit + i) % 8)) & 1) as usize) << i;
+		}
+		bit += size as usize;
+		if code == clear {
+			table = initial.clone();
+			size = min_code_size + 1;
+			prev = None;
+			continue;
+		}
+		if code == clear + 1 {
+			break;
+		}
+		let entry: Vec<u8> = match (table.get(code), &prev) {
+			(Some(entry), _) => entry.clone(),
+			(None, &Some(ref p)) if code == table.len() => { let mut e: Vec<u8> = p.clone(); e.push(p[0]); e },
+			_ => panic!("invalid code {}", code),
+		};
+		out.extend(&entry);
+		if let Some(mut p) = prev.take() {
+			if table.len() < 4096 {
+				p.push(entry[0]);
+				table.push(p);
+			}
+		}
+		prev = Some(entry);
+		if table.len() == 1 << size && size < 12 {
+			size += 1;
+		}
+	}
+	out
+}
+
+#[test]
+fn gif_lzw_palette_and_frames() {
+	//long inputs fill the code table and restart it with a clear code
+	let mut seed: u32 = 7;
+	let noise: Vec<u8> = (0..40000).map(|_| { seed = seed.wrapping_mul(1103515245).wrapping_add(12345); (seed >> 16) as u8 }).collect();
+	assert_eq!(lzw_decode(&lzw_encode(&noise, 8), 8), noise);
+	let pattern: Vec<u8> = (0..20000u32).map(|i| (i * i % 7 % 4) as u8).collect();
+	assert_eq!(lzw_decode(&lzw_encode(&pattern, 2), 2), pattern);
+	assert_eq!(lzw_decode(&lzw_encode(&[], 2), 2).len(), 0);
+	
+	//a flat gray between black and white dithers to the right average
+	let mut gray: Texture = Texture::new(32, 32);
+	for color in gray.buffer.iter_mut() {
+		color.set_from_rgb(100.0, 100.0, 100.0);
+	}
+	let palette: Vec<Color> = vec![BLACK.clone(), WHITE.clone()];
+	assert_eq!(map_to_palette(&gray, &palette, false).iter().all(|&i| i == 0), true);
+	let dithered: Vec<u8> = map_to_palette(&gray, &palette, true);
+	let mean: f32 = dithered.iter().map(|&i| i as f32 * 255.0).sum::<f32>() / dithered.len() as f32;
+	assert_eq!((mean - 100.0).abs() < 4.0, true);
+	
+	//more than 256 colors are cut down, few colors are kept exactly
+	let mut gradient: Texture = Texture::new(64, 64);
+	for j in 0..64 {
+		for i in 0..64 {
+			gradient.buffer[j * 64 + i].set_from_rgb(i as f32 * 4.0, j as f32 * 4.0, 128.0);
+		}
+	}
+	let cut: Vec<Color> = median_cut(&gradient, 256);
+	assert_eq!(cut.len(), 256);
+	let indices: Vec<u8> = map_to_palette(&gradient, &cut, false);
+	for (color, &index) in gradient.buffer.iter().zip(indices.iter()) {
+		let p: &Color = &cut[index as usize];
+		assert_eq!((p.r - color.r).abs() <= 8.0 && (p.g - color.g).abs() <= 8.0 && p.b == 128.0, true);
+	}
+	
+	let mut texture: Texture = Texture::new(4, 3);
+	texture.buffer[1].set_from_rgb(255.0, 0.0, 0.0);
+	texture.buffer[6].set_from_rgb(0.0, 0.0, 255.0);
+	let mut writer: GifWriter<Vec<u8>> = GifWriter::new(Vec::new(), 4, 3, Some(0)).unwrap();
+	writer.delay = 10;
+	writer.write_frame(&texture).unwrap();
+	writer.write_frame(&gradient).err().unwrap();
+	texture.buffer[1].set_from_rgb(0.0, 255.0, 0.0);
+	writer.write_frame(&texture).unwrap();
+	writer.finish().unwrap();
+	assert_eq!(writer.frames, 2);
+	match writer.write_frame(&texture) {
+		Err(RenderError::InvalidImage(_)) => {},
+		_ => panic!("frames after the trailer must be rejected"),
+	}
+	
+	let data: Vec<u8> = writer.into_inner();
+	assert_eq!(&data[..13], &[b'G', b'I', b'F', b'8', b'9', b'a', 4, 0, 3, 0, 0x70, 0, 0]);
+	assert_eq!(&data[13..16], &[0x21, 0xff, 11]);
+	assert_eq!(&data[16..27], b"NETSCAPE2.0");
+	assert_eq!(&data[27..32], &[3, 1, 0, 0, 0]);
+	let mut pos: usize = 32;
+	for frame in 0..2 {
+		assert_eq!(&data[pos..pos + 8], &[0x21, 0xf9, 4, 0, 10, 0, 0, 0]);
+		assert_eq!(&data[pos + 8..pos + 18], &[0x2c, 0, 0, 0, 0, 4, 0, 3, 0, 0x81]);
+		let table: &[u8] = &data[pos + 18..pos + 30];
+		pos += 30;
+		let min_code_size: u32 = data[pos] as u32;
+		assert_eq!(min_code_size, 2);
+		pos += 1;
+		let mut stream: Vec<u8> = Vec::new();
+		while data[pos] != 0 {
+			let len: usize = data[pos] as usize;
+			stream.extend(&data[pos + 1..pos + 1 + len]);
+			pos += 1 + len;
+		}
+		pos += 1;
+		let pixels: Vec<u8> = lzw_decode(&stream, min_code_size);
+		assert_eq!(pixels.len(), 12);
+		for (i, &index) in pixels.iter().enumerate() {
+			let rgb: &[u8] = &table[index as usize * 3..index as usize * 3 + 3];
+			let expected: &Color = &texture.buffer[i];
+			let expected: Color = if frame == 0 && i == 1 { Color::new(255.0, 0.0, 0.0) } else { expected.clone() };
+			assert_eq!(Color::new(rgb[0] as f32, rgb[1] as f32, rgb[2] as f32) == expected, true);
+		}
+	}
+	assert_eq!(&data[pos..], &[0x3b]);
+}
+
+#[test]
+fn y4m_header_and_420_planes() {
+	assert_eq!(rgb_to_yuv(255.0, 255.0, 255.0), (235, 128, 128));
+	assert_eq!(rgb_to_yuv(-10.0, 0.0, 0.0), (16, 128, 128));
+	assert_eq!(rgb_to_yuv(255.0, 0.0, 0.0), (81, 90, 240));
+	
+	//3x3 frame, the chroma of the right column and bottom row comes from the edge pixels only
+	let mut texture: Texture = Texture::new(3, 3);
+	for color in texture.buffer.iter_mut() {
+		color.set_from_rgb(255.0, 0.0, 0.0);
+	}
+	for i in 0..2 {
+		for j in 0..2 {
+			texture.buffer[j * 3 + i].set_from_rgb(255.0, 255.0, 255.0);
+		}
+	}
+	let mut writer: Y4mWriter<Vec<u8>> = Y4mWriter::new(Vec::new(), 3, 3, 25, 1).unwrap();
+	writer.write_frame(&texture).unwrap();
+	writer.write_frame(&texture).unwrap();
+	assert_eq!(writer.frames, 2);
+	match writer.write_frame(&Texture::new(2, 3)) {
+		Err(RenderError::DimensionMismatch { .. }) => {},
+		_ => panic!("frames of another size must be rejected"),
+	}
+	
+	let data: Vec<u8> = writer.into_inner();
+	let header: &[u8] = b"YUV4MPEG2 W3 H3 F25:1 Ip A1:1 C420jpeg\n";
+	assert_eq!(&data[..header.len()], header);
+	let frame: &[u8] = &data[header.len()..];
+	assert_eq!(frame.len(), 2 * (6 + 9 + 4 + 4));
+	assert_eq!(&frame[..6], b"FRAME\n");
+	assert_eq!(&frame[6..15], &[235, 235, 81, 235, 235, 81, 81, 81, 81]);
+	assert_eq!(&frame[15..19], &[128, 90, 90, 90]);
+	assert_eq!(&frame[19..23], &[128, 240, 240, 240]);
+	assert_eq!(&frame[..23], &frame[23..]);
+}
+
 #[test]
 #[cfg(any(feature = "all", feature = "ds"))]
 fn noise_texture_ds() {	
@@ -23,10 +530,10 @@ fn noise_texture_ds() {
 	ds.create();
 	
 	let mut texture: Texture =  Texture::new(w as u32, h as u32);
-	texture.from_noise(&ds.noise);
+	texture.from_noise(&ds.noise).unwrap();
 	
 	if cfg!(feature = "output") {
-		texture.write_as_ppm("target/ds_noise.ppm");
+		texture.write_as_ppm("target/ds_noise.ppm").unwrap();
 	}
 	
 	if cfg!(feature = "filter_arith") {
@@ -34,17 +541,17 @@ fn noise_texture_ds() {
 		let mut tex_arith = texture.clone();
 		tex_arith.filter(&texfilter);
 		if cfg!(feature = "output") {
-			tex_arith.write_as_ppm("target/sd_noise_arith_filter.ppm");
+			tex_arith.write_as_ppm("target/sd_noise_arith_filter.ppm").unwrap();
 		}
 	}
 	
 	if cfg!(feature = "filter_gauss") {
 		let texfilter = GaussFilter::new(2, 1.0);
-
+		
 		let mut tex_gauss = texture.clone();
 		tex_gauss.filter(&texfilter);
 		if cfg!(feature = "output") {
-			tex_gauss.write_as_ppm("target/sd_noise_gauss_filter.ppm");
+			tex_gauss.write_as_ppm("target/sd_noise_gauss_filter.ppm").unwrap();
 		}
 	}
 	
@@ -65,10 +572,10 @@ fn noise_texture_md() {
 	md.create();
 	
 	let mut texture: Texture =  Texture::new(w as u32, h as u32);
-	texture.from_noise(&md.noise);
+	texture.from_noise(&md.noise).unwrap();
 	
 	if cfg!(feature = "output") {
-		texture.write_as_ppm("target/md_noise.ppm");
+		texture.write_as_ppm("target/md_noise.ppm").unwrap();
 	}
 	
 	if cfg!(feature = "filter_arith") {
@@ -76,17 +583,17 @@ fn noise_texture_md() {
 		let mut tex_arith = texture.clone();
 		tex_arith.filter(&texfilter);
 		if cfg!(feature = "output") {
-			tex_arith.write_as_ppm("target/md_noise_arith_filter.ppm");
+			tex_arith.write_as_ppm("target/md_noise_arith_filter.ppm").unwrap();
 		}
 	}
 	
 	if cfg!(feature = "filter_gauss") {
 		let texfilter = GaussFilter::new(2, 1.0);
-
+		
 		let mut tex_gauss = texture.clone();
 		tex_gauss.filter(&texfilter);
 		if cfg!(feature = "output") {
-			tex_gauss.write_as_ppm("target/md_noise_gauss_filter.ppm");
+			tex_gauss.write_as_ppm("target/md_noise_gauss_filter.ppm").unwrap();
 		}
 	}
 	
@@ -105,10 +612,10 @@ fn fractal_texture_mandelbrot() {
 	mb.create();
 	
 	let mut texture: Texture =  Texture::new(mb.width, mb.height);
-	texture.from_mandelbrot(&mb, mandelbrot_color_line_int_rgb);
+	texture.from_mandelbrot(&mb, mandelbrot_color_line_int_rgb).unwrap();
 	
 	if cfg!(feature = "output") {
-		texture.write_as_ppm("target/mandelbrot.ppm");
+		texture.write_as_ppm("target/mandelbrot.ppm").unwrap();
 	}
 	
 	if cfg!(feature = "filter_arith") {
@@ -116,17 +623,17 @@ fn fractal_texture_mandelbrot() {
 		let mut tex_arith = texture.clone();
 		tex_arith.filter(&texfilter);
 		if cfg!(feature = "output") {
-			tex_arith.write_as_ppm("target/mandelbrot_arith_filter.ppm");
+			tex_arith.write_as_ppm("target/mandelbrot_arith_filter.ppm").unwrap();
 		}
 	}
 	
 	if cfg!(feature = "filter_gauss") {
 		let texfilter = GaussFilter::new(2, 1.0);
-
+		
 		let mut tex_gauss = texture.clone();
 		tex_gauss.filter(&texfilter);
 		if cfg!(feature = "output") {
-			tex_gauss.write_as_ppm("target/mandelbrot_gauss_filter.ppm");
+			tex_gauss.write_as_ppm("target/mandelbrot_gauss_filter.ppm").unwrap();
 		}
 	}
 	
@@ -147,10 +654,10 @@ fn fractal_texture_julia() {
 	julia.create();
 	
 	let mut texture: Texture =  Texture::new(julia.width, julia.height);
-	texture.from_julia(&julia, julia_color_line_int_8_bit);
+	texture.from_julia(&julia, julia_color_line_int_8_bit).unwrap();
 	
 	if cfg!(feature = "output") {
-		texture.write_as_ppm("target/julia.ppm");
+		texture.write_as_ppm("target/julia.ppm").unwrap();
 	}
 	
 	if cfg!(feature = "filter_arith") {
@@ -158,17 +665,17 @@ fn fractal_texture_julia() {
 		let mut tex_arith = texture.clone();
 		tex_arith.filter(&texfilter);
 		if cfg!(feature = "output") {
-			tex_arith.write_as_ppm("target/julia_arith_filter.ppm");
+			tex_arith.write_as_ppm("target/julia_arith_filter.ppm").unwrap();
 		}
 	}
 	
 	if cfg!(feature = "filter_gauss") {
 		let texfilter = GaussFilter::new(2, 1.0);
-
+		
 		let mut tex_gauss = texture.clone();
 		tex_gauss.filter(&texfilter);
 		if cfg!(feature = "output") {
-			tex_gauss.write_as_ppm("target/julia_gauss_filter.ppm");
+			tex_gauss.write_as_ppm("target/julia_gauss_filter.ppm").unwrap();
 		}
 	}
 	